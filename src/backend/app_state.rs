@@ -5,6 +5,7 @@ use std::time::Instant;
 use rhai::Engine;
 use tokio::sync::RwLock;
 use tokio::sync::mpsc::{Sender, error::TrySendError};
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use alloy::primitives::Address;
@@ -53,6 +54,20 @@ pub type PendingBuilderFeeApprovalStore = Arc<RwLock<HashMap<String, PendingBuil
 /// Users with an in-flight bot startup.
 pub type BotStartupStore = Arc<RwLock<HashSet<String>>>;
 
+/// The backtest a user has in flight.
+#[derive(Debug, Clone)]
+pub struct ActiveBacktest {
+    pub run_id: String,
+    /// Stops the job, whichever runner it is, at its next cancellation check.
+    pub cancel: CancellationToken,
+}
+
+/// pubkey → in-flight backtest (one per user).
+pub type ActiveBacktests = Arc<RwLock<HashMap<String, ActiveBacktest>>>;
+
+/// pubkey → open step sessions, each holding a loaded run in memory.
+pub type StepSessions = Arc<std::sync::Mutex<HashMap<String, usize>>>;
+
 /// A compiled strategy with its metadata, ready to be dispatched to a Bot/Market.
 #[derive(Debug, Clone)]
 pub struct CachedStrategy {
@@ -72,7 +87,8 @@ pub struct AppState {
     pub rhai_engine: Arc<Engine>,
     pub strategy_cache: StrategyCache,
     pub candle_store: Arc<CandleStore>,
-    pub active_backtests: Arc<RwLock<HashSet<String>>>,
+    pub active_backtests: ActiveBacktests,
+    pub step_sessions: StepSessions,
     pub bot_startups: BotStartupStore,
     pub jwt_secret: String,
     pub encryption_key: [u8; 32],
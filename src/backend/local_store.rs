@@ -8,9 +8,13 @@ use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
-use super::storage_models::{StrategyRow, StrategySummary, TradeRow};
+use super::storage_models::{BacktestRunRow, StrategyRow, StrategySummary, TradeRow};
+use crate::backtest::{BacktestCheckpoint, BacktestResult, CheckpointStatus};
 
 const STORE_VERSION: u32 = 1;
+/// Oldest runs beyond this count are evicted when a new run is saved.
+pub(crate) const MAX_BACKTEST_RUNS_PER_WALLET: usize = 200;
+pub(crate) const MAX_BACKTEST_RUN_ID_LEN: usize = 96;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 struct WalletRecord {
@@ -63,6 +67,33 @@ impl Default for TradeFile {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct BacktestIndexFile {
+    version: u32,
+    runs: Vec<BacktestRunRow>,
+}
+
+impl Default for BacktestIndexFile {
+    fn default() -> Self {
+        Self {
+            version: STORE_VERSION,
+            runs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BacktestResultFile {
+    version: u32,
+    result: BacktestResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BacktestCheckpointFile {
+    version: u32,
+    checkpoint: BacktestCheckpoint,
+}
+
 pub struct LocalStore {
     root: PathBuf,
     encryption_key: [u8; 32],
@@ -77,6 +108,10 @@ impl LocalStore {
             .map_err(|e| format!("create local storage directory: {e}"))?;
         set_dir_permissions(&root).await?;
         set_dir_permissions(&root.join("trades")).await?;
+        tokio::fs::create_dir_all(root.join("backtests"))
+            .await
+            .map_err(|e| format!("create backtest storage directory: {e}"))?;
+        set_dir_permissions(&root.join("backtests")).await?;
 
         let encryption_key = load_or_create_master_key(&root.join("master.key")).await?;
         let store = Self {
@@ -101,6 +136,7 @@ impl LocalStore {
                 .await?
                 .version,
         )?;
+        store.mark_interrupted_backtest_checkpoints().await?;
         Ok(store)
     }
 
@@ -255,6 +291,259 @@ impl LocalStore {
             .collect())
     }
 
+    pub async fn save_backtest_run(
+        &self,
+        row: BacktestRunRow,
+        result: &BacktestResult,
+    ) -> Result<(), String> {
+        let _guard = self.io_lock.lock().await;
+        let dir = self.backtest_dir(&row.pubkey)?;
+        if !dir.exists() {
+            tokio::fs::create_dir_all(&dir)
+                .await
+                .map_err(|e| format!("create {}: {e}", dir.display()))?;
+            set_dir_permissions(&dir).await?;
+        }
+
+        let result_path = backtest_result_path(&dir, &row.id)?;
+        write_json_atomic(
+            &result_path,
+            &BacktestResultFile {
+                version: STORE_VERSION,
+                result: result.clone(),
+            },
+        )
+        .await?;
+
+        let index_path = dir.join("index.json");
+        let mut data = if index_path.exists() {
+            read_json::<BacktestIndexFile>(&index_path).await?
+        } else {
+            BacktestIndexFile::default()
+        };
+        validate_version(data.version)?;
+        data.runs.retain(|run| run.id != row.id);
+        data.runs.push(row);
+        data.runs
+            .sort_by_key(|run| std::cmp::Reverse(run.finished_at));
+
+        let evicted = data
+            .runs
+            .split_off(data.runs.len().min(MAX_BACKTEST_RUNS_PER_WALLET));
+        write_json_atomic(&index_path, &data).await?;
+
+        for run in evicted {
+            let path = backtest_result_path(&dir, &run.id)?;
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                log::warn!("failed to remove evicted backtest {}: {e}", path.display());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn list_backtest_runs(
+        &self,
+        pubkey: &str,
+        asset: Option<&str>,
+        strategy_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<BacktestRunRow>, String> {
+        let _guard = self.io_lock.lock().await;
+        let index_path = self.backtest_dir(pubkey)?.join("index.json");
+        if !index_path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut data: BacktestIndexFile = read_json(&index_path).await?;
+        validate_version(data.version)?;
+        data.runs.retain(|run| {
+            asset.is_none_or(|asset| run.asset.eq_ignore_ascii_case(asset))
+                && strategy_id.is_none_or(|id| run.strategy_id == id)
+        });
+        data.runs
+            .sort_by_key(|run| std::cmp::Reverse(run.finished_at));
+        Ok(data
+            .runs
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    pub async fn backtest_result(
+        &self,
+        pubkey: &str,
+        run_id: &str,
+    ) -> Result<Option<BacktestResult>, String> {
+        let _guard = self.io_lock.lock().await;
+        let path = backtest_result_path(&self.backtest_dir(pubkey)?, run_id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data: BacktestResultFile = read_json(&path).await?;
+        validate_version(data.version)?;
+        Ok(Some(data.result))
+    }
+
+    pub async fn delete_backtest_run(&self, pubkey: &str, run_id: &str) -> Result<bool, String> {
+        let _guard = self.io_lock.lock().await;
+        let dir = self.backtest_dir(pubkey)?;
+        let index_path = dir.join("index.json");
+        if !index_path.exists() {
+            return Ok(false);
+        }
+        let mut data: BacktestIndexFile = read_json(&index_path).await?;
+        validate_version(data.version)?;
+        let old_len = data.runs.len();
+        data.runs.retain(|run| run.id != run_id);
+        if data.runs.len() == old_len {
+            return Ok(false);
+        }
+        write_json_atomic(&index_path, &data).await?;
+
+        let path = backtest_result_path(&dir, run_id)?;
+        if path.exists() {
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|e| format!("remove {}: {e}", path.display()))?;
+        }
+        Ok(true)
+    }
+
+    pub async fn save_backtest_checkpoint(
+        &self,
+        pubkey: &str,
+        checkpoint: &BacktestCheckpoint,
+    ) -> Result<(), String> {
+        let _guard = self.io_lock.lock().await;
+        let dir = self.backtest_dir(pubkey)?.join("checkpoints");
+        if !dir.exists() {
+            tokio::fs::create_dir_all(&dir)
+                .await
+                .map_err(|e| format!("create {}: {e}", dir.display()))?;
+            set_dir_permissions(&dir).await?;
+        }
+        write_json_atomic(
+            &backtest_result_path(&dir, &checkpoint.run_id)?,
+            &BacktestCheckpointFile {
+                version: STORE_VERSION,
+                checkpoint: checkpoint.clone(),
+            },
+        )
+        .await
+    }
+
+    pub async fn backtest_checkpoint(
+        &self,
+        pubkey: &str,
+        run_id: &str,
+    ) -> Result<Option<BacktestCheckpoint>, String> {
+        let _guard = self.io_lock.lock().await;
+        let dir = self.backtest_dir(pubkey)?.join("checkpoints");
+        let path = backtest_result_path(&dir, run_id)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data: BacktestCheckpointFile = read_json(&path).await?;
+        validate_version(data.version)?;
+        Ok(Some(data.checkpoint))
+    }
+
+    pub async fn list_backtest_checkpoints(
+        &self,
+        pubkey: &str,
+    ) -> Result<Vec<BacktestCheckpoint>, String> {
+        let _guard = self.io_lock.lock().await;
+        let dir = self.backtest_dir(pubkey)?.join("checkpoints");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries = tokio::fs::read_dir(&dir)
+            .await
+            .map_err(|e| format!("read {}: {e}", dir.display()))?;
+        let mut out = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("read {}: {e}", dir.display()))?
+        {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match read_json::<BacktestCheckpointFile>(&path).await {
+                Ok(data) if data.version == STORE_VERSION => out.push(data.checkpoint),
+                Ok(data) => log::warn!(
+                    "skipping checkpoint {} with version {}",
+                    path.display(),
+                    data.version
+                ),
+                Err(e) => log::warn!("skipping unreadable checkpoint: {e}"),
+            }
+        }
+        out.sort_by_key(|checkpoint| std::cmp::Reverse(checkpoint.updated_at));
+        Ok(out)
+    }
+
+    pub async fn delete_backtest_checkpoint(
+        &self,
+        pubkey: &str,
+        run_id: &str,
+    ) -> Result<bool, String> {
+        let _guard = self.io_lock.lock().await;
+        let dir = self.backtest_dir(pubkey)?.join("checkpoints");
+        let path = backtest_result_path(&dir, run_id)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(|e| format!("remove {}: {e}", path.display()))?;
+        Ok(true)
+    }
+
+    /// No job runs before the store opens, so a checkpoint still `Running` was
+    /// left by a process that stopped mid-run.
+    async fn mark_interrupted_backtest_checkpoints(&self) -> Result<(), String> {
+        let _guard = self.io_lock.lock().await;
+        let root = self.root.join("backtests");
+        let mut wallets = tokio::fs::read_dir(&root)
+            .await
+            .map_err(|e| format!("read {}: {e}", root.display()))?;
+        while let Some(wallet) = wallets
+            .next_entry()
+            .await
+            .map_err(|e| format!("read {}: {e}", root.display()))?
+        {
+            let dir = wallet.path().join("checkpoints");
+            if !dir.is_dir() {
+                continue;
+            }
+            let mut entries = tokio::fs::read_dir(&dir)
+                .await
+                .map_err(|e| format!("read {}: {e}", dir.display()))?;
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| format!("read {}: {e}", dir.display()))?
+            {
+                let path = entry.path();
+                if path.extension().is_none_or(|ext| ext != "json") {
+                    continue;
+                }
+                match read_json::<BacktestCheckpointFile>(&path).await {
+                    Ok(mut data) if data.checkpoint.status == CheckpointStatus::Running => {
+                        data.checkpoint.status = CheckpointStatus::Interrupted;
+                        write_json_atomic(&path, &data).await?;
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("skipping unreadable checkpoint: {e}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn wallets_path(&self) -> PathBuf {
         self.root.join("wallets.json")
     }
@@ -264,14 +553,15 @@ impl LocalStore {
     }
 
     fn trade_path(&self, pubkey: &str) -> Result<PathBuf, String> {
-        let key = pubkey.strip_prefix("0x").unwrap_or(pubkey);
-        if key.is_empty() || !key.bytes().all(|byte| byte.is_ascii_hexdigit()) {
-            return Err("invalid wallet key for local trade storage".to_string());
-        }
-        Ok(self
-            .root
-            .join("trades")
-            .join(format!("{}.json", key.to_ascii_lowercase())))
+        let key = wallet_dir_name(pubkey)
+            .ok_or_else(|| "invalid wallet key for local trade storage".to_string())?;
+        Ok(self.root.join("trades").join(format!("{key}.json")))
+    }
+
+    fn backtest_dir(&self, pubkey: &str) -> Result<PathBuf, String> {
+        let key = wallet_dir_name(pubkey)
+            .ok_or_else(|| "invalid wallet key for local backtest storage".to_string())?;
+        Ok(self.root.join("backtests").join(key))
     }
 
     async fn ensure_file<T>(&self, path: &Path) -> Result<(), String>
@@ -285,6 +575,22 @@ impl LocalStore {
     }
 }
 
+fn wallet_dir_name(pubkey: &str) -> Option<String> {
+    let key = pubkey.strip_prefix("0x").unwrap_or(pubkey);
+    if key.is_empty() || !key.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(key.to_ascii_lowercase())
+}
+
+/// Run ids are client-supplied, so they are hex-encoded rather than used as file names.
+fn backtest_result_path(dir: &Path, run_id: &str) -> Result<PathBuf, String> {
+    if run_id.is_empty() || run_id.len() > MAX_BACKTEST_RUN_ID_LEN {
+        return Err("invalid backtest run id for local storage".to_string());
+    }
+    Ok(dir.join(format!("{}.json", hex::encode(run_id))))
+}
+
 async fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
     let bytes = tokio::fs::read(path)
         .await
@@ -390,6 +696,83 @@ mod tests {
         }
     }
 
+    fn test_backtest_result(run_id: &str, asset: &str, finished_at: u64) -> BacktestResult {
+        use crate::TimeFrame;
+        use crate::backtest::{BacktestConfig, BacktestSummary, DataSource};
+
+        BacktestResult {
+            run_id: run_id.to_string(),
+            started_at: finished_at - 1,
+            finished_at,
+            candles_loaded: 0,
+            candles_processed: 0,
+            config: BacktestConfig {
+                asset: asset.to_string(),
+                source: DataSource::default(),
+                strategy_id: Uuid::nil(),
+                resolution: TimeFrame::Hour1,
+                margin: 1_000.0,
+                lev: 1,
+                taker_fee_bps: 0,
+                maker_fee_bps: 0,
+                funding_rate_bps_per_8h: 0.0,
+                historical_funding: false,
+                start_time: 0,
+                end_time: 1,
+                snapshot_interval_candles: 0,
+                max_equity_points: 2000,
+                max_snapshots: 500,
+                max_script_logs: 1000,
+                fill_model: Default::default(),
+                intrabar_resolution: false,
+            },
+            summary: BacktestSummary {
+                initial_equity: 1_000.0,
+                final_equity: 1_000.0,
+                net_pnl: 0.0,
+                return_pct: 0.0,
+                max_drawdown_abs: 0.0,
+                max_drawdown_pct: 0.0,
+                total_trades: 0,
+                wins: 0,
+                losses: 0,
+                win_rate_pct: 0.0,
+                gross_profit: 0.0,
+                gross_loss: 0.0,
+                avg_win: 0.0,
+                avg_loss: 0.0,
+                profit_factor: None,
+                expectancy: 0.0,
+                sharpe_ratio: None,
+                monte_carlo: None,
+                slippage_cost: 0.0,
+                intrabar_resolved_bars: 0,
+                sortino_ratio: None,
+                calmar_ratio: None,
+                time_in_market_pct: 0.0,
+                avg_trade_duration_ms: 0,
+                median_trade_duration_ms: 0,
+                longest_win_streak: 0,
+                longest_loss_streak: 0,
+                avg_mae_pct: 0.0,
+                avg_mfe_pct: 0.0,
+                trade_excursions: Vec::new(),
+                monthly_returns: Vec::new(),
+            },
+            trades: Vec::new(),
+            equity_curve: Vec::new(),
+            snapshots: Vec::new(),
+            benchmark: None,
+            script_logs: Vec::new(),
+            script_logs_dropped: 0,
+        }
+    }
+
+    async fn save_test_run(store: &LocalStore, wallet: &str, result: &BacktestResult) {
+        let row = BacktestRunRow::from_result(wallet, "shared".to_string(), result);
+        store.save_backtest_run(row, result).await.unwrap();
+    }
+
     async fn test_store() -> (PathBuf, LocalStore) {
         let root = std::env::temp_dir().join(format!("kwant-store-test-{}", Uuid::new_v4()));
         let store = LocalStore::open(&root).await.expect("store should open");
@@ -474,4 +857,207 @@ mod tests {
         );
         tokio::fs::remove_dir_all(root).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn backtest_runs_are_wallet_scoped_filtered_and_deletable() {
+        let (root, store) = test_store().await;
+        save_test_run(
+            &store,
+            "0xaaaa",
+            &test_backtest_result("bt-BTC-1", "BTC", 10),
+        )
+        .await;
+        save_test_run(
+            &store,
+            "0xaaaa",
+            &test_backtest_result("bt-ETH-2", "ETH", 20),
+        )
+        .await;
+        save_test_run(
+            &store,
+            "0xbbbb",
+            &test_backtest_result("bt-BTC-3", "BTC", 30),
+        )
+        .await;
+
+        let runs = store
+            .list_backtest_runs("0xaaaa", None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            runs.iter().map(|run| run.id.as_str()).collect::<Vec<_>>(),
+            vec!["bt-ETH-2", "bt-BTC-1"]
+        );
+        let btc = store
+            .list_backtest_runs("0xaaaa", Some("btc"), Some(Uuid::nil()), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(btc.len(), 1);
+        assert!(
+            store
+                .list_backtest_runs("0xaaaa", None, Some(Uuid::new_v4()), 10, 0)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let stored = store.backtest_result("0xaaaa", "bt-BTC-1").await.unwrap();
+        assert_eq!(stored.unwrap().config.asset, "BTC");
+        assert!(
+            store
+                .backtest_result("0xbbbb", "bt-BTC-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(
+            store
+                .delete_backtest_run("0xaaaa", "bt-BTC-1")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .delete_backtest_run("0xaaaa", "bt-BTC-1")
+                .await
+                .unwrap()
+        );
+        assert!(
+            store
+                .backtest_result("0xaaaa", "bt-BTC-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    fn test_checkpoint(
+        run_id: &str,
+        status: CheckpointStatus,
+        updated_at: u64,
+    ) -> BacktestCheckpoint {
+        use crate::backtest::BacktestRunRequest;
+
+        BacktestCheckpoint {
+            run_id: run_id.to_string(),
+            request: BacktestRunRequest {
+                run_id: Some(run_id.to_string()),
+                config: test_backtest_result(run_id, "BTC", 1).config,
+                warmup_candles: 100,
+                monte_carlo: None,
+            },
+            status,
+            processed: 42,
+            total: 100,
+            started_at: 1,
+            updated_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn backtest_checkpoints_round_trip_per_wallet() {
+        let (root, store) = test_store().await;
+        for (run_id, updated_at) in [("bt-BTC-1", 10), ("bt-BTC-2", 20)] {
+            let checkpoint = test_checkpoint(run_id, CheckpointStatus::Cancelled, updated_at);
+            store
+                .save_backtest_checkpoint("0xaaaa", &checkpoint)
+                .await
+                .unwrap();
+        }
+
+        let listed = store.list_backtest_checkpoints("0xaaaa").await.unwrap();
+        assert_eq!(
+            listed.iter().map(|c| c.run_id.as_str()).collect::<Vec<_>>(),
+            vec!["bt-BTC-2", "bt-BTC-1"]
+        );
+        let loaded = store
+            .backtest_checkpoint("0xaaaa", "bt-BTC-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.processed, 42);
+        assert!(
+            store
+                .backtest_checkpoint("0xbbbb", "bt-BTC-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(
+            store
+                .delete_backtest_checkpoint("0xaaaa", "bt-BTC-1")
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            store
+                .list_backtest_checkpoints("0xaaaa")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reopening_the_store_marks_running_checkpoints_interrupted() {
+        let (root, store) = test_store().await;
+        for (run_id, status) in [
+            ("bt-BTC-1", CheckpointStatus::Running),
+            ("bt-BTC-2", CheckpointStatus::Cancelled),
+        ] {
+            store
+                .save_backtest_checkpoint("0xaaaa", &test_checkpoint(run_id, status, 1))
+                .await
+                .unwrap();
+        }
+        drop(store);
+
+        let reopened = LocalStore::open(&root).await.unwrap();
+        for (run_id, expected) in [
+            ("bt-BTC-1", CheckpointStatus::Interrupted),
+            ("bt-BTC-2", CheckpointStatus::Cancelled),
+        ] {
+            let checkpoint = reopened
+                .backtest_checkpoint("0xaaaa", run_id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(checkpoint.status, expected);
+        }
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn backtest_retention_evicts_oldest_runs() {
+        let (root, store) = test_store().await;
+        for idx in 0..=MAX_BACKTEST_RUNS_PER_WALLET as u64 {
+            let run_id = format!("bt-BTC-{idx}");
+            save_test_run(
+                &store,
+                "0xaaaa",
+                &test_backtest_result(&run_id, "BTC", idx + 1),
+            )
+            .await;
+        }
+
+        let runs = store
+            .list_backtest_runs("0xaaaa", None, None, i64::MAX, 0)
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), MAX_BACKTEST_RUNS_PER_WALLET);
+        assert!(runs.iter().all(|run| run.id != "bt-BTC-0"));
+        assert!(
+            store
+                .backtest_result("0xaaaa", "bt-BTC-0")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
 }
@@ -16,5 +16,8 @@ pub use auth::{AuthUser, spawn_nonce_pruner, spawn_pending_agent_pruner};
 pub use bot_manager::BotManager;
 pub use local_store::LocalStore;
 pub use routes::create_router;
-pub use scripting::{CompiledStrategy, StateDeclarations, compile_strategy, create_engine};
+pub use scripting::{
+    CompiledStrategy, ScriptLogKind, StateDeclarations, capture_script_output, compile_strategy,
+    create_engine,
+};
 pub use storage_models::{StrategyRow, TradeRow};
@@ -4,24 +4,38 @@ use std::time::{Duration, Instant};
 use alloy::primitives::Address;
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{Path, Query, State, WebSocketUpgrade};
-use axum::http::{HeaderValue, Method, Request, StatusCode};
-use axum::response::IntoResponse;
+use axum::http::{HeaderValue, Method, Request, StatusCode, header};
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use futures_util::StreamExt;
 use log::info;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
-use super::app_state::{AppState, CachedStrategy, WsConnection, broadcast_to_user};
+use super::app_state::{
+    ActiveBacktest, ActiveBacktests, AppState, CachedStrategy, StepSessions, WsConnection,
+    WsConnections, broadcast_to_user,
+};
 use super::auth::{self, AuthUser};
-use crate::backtest::BacktestRunRequest;
+use super::local_store::{LocalStore, MAX_BACKTEST_RUN_ID_LEN};
+use super::storage_models::BacktestRunRow;
+use crate::backtest::{
+    BacktestCheckpoint, BacktestProgress, BacktestResult, BacktestRunRequest, Breakpoint,
+    CheckpointStatus, CompareRequest, OptimizeRequest, Optimizer, PortfolioBacktester,
+    PortfolioRunRequest, ReportFormat, StepCommand, StepEvent, StepSession, StopReason,
+    WalkForward, WalkForwardRequest,
+};
+use crate::backtest::{compare, report};
 use crate::metrics::{RuntimeMetricsSnapshot, runtime_metrics_snapshot};
 use crate::{
     BacktestProgressUpdate, BacktestResultUpdate, BacktestRunError, BacktestRunPayload,
-    BacktestRunResponse, Backtester, Bot, BotEvent, DEFAULT_BUILDER_ADDRESS, DEFAULT_BUILDER_FEE,
-    UpdateFrontend, get_time_now,
+    BacktestRunResponse, Backtester, Bot, BotEvent, ClientMessage, DEFAULT_BUILDER_ADDRESS,
+    DEFAULT_BUILDER_FEE, OptimizeRunResponse, PortfolioRunResponse, UpdateFrontend,
+    WalkForwardRunResponse, get_time_now,
 };
 
 const WS_SEND_TIMEOUT_SECS: u64 = 5;
@@ -53,12 +67,24 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         // Bot commands (authenticated)
         .route("/command", post(execute_command))
         .route("/backtest", post(run_backtest))
+        .route("/backtest/optimize", post(run_optimize))
+        .route("/backtest/walk-forward", post(run_walk_forward))
+        .route("/backtest/portfolio", post(run_portfolio_backtest))
+        .route("/backtest/{id}/cancel", post(cancel_backtest))
+        .route("/backtest/{id}/retry", post(retry_backtest))
+        .route("/backtest/checkpoints", get(list_backtest_checkpoints))
+        .route(
+            "/backtest/checkpoints/{id}",
+            axum::routing::delete(delete_backtest_checkpoint),
+        )
         // Backtest history
         .route("/backtest/history", get(list_backtest_history))
         .route(
             "/backtest/history/{id}",
             get(get_backtest_result).delete(delete_backtest_run),
         )
+        .route("/backtest/history/{id}/report", get(get_backtest_report))
+        .route("/backtest/compare", post(compare_backtest_runs))
         // Data queries (authenticated)
         .route("/metrics", get(get_metrics))
         .route("/trades/{market}", get(get_trades))
@@ -477,25 +503,51 @@ fn make_backtest_run_id(asset: &str) -> String {
 }
 
 fn validate_backtest_request(request: &BacktestRunRequest) -> Result<(), String> {
-    let cfg = &request.config;
-    if cfg.asset.trim().is_empty() {
-        return Err("asset must not be empty".to_string());
+    request.config.validate()?;
+    if let Some(run_id) = request.run_id.as_deref() {
+        validate_backtest_run_id(run_id)?;
     }
-    if !cfg.margin.is_finite() || cfg.margin <= 0.0 {
-        return Err("margin must be a positive finite number".to_string());
+    if let Some(monte_carlo) = request.monte_carlo.as_ref() {
+        monte_carlo.validate()?;
     }
-    if cfg.lev == 0 {
-        return Err("lev must be greater than zero".to_string());
-    }
-    if cfg.end_time <= cfg.start_time {
-        return Err("endTime must be greater than startTime".to_string());
-    }
-    if cfg.resolution.to_millis() == 0 {
-        return Err("resolution must be a supported timeframe".to_string());
+    Ok(())
+}
+
+fn validate_backtest_run_id(run_id: &str) -> Result<(), String> {
+    if run_id.len() > MAX_BACKTEST_RUN_ID_LEN || run_id.chars().any(char::is_control) {
+        return Err(format!(
+            "runId must be at most {MAX_BACKTEST_RUN_ID_LEN} bytes without control characters"
+        ));
     }
     Ok(())
 }
 
+async fn persist_backtest_result(state: &AppState, pubkey: &str, result: &BacktestResult) {
+    let strategy_id = result.config.strategy_id;
+    let cached_name = state
+        .strategy_cache
+        .read()
+        .await
+        .get(&strategy_id)
+        .map(|cached| cached.name.clone());
+    let strategy_name = match cached_name {
+        Some(name) => name,
+        None => match state.store.strategy(strategy_id).await {
+            Ok(Some(row)) => row.name,
+            Ok(None) => String::new(),
+            Err(err) => {
+                log::warn!("failed to resolve strategy name for backtest history: {err}");
+                String::new()
+            }
+        },
+    };
+
+    let row = BacktestRunRow::from_result(pubkey, strategy_name, result);
+    if let Err(err) = state.store.save_backtest_run(row, result).await {
+        log::warn!("failed to persist backtest {}: {err}", result.run_id);
+    }
+}
+
 async fn run_backtest(
     State(state): State<Arc<AppState>>,
     auth: AuthUser,
@@ -518,129 +570,499 @@ async fn run_backtest(
     }
 
     request.run_id = Some(run_id.clone());
+    run_backtest_job(&state, &auth.pubkey, request, run_id).await
+}
+
+/// Retry a cancelled or interrupted backtest with its checkpointed request.
+/// The simulation starts over from the first candle; only candles the first
+/// attempt never stored are downloaded again.
+async fn retry_backtest(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Response {
+    if validate_backtest_run_id(&id).is_err() {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+    let checkpoint = match state.store.backtest_checkpoint(&auth.pubkey, &id).await {
+        Ok(Some(checkpoint)) => checkpoint,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(err) => return store_error("get backtest checkpoint", err).into_response(),
+    };
+    if checkpoint.status == CheckpointStatus::Running {
+        return StatusCode::CONFLICT.into_response();
+    }
+
+    let mut request = checkpoint.request;
+    request.run_id = Some(id.clone());
+    run_backtest_job(&state, &auth.pubkey, request, id).await
+}
+
+async fn cancel_backtest(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> StatusCode {
+    if cancel_active_backtest(&state.active_backtests, &auth.pubkey, &id).await {
+        StatusCode::ACCEPTED
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// Cancel `pubkey`'s running job if it is `run_id`.
+async fn cancel_active_backtest(active: &ActiveBacktests, pubkey: &str, run_id: &str) -> bool {
+    match active.read().await.get(pubkey) {
+        Some(job) if job.run_id == run_id => {
+            job.cancel.cancel();
+            true
+        }
+        _ => false,
+    }
+}
+
+async fn list_backtest_checkpoints(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+) -> Result<impl IntoResponse, StatusCode> {
+    let checkpoints = state
+        .store
+        .list_backtest_checkpoints(&auth.pubkey)
+        .await
+        .map_err(|err| store_error("list backtest checkpoints", err))?;
+    Ok(Json(checkpoints))
+}
+
+async fn delete_backtest_checkpoint(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    validate_backtest_run_id(&id).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let deleted = state
+        .store
+        .delete_backtest_checkpoint(&auth.pubkey, &id)
+        .await
+        .map_err(|err| store_error("delete backtest checkpoint", err))?;
+    Ok(if deleted {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    })
+}
+
+/// Simulated candles between checkpoint writes.
+const CHECKPOINT_EVERY_CANDLES: u64 = 5_000;
 
-    // Per-user concurrency guard: only 1 active backtest per user
+/// Progress of a running backtest job, recorded for the HTTP response and
+/// mirrored to the user's websockets as it happens.
+#[derive(Clone)]
+struct JobProgress {
+    ws_connections: WsConnections,
+    pubkey: String,
+    run_id: String,
+    events: Arc<std::sync::Mutex<Vec<BacktestProgress>>>,
+}
+
+impl JobProgress {
+    fn push(&self, evt: BacktestProgress) {
+        self.events
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(evt.clone());
+        let conns = self.ws_connections.clone();
+        let pk = self.pubkey.clone();
+        let rid = self.run_id.clone();
+        tokio::spawn(async move {
+            broadcast_to_user(
+                &conns,
+                &pk,
+                UpdateFrontend::BacktestProgress(BacktestProgressUpdate {
+                    run_id: rid,
+                    progress: evt,
+                }),
+            )
+            .await;
+        });
+    }
+
+    fn take(&self) -> Vec<BacktestProgress> {
+        std::mem::take(
+            &mut *self
+                .events
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+}
+
+/// Run `job` in the caller's single backtest slot. Errors, including a busy
+/// slot, are shaped into `BacktestRunError` responses; on success the result
+/// comes back with the progress it reported.
+async fn run_guarded_job<T, F, Fut>(
+    state: &AppState,
+    pubkey: &str,
+    run_id: &str,
+    cancel: CancellationToken,
+    job: F,
+) -> Result<(T, Vec<BacktestProgress>), Response>
+where
+    F: FnOnce(JobProgress) -> Fut,
+    Fut: Future<Output = Result<T, crate::Error>>,
+{
     let active_guard = match ActiveBacktestGuard::acquire(
         Arc::clone(&state.active_backtests),
-        auth.pubkey.clone(),
+        pubkey.to_string(),
+        ActiveBacktest {
+            run_id: run_id.to_string(),
+            cancel,
+        },
     )
     .await
     {
         Some(guard) => guard,
         None => {
-            return (
+            return Err((
                 StatusCode::TOO_MANY_REQUESTS,
                 Json(BacktestRunError {
-                    run_id,
+                    run_id: run_id.to_string(),
                     message: "A backtest is already running".to_string(),
                     progress: Vec::new(),
                 }),
             )
-                .into_response();
+                .into_response());
         }
     };
 
-    let mut backtester = match Backtester::from_request(
-        request,
-        state.rhai_engine.clone(),
-        state.strategy_cache.clone(),
-        state.store.clone(),
-        state.candle_store.clone(),
-    )
-    .await
-    {
-        Ok(bt) => bt,
-        Err(e) => {
-            active_guard.release().await;
-            return Json(BacktestRunError {
-                run_id,
-                message: e.to_string(),
-                progress: Vec::new(),
-            })
-            .into_response();
-        }
+    let progress = JobProgress {
+        ws_connections: state.ws_connections.clone(),
+        pubkey: pubkey.to_string(),
+        run_id: run_id.to_string(),
+        events: Arc::default(),
     };
-    let mut progress = Vec::new();
-
-    let ws_conns = state.ws_connections.clone();
-    let pubkey = auth.pubkey.clone();
-    let progress_run_id = run_id.clone();
-
-    let response = match backtester
-        .run_with_progress(|evt| {
-            progress.push(evt.clone());
-            let conns = ws_conns.clone();
-            let pk = pubkey.clone();
-            let rid = progress_run_id.clone();
-            let evt = evt.clone();
-            tokio::spawn(async move {
-                broadcast_to_user(
-                    &conns,
-                    &pk,
-                    UpdateFrontend::BacktestProgress(BacktestProgressUpdate {
-                        run_id: rid,
-                        progress: evt,
-                    }),
-                )
-                .await;
-            });
+    let outcome = job(progress.clone()).await;
+    active_guard.release().await;
+
+    match outcome {
+        Ok(result) => Ok((result, progress.take())),
+        Err(err) => Err(Json(BacktestRunError {
+            run_id: run_id.to_string(),
+            message: err.to_string(),
+            progress: progress.take(),
         })
-        .await
-    {
-        Ok(mut result) => {
-            result.run_id = run_id.clone();
+        .into_response()),
+    }
+}
+
+async fn run_backtest_job(
+    state: &AppState,
+    pubkey: &str,
+    request: BacktestRunRequest,
+    run_id: String,
+) -> Response {
+    let cancel = CancellationToken::new();
+    let job = async |progress: JobProgress| {
+        let started_at = get_time_now();
+        let mut checkpoint = BacktestCheckpoint {
+            run_id: run_id.clone(),
+            request: request.clone(),
+            status: CheckpointStatus::Running,
+            processed: 0,
+            total: 0,
+            started_at,
+            updated_at: started_at,
+        };
+
+        let mut backtester = Backtester::from_request(
+            request,
+            state.rhai_engine.clone(),
+            state.strategy_cache.clone(),
+            state.store.clone(),
+            state.candle_store.clone(),
+        )
+        .await?;
+        backtester.set_cancel_token(cancel.clone());
 
-            let ws_conns = state.ws_connections.clone();
-            let pk = auth.pubkey.clone();
-            let rid = run_id.clone();
-            let res_clone = result.clone();
+        if let Err(err) = state
+            .store
+            .save_backtest_checkpoint(pubkey, &checkpoint)
+            .await
+        {
+            log::warn!("failed to save checkpoint for backtest {run_id}: {err}");
+        }
+
+        // A single writer keeps checkpoint saves ordered and only persists the latest.
+        let (checkpoint_tx, mut checkpoint_rx) = tokio::sync::watch::channel(checkpoint.clone());
+        let checkpoint_writer = {
+            let store = state.store.clone();
+            let pubkey = pubkey.to_string();
             tokio::spawn(async move {
-                broadcast_to_user(
-                    &ws_conns,
-                    &pk,
-                    UpdateFrontend::BacktestResult(Box::new(BacktestResultUpdate {
-                        run_id: rid,
-                        result: res_clone,
-                    })),
-                )
-                .await;
-            });
+                while checkpoint_rx.changed().await.is_ok() {
+                    let latest = checkpoint_rx.borrow_and_update().clone();
+                    if let Err(err) = store.save_backtest_checkpoint(&pubkey, &latest).await {
+                        log::warn!(
+                            "failed to save checkpoint for backtest {}: {err}",
+                            latest.run_id
+                        );
+                    }
+                }
+            })
+        };
 
-            Json(BacktestRunResponse {
-                run_id,
-                result,
-                progress,
+        let run = backtester
+            .run_with_progress(|evt| {
+                match evt {
+                    BacktestProgress::Simulating { processed, total }
+                        if processed >= checkpoint.processed + CHECKPOINT_EVERY_CANDLES =>
+                    {
+                        checkpoint.processed = processed;
+                        checkpoint.total = total;
+                        checkpoint.updated_at = get_time_now();
+                        checkpoint_tx.send_replace(checkpoint.clone());
+                    }
+                    BacktestProgress::Cancelled { processed } => {
+                        checkpoint.status = CheckpointStatus::Cancelled;
+                        checkpoint.processed = checkpoint.processed.max(processed);
+                        checkpoint.updated_at = get_time_now();
+                    }
+                    _ => {}
+                }
+                progress.push(evt);
             })
-            .into_response()
+            .await;
+
+        drop(checkpoint_tx);
+        if let Err(err) = checkpoint_writer.await {
+            log::warn!("checkpoint writer for backtest {run_id} failed: {err}");
         }
-        Err(err) => Json(BacktestRunError {
+
+        let cancelled = cancel.is_cancelled() && run.is_err();
+        if let Err(err) =
+            settle_backtest_checkpoint(&state.store, pubkey, &checkpoint, cancelled).await
+        {
+            log::warn!("failed to update checkpoint for backtest {run_id}: {err}");
+        }
+
+        let mut result = run?;
+        result.run_id = run_id.clone();
+        persist_backtest_result(state, pubkey, &result).await;
+
+        let ws_conns = state.ws_connections.clone();
+        let pk = pubkey.to_string();
+        let rid = run_id.clone();
+        let res_clone = result.clone();
+        tokio::spawn(async move {
+            broadcast_to_user(
+                &ws_conns,
+                &pk,
+                UpdateFrontend::BacktestResult(Box::new(BacktestResultUpdate {
+                    run_id: rid,
+                    result: res_clone,
+                })),
+            )
+            .await;
+        });
+        Ok(result)
+    };
+
+    match run_guarded_job(state, pubkey, &run_id, cancel.clone(), job).await {
+        Ok((result, progress)) => Json(BacktestRunResponse {
             run_id,
-            message: err.to_string(),
+            result,
             progress,
         })
         .into_response(),
+        Err(response) => response,
+    }
+}
+
+/// A cancelled run keeps its checkpoint for a retry. Finished runs land in
+/// history and failed ones would fail again on retry, so theirs is removed.
+async fn settle_backtest_checkpoint(
+    store: &LocalStore,
+    pubkey: &str,
+    checkpoint: &BacktestCheckpoint,
+    cancelled: bool,
+) -> Result<(), String> {
+    if cancelled {
+        store.save_backtest_checkpoint(pubkey, checkpoint).await
+    } else {
+        store
+            .delete_backtest_checkpoint(pubkey, &checkpoint.run_id)
+            .await
+            .map(|_| ())
+    }
+}
+
+async fn run_optimize(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(mut request): Json<OptimizeRequest>,
+) -> impl IntoResponse {
+    let run_id = request
+        .run_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| format!("opt-{}-{}", request.base.config.asset, get_time_now()));
+
+    if let Err(message) =
+        validate_backtest_request(&request.base).and_then(|_| validate_backtest_run_id(&run_id))
+    {
+        return Json(BacktestRunError {
+            run_id,
+            message,
+            progress: Vec::new(),
+        })
+        .into_response();
+    }
+
+    request.run_id = Some(run_id.clone());
+
+    // Optimizations count against the same per-user backtest slot
+    let cancel = CancellationToken::new();
+    let job = async |progress: JobProgress| {
+        let mut optimizer = Optimizer::from_request(
+            request,
+            state.rhai_engine.clone(),
+            state.store.clone(),
+            state.candle_store.clone(),
+        )
+        .await?;
+        optimizer.set_cancel_token(cancel.clone());
+        optimizer.run_with_progress(|evt| progress.push(evt)).await
     };
+    match run_guarded_job(&state, &auth.pubkey, &run_id, cancel.clone(), job).await {
+        Ok((result, progress)) => Json(OptimizeRunResponse {
+            run_id,
+            result,
+            progress,
+        })
+        .into_response(),
+        Err(response) => response,
+    }
+}
 
-    active_guard.release().await;
+async fn run_walk_forward(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(mut request): Json<WalkForwardRequest>,
+) -> impl IntoResponse {
+    let run_id = request
+        .run_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| {
+            format!(
+                "wf-{}-{}",
+                request.optimize.base.config.asset,
+                get_time_now()
+            )
+        });
+
+    if let Err(message) = validate_backtest_request(&request.optimize.base)
+        .and_then(|_| validate_backtest_run_id(&run_id))
+    {
+        return Json(BacktestRunError {
+            run_id,
+            message,
+            progress: Vec::new(),
+        })
+        .into_response();
+    }
 
-    response
+    request.run_id = Some(run_id.clone());
+
+    let cancel = CancellationToken::new();
+    let job = async |progress: JobProgress| {
+        let mut walk_forward = WalkForward::from_request(
+            request,
+            state.rhai_engine.clone(),
+            state.store.clone(),
+            state.candle_store.clone(),
+        )
+        .await?;
+        walk_forward.set_cancel_token(cancel.clone());
+        walk_forward
+            .run_with_progress(|evt| progress.push(evt))
+            .await
+    };
+    match run_guarded_job(&state, &auth.pubkey, &run_id, cancel.clone(), job).await {
+        Ok((result, progress)) => Json(WalkForwardRunResponse {
+            run_id,
+            result,
+            progress,
+        })
+        .into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn run_portfolio_backtest(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(mut request): Json<PortfolioRunRequest>,
+) -> impl IntoResponse {
+    let run_id = request
+        .run_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| format!("pf-{}", get_time_now()));
+
+    if let Err(message) = request
+        .config
+        .validate()
+        .and_then(|_| validate_backtest_run_id(&run_id))
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(BacktestRunError {
+                run_id,
+                message,
+                progress: Vec::new(),
+            }),
+        )
+            .into_response();
+    }
+
+    request.run_id = Some(run_id.clone());
+
+    let cancel = CancellationToken::new();
+    let job = async |progress: JobProgress| {
+        let mut portfolio = PortfolioBacktester::from_request(
+            request,
+            state.rhai_engine.clone(),
+            state.strategy_cache.clone(),
+            state.store.clone(),
+            state.candle_store.clone(),
+        )
+        .await?;
+        portfolio.set_cancel_token(cancel.clone());
+        portfolio.run_with_progress(|evt| progress.push(evt)).await
+    };
+    match run_guarded_job(&state, &auth.pubkey, &run_id, cancel.clone(), job).await {
+        Ok((result, progress)) => Json(PortfolioRunResponse {
+            run_id,
+            result,
+            progress,
+        })
+        .into_response(),
+        Err(response) => response,
+    }
 }
 
 struct ActiveBacktestGuard {
-    active: Arc<tokio::sync::RwLock<std::collections::HashSet<String>>>,
+    active: ActiveBacktests,
     pubkey: Option<String>,
 }
 
 impl ActiveBacktestGuard {
-    async fn acquire(
-        active: Arc<tokio::sync::RwLock<std::collections::HashSet<String>>>,
-        pubkey: String,
-    ) -> Option<Self> {
+    async fn acquire(active: ActiveBacktests, pubkey: String, job: ActiveBacktest) -> Option<Self> {
         {
             let mut guard = active.write().await;
-            if !guard.insert(pubkey.clone()) {
+            if guard.contains_key(&pubkey) {
                 return None;
             }
+            guard.insert(pubkey.clone(), job);
         }
 
         Some(Self {
@@ -1671,6 +2093,8 @@ async fn handle_ws(socket: WebSocket, state: Arc<AppState>, pubkey: String) {
     // Create channel for this connection
     let (tx, mut rx) = tokio::sync::mpsc::channel::<UpdateFrontend>(128);
     let conn_id = uuid::Uuid::new_v4();
+    let step_updates = tx.clone();
+    let mut step_commands: Option<tokio::sync::mpsc::Sender<StepCommand>> = None;
 
     // Register in connections map
     {
@@ -1727,6 +2151,29 @@ async fn handle_ws(socket: WebSocket, state: Arc<AppState>, pubkey: String) {
                             }
                         }
                     }
+                    Some(Ok(Message::Text(text))) => {
+                        match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                            Ok(ClientMessage::BacktestStep(command)) => {
+                                // One stepping session per connection, started on first use.
+                                let commands = step_commands.get_or_insert_with(|| {
+                                    let (cmd_tx, cmd_rx) = tokio::sync::mpsc::channel(16);
+                                    tokio::spawn(run_step_session(
+                                        Arc::clone(&state),
+                                        pubkey.clone(),
+                                        cmd_rx,
+                                        step_updates.clone(),
+                                    ));
+                                    cmd_tx
+                                });
+                                if commands.try_send(command).is_err() {
+                                    log::warn!("dropping backtest step command for user {pubkey}");
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("ignoring websocket message from user {pubkey}: {err}");
+                            }
+                        }
+                    }
                     Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
                     Some(Ok(_)) => {}
                 }
@@ -1748,6 +2195,255 @@ async fn handle_ws(socket: WebSocket, state: Arc<AppState>, pubkey: String) {
     info!("WebSocket disconnected for user {}", pubkey);
 }
 
+/// Bars simulated between checks for a pause while a step session runs.
+const STEP_CHUNK_BARS: u64 = 500;
+/// Step sessions a user may keep open at once, across all their connections.
+const MAX_STEP_SESSIONS_PER_USER: usize = 2;
+
+async fn run_step_session(
+    state: Arc<AppState>,
+    pubkey: String,
+    mut commands: tokio::sync::mpsc::Receiver<StepCommand>,
+    updates: tokio::sync::mpsc::Sender<UpdateFrontend>,
+) {
+    let mut session: Option<(StepSession, StepSessionSlot)> = None;
+    let mut pending: Option<StepCommand> = None;
+
+    loop {
+        let command = match pending.take() {
+            Some(command) => command,
+            None => match commands.recv().await {
+                Some(command) => command,
+                None => break,
+            },
+        };
+
+        let command = match command {
+            StepCommand::Start {
+                request,
+                breakpoints,
+            } => {
+                session = None;
+                let event =
+                    match start_step_session(&state, &pubkey, request, breakpoints, &updates).await
+                    {
+                        Ok((started, slot)) => {
+                            let event = StepEvent::Started {
+                                session_id: started.id().to_string(),
+                                total: started.total(),
+                            };
+                            session = Some((started, slot));
+                            event
+                        }
+                        Err(message) => StepEvent::Error {
+                            session_id: None,
+                            message,
+                        },
+                    };
+                send_step_event(&updates, event).await;
+                continue;
+            }
+            StepCommand::Close => {
+                session = None;
+                continue;
+            }
+            command => command,
+        };
+
+        let Some((active, _)) = session.as_mut() else {
+            send_step_event(
+                &updates,
+                StepEvent::Error {
+                    session_id: None,
+                    message: "No backtest step session is open".to_string(),
+                },
+            )
+            .await;
+            continue;
+        };
+
+        let reason = match command {
+            StepCommand::SetBreakpoints { breakpoints } => {
+                active.set_breakpoints(breakpoints);
+                continue;
+            }
+            StepCommand::Step { bars } => {
+                advance_step_session(active, &mut commands, &mut pending, bars.max(1)).await
+            }
+            StepCommand::Continue => {
+                advance_step_session(active, &mut commands, &mut pending, u64::MAX).await
+            }
+            // Pause; Start and Close were handled above.
+            _ => StopReason::Paused,
+        };
+
+        let event = StepEvent::Stopped {
+            session_id: active.id().to_string(),
+            reason,
+            frame: active.frame(),
+        };
+        let done = active.is_done();
+        send_step_event(&updates, event).await;
+
+        if done && let Some((finished, _slot)) = session.take() {
+            let session_id = finished.id().to_string();
+            let event = match finished.finish() {
+                Ok(result) => StepEvent::Finished {
+                    session_id,
+                    result: Box::new(result),
+                },
+                Err(err) => StepEvent::Error {
+                    session_id: Some(session_id),
+                    message: err.to_string(),
+                },
+            };
+            send_step_event(&updates, event).await;
+        }
+    }
+}
+
+/// Run up to `bars` bars in chunks, yielding between them so a pause or any
+/// other command can interrupt a long run. Commands other than `Pause` are
+/// left in `pending` for the session loop.
+async fn advance_step_session(
+    session: &mut StepSession,
+    commands: &mut tokio::sync::mpsc::Receiver<StepCommand>,
+    pending: &mut Option<StepCommand>,
+    mut bars: u64,
+) -> StopReason {
+    loop {
+        let chunk = bars.min(STEP_CHUNK_BARS);
+        let reason = session.advance(chunk);
+        bars -= chunk;
+        if reason != StopReason::Bars || bars == 0 {
+            return reason;
+        }
+        tokio::task::yield_now().await;
+        match commands.try_recv() {
+            Ok(StepCommand::Pause) => return StopReason::Paused,
+            Ok(other) => {
+                *pending = Some(other);
+                return StopReason::Paused;
+            }
+            Err(TryRecvError::Empty) => {}
+            // The connection is gone; the session loop ends on its next receive.
+            Err(TryRecvError::Disconnected) => return StopReason::Paused,
+        }
+    }
+}
+
+async fn start_step_session(
+    state: &AppState,
+    pubkey: &str,
+    mut request: BacktestRunRequest,
+    breakpoints: Vec<Breakpoint>,
+    updates: &tokio::sync::mpsc::Sender<UpdateFrontend>,
+) -> Result<(StepSession, StepSessionSlot), String> {
+    let session_id = request
+        .run_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .unwrap_or_else(|| make_backtest_run_id(&request.config.asset));
+    validate_backtest_request(&request)?;
+    request.run_id = Some(session_id.clone());
+
+    let slot = StepSessionSlot::acquire(Arc::clone(&state.step_sessions), pubkey.to_string())
+        .ok_or_else(|| {
+            format!("At most {MAX_STEP_SESSIONS_PER_USER} backtest step sessions can be open")
+        })?;
+
+    // Loading candles takes the user's backtest slot; stepping afterwards does not.
+    let cancel = CancellationToken::new();
+    let active_guard = ActiveBacktestGuard::acquire(
+        Arc::clone(&state.active_backtests),
+        pubkey.to_string(),
+        ActiveBacktest {
+            run_id: session_id.clone(),
+            cancel: cancel.clone(),
+        },
+    )
+    .await
+    .ok_or_else(|| "A backtest is already running".to_string())?;
+
+    let started = async {
+        let mut backtester = Backtester::from_request(
+            request,
+            state.rhai_engine.clone(),
+            state.strategy_cache.clone(),
+            state.store.clone(),
+            state.candle_store.clone(),
+        )
+        .await?;
+        backtester.set_cancel_token(cancel);
+        let progress_updates = updates.clone();
+        let progress_run_id = session_id.clone();
+        StepSession::start(
+            session_id.clone(),
+            backtester,
+            breakpoints,
+            move |progress| {
+                let _ = progress_updates.try_send(UpdateFrontend::BacktestProgress(
+                    BacktestProgressUpdate {
+                        run_id: progress_run_id.clone(),
+                        progress,
+                    },
+                ));
+            },
+        )
+        .await
+    }
+    .await;
+
+    active_guard.release().await;
+    started
+        .map(|session| (session, slot))
+        .map_err(|err| err.to_string())
+}
+
+/// Holds one of the user's step session slots until dropped.
+struct StepSessionSlot {
+    sessions: StepSessions,
+    pubkey: String,
+}
+
+impl StepSessionSlot {
+    fn acquire(sessions: StepSessions, pubkey: String) -> Option<Self> {
+        {
+            let mut open = sessions
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let count = open.entry(pubkey.clone()).or_default();
+            if *count >= MAX_STEP_SESSIONS_PER_USER {
+                return None;
+            }
+            *count += 1;
+        }
+        Some(Self { sessions, pubkey })
+    }
+}
+
+impl Drop for StepSessionSlot {
+    fn drop(&mut self) {
+        let mut open = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(count) = open.get_mut(&self.pubkey) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                open.remove(&self.pubkey);
+            }
+        }
+    }
+}
+
+async fn send_step_event(updates: &tokio::sync::mpsc::Sender<UpdateFrontend>, event: StepEvent) {
+    // The socket may already be gone; the session ends with its command channel.
+    let _ = updates
+        .send(UpdateFrontend::BacktestStep(Box::new(event)))
+        .await;
+}
+
 // ── Backtest History Routes ─────────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -1759,20 +2455,32 @@ struct BacktestHistoryQuery {
 }
 
 async fn list_backtest_history(
-    State(_state): State<Arc<AppState>>,
-    _auth: AuthUser,
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
     Query(params): Query<BacktestHistoryQuery>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    if let Some(strategy_id) = params.strategy_id {
-        strategy_id
-            .parse::<uuid::Uuid>()
-            .map_err(|_| StatusCode::BAD_REQUEST)?;
-    }
-    let _ = (
-        params.asset,
-        bounded_pagination(params.limit, params.offset),
-    );
-    Ok(Json(Vec::<serde_json::Value>::new()))
+    let strategy_id = params
+        .strategy_id
+        .map(|id| id.parse::<uuid::Uuid>())
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let asset = params
+        .asset
+        .as_deref()
+        .map(str::trim)
+        .filter(|asset| !asset.is_empty());
+    if let Some(asset) = asset {
+        validate_market_path(asset)?;
+    }
+    let (limit, offset) = bounded_pagination(params.limit, params.offset);
+
+    let rows = state
+        .store
+        .list_backtest_runs(&auth.pubkey, asset, strategy_id, limit, offset)
+        .await
+        .map_err(|err| store_error("list backtest runs", err))?;
+
+    Ok(Json(rows))
 }
 
 fn bounded_pagination(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
@@ -1803,19 +2511,115 @@ fn validate_market_path(market: &str) -> Result<(), StatusCode> {
 }
 
 async fn get_backtest_result(
-    State(_state): State<Arc<AppState>>,
-    _auth: AuthUser,
-    Path(_id): Path<uuid::Uuid>,
-) -> StatusCode {
-    StatusCode::NOT_FOUND
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    validate_backtest_run_id(&id).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let result = state
+        .store
+        .backtest_result(&auth.pubkey, &id)
+        .await
+        .map_err(|err| store_error("get backtest result", err))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(result))
+}
+
+#[derive(Deserialize)]
+struct BacktestReportQuery {
+    format: Option<String>,
+}
+
+async fn get_backtest_report(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+    Query(query): Query<BacktestReportQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    validate_backtest_run_id(&id).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let format = match query.format.as_deref() {
+        Some(raw) => raw
+            .parse::<ReportFormat>()
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => ReportFormat::default(),
+    };
+    let result = state
+        .store
+        .backtest_result(&auth.pubkey, &id)
+        .await
+        .map_err(|err| store_error("get backtest report", err))?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let disposition = format!(
+        "attachment; filename=\"{}-{}\"",
+        sanitize_filename(&id),
+        format.file_suffix()
+    );
+    let disposition = HeaderValue::from_str(&disposition).map_err(|_| StatusCode::BAD_REQUEST)?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(format.content_type()),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        report::render(&result, format),
+    ))
+}
+
+async fn compare_backtest_runs(
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Json(request): Json<CompareRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    request.validate().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let mut results = Vec::with_capacity(request.run_ids.len());
+    for id in &request.run_ids {
+        validate_backtest_run_id(id).map_err(|_| StatusCode::BAD_REQUEST)?;
+        let result = state
+            .store
+            .backtest_result(&auth.pubkey, id)
+            .await
+            .map_err(|err| store_error("compare backtest runs", err))?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        results.push(result);
+    }
+
+    let comparison = compare::compare(&results).ok_or(StatusCode::BAD_REQUEST)?;
+    Ok(Json(comparison))
+}
+
+fn sanitize_filename(raw: &str) -> String {
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
 }
 
 async fn delete_backtest_run(
-    State(_state): State<Arc<AppState>>,
-    _auth: AuthUser,
-    Path(_id): Path<uuid::Uuid>,
-) -> impl IntoResponse {
-    StatusCode::NOT_FOUND
+    State(state): State<Arc<AppState>>,
+    auth: AuthUser,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    validate_backtest_run_id(&id).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let deleted = state
+        .store
+        .delete_backtest_run(&auth.pubkey, &id)
+        .await
+        .map_err(|err| store_error("delete backtest run", err))?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Ok(StatusCode::NOT_FOUND)
+    }
 }
 
 #[cfg(test)]
@@ -1861,6 +2665,13 @@ mod tests {
         assert!(validate_market_path(&"x".repeat(MARKET_PATH_MAX_LEN + 1)).is_err());
     }
 
+    #[test]
+    fn validate_backtest_run_id_rejects_control_and_oversized_values() {
+        assert!(validate_backtest_run_id("bt-xyz:BTC-1700000000000").is_ok());
+        assert!(validate_backtest_run_id("bad\nid").is_err());
+        assert!(validate_backtest_run_id(&"x".repeat(MAX_BACKTEST_RUN_ID_LEN + 1)).is_err());
+    }
+
     #[test]
     fn parse_cors_origins_parses_comma_separated_header_values() {
         let origins = parse_cors_origins("https://app.example, http://localhost:5173")
@@ -2100,31 +2911,140 @@ mod tests {
 
     #[tokio::test]
     async fn active_backtest_guard_releases_on_explicit_release_and_drop() {
-        let active = Arc::new(tokio::sync::RwLock::new(std::collections::HashSet::new()));
+        let active: ActiveBacktests =
+            Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new()));
+        let job = |run_id: &str| ActiveBacktest {
+            run_id: run_id.to_string(),
+            cancel: CancellationToken::new(),
+        };
 
-        let guard = ActiveBacktestGuard::acquire(Arc::clone(&active), "user".to_string())
+        let guard = ActiveBacktestGuard::acquire(Arc::clone(&active), "user".to_string(), job("a"))
             .await
             .expect("first guard should acquire");
         assert!(
-            ActiveBacktestGuard::acquire(Arc::clone(&active), "user".to_string())
+            ActiveBacktestGuard::acquire(Arc::clone(&active), "user".to_string(), job("b"))
                 .await
                 .is_none()
         );
+        assert_eq!(active.read().await["user"].run_id, "a");
 
         guard.release().await;
         assert!(
-            ActiveBacktestGuard::acquire(Arc::clone(&active), "user".to_string())
+            ActiveBacktestGuard::acquire(Arc::clone(&active), "user".to_string(), job("b"))
                 .await
                 .is_some()
         );
 
-        let guard = ActiveBacktestGuard::acquire(Arc::clone(&active), "drop-user".to_string())
-            .await
-            .expect("drop guard should acquire");
+        let guard =
+            ActiveBacktestGuard::acquire(Arc::clone(&active), "drop-user".to_string(), job("c"))
+                .await
+                .expect("drop guard should acquire");
         drop(guard);
         tokio::time::sleep(Duration::from_millis(10)).await;
 
-        assert!(!active.read().await.contains("drop-user"));
+        assert!(!active.read().await.contains_key("drop-user"));
+    }
+
+    #[tokio::test]
+    async fn cancel_only_reaches_the_users_own_running_job() {
+        let active: ActiveBacktests =
+            Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new()));
+        let cancel = CancellationToken::new();
+        active.write().await.insert(
+            "user".to_string(),
+            ActiveBacktest {
+                run_id: "bt-BTC-1".to_string(),
+                cancel: cancel.clone(),
+            },
+        );
+
+        assert!(!cancel_active_backtest(&active, "other", "bt-BTC-1").await);
+        assert!(!cancel_active_backtest(&active, "user", "bt-BTC-2").await);
+        assert!(!cancel.is_cancelled());
+        assert!(cancel_active_backtest(&active, "user", "bt-BTC-1").await);
+        assert!(cancel.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn only_cancelled_runs_keep_their_checkpoint() {
+        use crate::TimeFrame;
+        use crate::backtest::{BacktestConfig, DataSource, FillModel};
+
+        let root = std::env::temp_dir().join(format!("kwant-routes-test-{}", uuid::Uuid::new_v4()));
+        let store = LocalStore::open(&root).await.unwrap();
+        let checkpoint = |run_id: &str| BacktestCheckpoint {
+            run_id: run_id.to_string(),
+            request: BacktestRunRequest {
+                run_id: Some(run_id.to_string()),
+                config: BacktestConfig {
+                    asset: "BTC".to_string(),
+                    source: DataSource::default(),
+                    strategy_id: uuid::Uuid::nil(),
+                    resolution: TimeFrame::Hour1,
+                    margin: 1_000.0,
+                    lev: 1,
+                    taker_fee_bps: 0,
+                    maker_fee_bps: 0,
+                    funding_rate_bps_per_8h: 0.0,
+                    historical_funding: false,
+                    start_time: 0,
+                    end_time: 3_600_000,
+                    snapshot_interval_candles: 0,
+                    max_equity_points: 100,
+                    max_snapshots: 0,
+                    max_script_logs: 0,
+                    fill_model: FillModel::default(),
+                    intrabar_resolution: false,
+                },
+                warmup_candles: 0,
+                monte_carlo: None,
+            },
+            status: CheckpointStatus::Running,
+            processed: 10,
+            total: 100,
+            started_at: 1,
+            updated_at: 2,
+        };
+
+        for run_id in ["bt-BTC-1", "bt-BTC-2"] {
+            store
+                .save_backtest_checkpoint("0xaaaa", &checkpoint(run_id))
+                .await
+                .unwrap();
+        }
+        let cancelled = BacktestCheckpoint {
+            status: CheckpointStatus::Cancelled,
+            ..checkpoint("bt-BTC-1")
+        };
+        settle_backtest_checkpoint(&store, "0xaaaa", &cancelled, true)
+            .await
+            .unwrap();
+        settle_backtest_checkpoint(&store, "0xaaaa", &checkpoint("bt-BTC-2"), false)
+            .await
+            .unwrap();
+
+        let left = store.list_backtest_checkpoints("0xaaaa").await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].run_id, "bt-BTC-1");
+        assert_eq!(left[0].status, CheckpointStatus::Cancelled);
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[test]
+    fn step_session_slots_are_capped_per_user_until_dropped() {
+        let sessions: StepSessions = Arc::default();
+        let slots: Vec<_> = (0..MAX_STEP_SESSIONS_PER_USER)
+            .map(|_| {
+                StepSessionSlot::acquire(Arc::clone(&sessions), "user".to_string())
+                    .expect("slot under the cap")
+            })
+            .collect();
+        assert!(StepSessionSlot::acquire(Arc::clone(&sessions), "user".to_string()).is_none());
+        assert!(StepSessionSlot::acquire(Arc::clone(&sessions), "other".to_string()).is_some());
+
+        drop(slots);
+        assert!(!sessions.lock().unwrap().contains_key("user"));
+        assert!(StepSessionSlot::acquire(Arc::clone(&sessions), "user".to_string()).is_some());
     }
 
     #[tokio::test]
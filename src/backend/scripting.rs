@@ -1,18 +1,24 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
 
 use regex::Regex;
-use rhai::{AST, Dynamic, Engine, Scope};
+use rhai::{AST, Array, Dynamic, Engine, ImmutableString, Map, NativeCallContext, Scope};
+use serde::{Deserialize, Serialize};
 
+use crate::signal::{AssetTimeFrame, TrackerCandles};
 use crate::strategy::{
-    BusyType, Intent, LimitOptions, LiqSide, OnTimeout, Order, ReduceOrder, SizeSpec, TimeoutInfo,
-    Triggers, check_asset_fix,
+    AddOrder, BusyType, Intent, LimitOptions, LiqSide, OnTimeout, Order, ReduceOrder, SizeSpec,
+    TimeoutInfo, Trail, Triggers, check_asset_fix,
 };
-use crate::{OpenPosInfo, Price, Side, TimeDelta, TimeFrame, TimedValue, Value};
+use crate::{IndicatorKind, OpenPosInfo, Price, Side, TimeDelta, TimeFrame, TimedValue, Value};
 
 /// State variable declarations: variable name → default value as Rhai literal.
 pub type StateDeclarations = HashMap<String, serde_json::Value>;
 const MAX_STATE_DECLARATIONS: usize = 256;
+/// Deepest `prev`/`history` lookback; matches the engine's array size limit.
+const MAX_INDICATOR_HISTORY: usize = 1024;
 
 // ── Compiled strategy (validated ASTs) ──────────────────────────────────────
 
@@ -23,6 +29,8 @@ pub struct CompiledStrategy {
     pub ast_on_busy: AST,
     /// Names of user-declared state variables (for post-eval sync-back).
     pub state_var_names: Vec<String>,
+    /// Past indicator closes the scripts look back on via `prev`/`history`.
+    pub history_len: usize,
 }
 
 impl CompiledStrategy {
@@ -34,6 +42,7 @@ impl CompiledStrategy {
             ast_on_open: ast.clone(),
             ast_on_busy: ast,
             state_var_names: Vec::new(),
+            history_len: 0,
         }
     }
 }
@@ -65,10 +74,101 @@ pub fn create_engine() -> Engine {
     register_intent(&mut engine);
     register_busy_type(&mut engine);
     register_timeframe(&mut engine);
+    register_history(&mut engine);
+    register_candles(&mut engine);
+
+    engine.on_print(|text| {
+        if !capture_script_log(ScriptLogKind::Print, text) {
+            println!("{text}");
+        }
+    });
 
     engine
 }
 
+// ── Script output capture ───────────────────────────────────────────────────
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScriptLogKind {
+    Print,
+    Error,
+}
+
+thread_local! {
+    static CAPTURED_OUTPUT: RefCell<Option<Vec<(ScriptLogKind, String)>>> =
+        const { RefCell::new(None) };
+}
+
+/// Run `f` with script prints and runtime errors on this thread collected
+/// instead of printed or sent to `log_tx`. Backtests share one engine, so a
+/// per-run `on_print` hook isn't available to them.
+pub fn capture_script_output<R>(f: impl FnOnce() -> R) -> (R, Vec<(ScriptLogKind, String)>) {
+    let previous = CAPTURED_OUTPUT.with(|cell| cell.replace(Some(Vec::new())));
+    let out = f();
+    let captured = CAPTURED_OUTPUT
+        .with(|cell| cell.replace(previous))
+        .unwrap_or_default();
+    (out, captured)
+}
+
+/// Returns `false` when nothing on this thread is capturing.
+pub(crate) fn capture_script_log(kind: ScriptLogKind, msg: &str) -> bool {
+    CAPTURED_OUTPUT.with(|cell| match cell.borrow_mut().as_mut() {
+        Some(buf) => {
+            buf.push((kind, msg.to_string()));
+            true
+        }
+        None => false,
+    })
+}
+
+// ── Candle history for scripts ──────────────────────────────────────────────
+
+/// Tracker candles `candles()` reads while a strategy script runs.
+#[derive(Debug, Default)]
+pub struct ScriptCandles {
+    /// Market asset `"self"` resolves to.
+    pub asset: Arc<str>,
+    pub series: Vec<(AssetTimeFrame, TrackerCandles)>,
+}
+
+impl ScriptCandles {
+    /// Up to `n` closed candles for `asset` on `tf`, newest first.
+    fn recent(&self, asset: &str, tf: TimeFrame, n: usize) -> Vec<Price> {
+        let asset = if asset == "self" {
+            self.asset.as_ref()
+        } else {
+            asset
+        };
+        self.series
+            .iter()
+            .find(|((series_asset, series_tf), _)| {
+                *series_tf == tf
+                    && (series_asset.as_ref() == asset
+                        || check_asset_fix(series_asset) == check_asset_fix(asset))
+            })
+            .map(|(_, history)| history.recent(tf, n))
+            .unwrap_or_default()
+    }
+}
+
+thread_local! {
+    static SCRIPT_CANDLES: RefCell<Option<ScriptCandles>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `candles` visible to the scripts' `candles()` function and
+/// hand them back afterwards. The engine is shared, so the history can't live
+/// in a registered closure.
+pub fn with_script_candles<R>(candles: ScriptCandles, f: impl FnOnce() -> R) -> (R, ScriptCandles) {
+    let previous = SCRIPT_CANDLES.with(|cell| cell.replace(Some(candles)));
+    let out = f();
+    let candles = SCRIPT_CANDLES
+        .with(|cell| cell.replace(previous))
+        .unwrap_or_default();
+    (out, candles)
+}
+
 /// Build a Rhai scope declaring all variables a strategy script may reference.
 /// The values are dummies — only the *names* matter for strict-variable checking.
 fn validation_scope(extra: &[&str]) -> Scope<'static> {
@@ -96,6 +196,7 @@ fn validation_scope(extra: &[&str]) -> Scope<'static> {
     scope.push("lev", 0_i64);
     scope.push("last_price", Dynamic::UNIT);
     scope.push("indicators", Dynamic::UNIT);
+    scope.push("indicator_history", Dynamic::UNIT);
     scope.push("state", Dynamic::UNIT);
 
     // Script-specific variables
@@ -122,6 +223,7 @@ pub fn compile_strategy(
         validate_state_declarations(declarations)?;
     }
 
+    let history_len = history_lookback(&[on_idle, on_open, on_busy])?;
     let state_preamble = state_declarations
         .map(generate_state_preamble)
         .unwrap_or_default();
@@ -162,6 +264,7 @@ pub fn compile_strategy(
         ast_on_open,
         ast_on_busy,
         state_var_names,
+        history_len,
     })
 }
 
@@ -215,6 +318,7 @@ fn is_reserved_strategy_identifier(name: &str) -> bool {
             | "lev"
             | "last_price"
             | "indicators"
+            | "indicator_history"
             | "state"
             | "is_armed"
             | "open_position"
@@ -224,10 +328,10 @@ fn is_reserved_strategy_identifier(name: &str) -> bool {
 
 // ── Script expansion (transpilation) ───────────────────────────────────────
 
-/// Expand a raw user script: apply extract() macro expansion, then prepend
-/// the state initialization preamble.
+/// Expand a raw user script: apply extract() and prev()/history() macro
+/// expansion, then prepend the state initialization preamble.
 fn expand_script(src: &str, state_preamble: &str) -> String {
-    let expanded = expand_extract(src);
+    let expanded = expand_history(&expand_extract(src));
     if state_preamble.is_empty() {
         expanded
     } else {
@@ -312,6 +416,183 @@ fn expand_extract(src: &str) -> String {
     .into_owned()
 }
 
+fn lookback_call_re() -> Option<&'static Regex> {
+    static LOOKBACK_CALL_RE: OnceLock<Result<Regex, regex::Error>> = OnceLock::new();
+    LOOKBACK_CALL_RE
+        .get_or_init(|| Regex::new(r"\b(?:prev|history)\s*\("))
+        .as_ref()
+        .ok()
+}
+
+/// Where `prev(`/`history(` matches in `src` are real lookback calls.
+struct LookbackSites<'a> {
+    src: &'a str,
+    /// String literals and comments.
+    skipped: Vec<Range<usize>>,
+    /// `prev`/`history` defined by the script itself.
+    user_defined: Vec<&'a str>,
+}
+
+impl<'a> LookbackSites<'a> {
+    fn new(src: &'a str, call_re: &Regex) -> Self {
+        let skipped = literal_and_comment_spans(src);
+        let user_defined = call_re
+            .find_iter(src)
+            .filter(|m| !skipped.iter().any(|span| span.contains(&m.start())))
+            .filter(|m| is_fn_definition(&src[..m.start()]))
+            .map(|m| call_name(m.as_str()))
+            .collect();
+        Self {
+            src,
+            skipped,
+            user_defined,
+        }
+    }
+
+    /// A match is a lookback unless it sits in a literal or comment, is a
+    /// method on something else (`x.prev(...)`), or names a script function.
+    fn is_lookback(&self, call: &str, start: usize) -> bool {
+        let before = self.src[..start].trim_end();
+        !before.ends_with('.')
+            && !is_fn_definition(before)
+            && !self.user_defined.contains(&call_name(call))
+            && !self.skipped.iter().any(|span| span.contains(&start))
+    }
+}
+
+fn call_name(call: &str) -> &str {
+    call.split('(').next().unwrap_or(call).trim_end()
+}
+
+fn is_fn_definition(before: &str) -> bool {
+    before
+        .trim_end()
+        .strip_suffix("fn")
+        .is_some_and(|rest| !rest.ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+/// Byte ranges of Rhai string/character literals and line/block comments.
+fn literal_and_comment_spans(src: &str) -> Vec<Range<usize>> {
+    let bytes = src.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            quote @ (b'"' | b'`' | b'\'') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' && quote != b'`' {
+                        2
+                    } else {
+                        1
+                    };
+                }
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                // Rhai block comments nest.
+                let mut depth = 0_usize;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"/*") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b"*/") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        spans.push(start..i.min(bytes.len()));
+    }
+    spans
+}
+
+/// Deepest lookback requested by `prev(key, n)` / `history(key, n)` across
+/// the scripts; it sizes the per-indicator history the engine keeps, so `n`
+/// must be an integer literal.
+fn history_lookback(scripts: &[&str]) -> Result<usize, String> {
+    static LOOKBACK_RE: OnceLock<Result<Regex, regex::Error>> = OnceLock::new();
+    let (Some(call_re), Ok(lookback_re)) = (
+        lookback_call_re(),
+        LOOKBACK_RE.get_or_init(|| Regex::new(r"\b(?:prev|history)\s*\([^,()]*,\s*(\d+)\s*\)")),
+    ) else {
+        return Ok(0);
+    };
+
+    let mut depth = 0;
+    for src in scripts {
+        let sites = LookbackSites::new(src, call_re);
+        let calls = call_re
+            .find_iter(src)
+            .filter(|m| sites.is_lookback(m.as_str(), m.start()))
+            .count();
+        let mut literal_calls = 0;
+        for caps in lookback_re.captures_iter(src) {
+            let Some(call) = caps.get(0) else {
+                continue;
+            };
+            if !sites.is_lookback(call.as_str(), call.start()) {
+                continue;
+            }
+            literal_calls += 1;
+            let n: usize = caps[1]
+                .parse()
+                .map_err(|_| format!("invalid lookback: {}", &caps[1]))?;
+            depth = depth.max(n);
+        }
+        if literal_calls != calls {
+            return Err(
+                "prev()/history() take an indicator key and an integer literal lookback, e.g. prev(\"rsi_14_1h\", 1)"
+                    .to_string(),
+            );
+        }
+    }
+    if depth > MAX_INDICATOR_HISTORY {
+        return Err(format!(
+            "prev()/history() can look back at most {MAX_INDICATOR_HISTORY} closes"
+        ));
+    }
+    Ok(depth)
+}
+
+/// Pass the per-tick `indicator_history` map into `prev(key, n)` and
+/// `history(key, n)` calls.
+fn expand_history(src: &str) -> String {
+    let Some(re) = lookback_call_re() else {
+        return src.to_string();
+    };
+
+    let sites = LookbackSites::new(src, re);
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for m in re.find_iter(src) {
+        if !sites.is_lookback(m.as_str(), m.start()) {
+            continue;
+        }
+        out.push_str(&src[last..m.end()]);
+        out.push_str("indicator_history, ");
+        last = m.end();
+    }
+    out.push_str(&src[last..]);
+    out
+}
+
 fn rhai_string_literal(value: &str) -> String {
     format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
 }
@@ -349,6 +630,42 @@ fn json_to_rhai_literal(val: &serde_json::Value) -> String {
     }
 }
 
+/// Convert a Rhai value to JSON for display. Registered types fall back to
+/// their string form.
+pub fn dynamic_to_json(val: &Dynamic) -> serde_json::Value {
+    use serde_json::Value as Json;
+
+    if val.is_unit() {
+        return Json::Null;
+    }
+    if let Ok(b) = val.as_bool() {
+        return Json::Bool(b);
+    }
+    if let Ok(i) = val.as_int() {
+        return Json::from(i);
+    }
+    if let Ok(f) = val.as_float() {
+        return serde_json::Number::from_f64(f).map_or(Json::Null, Json::Number);
+    }
+    if let Ok(c) = val.as_char() {
+        return Json::String(c.to_string());
+    }
+    if let Some(s) = val.read_lock::<ImmutableString>() {
+        return Json::String(s.to_string());
+    }
+    if let Some(items) = val.read_lock::<Array>() {
+        return Json::Array(items.iter().map(dynamic_to_json).collect());
+    }
+    if let Some(map) = val.read_lock::<Map>() {
+        return Json::Object(
+            map.iter()
+                .map(|(key, value)| (key.to_string(), dynamic_to_json(value)))
+                .collect(),
+        );
+    }
+    Json::String(val.to_string())
+}
+
 // ── Type registrations ──────────────────────────────────────────────────────
 
 fn register_side(engine: &mut Engine) {
@@ -546,14 +863,37 @@ fn register_triggers(engine: &mut Engine) {
     engine.register_fn("triggers", |tp: f64, sl: f64| Triggers {
         tp: Some(tp),
         sl: Some(sl),
+        trail: None,
     });
     engine.register_fn("tp_only", |tp: f64| Triggers {
         tp: Some(tp),
         sl: None,
+        trail: None,
     });
     engine.register_fn("sl_only", |sl: f64| Triggers {
         tp: None,
         sl: Some(sl),
+        trail: None,
+    });
+
+    engine.register_type_with_name::<Trail>("Trail");
+    engine.register_fn("trail_pct", |pct: f64| Trail::Pct(pct));
+    engine.register_fn("trail_atr", |tf: TimeFrame, period: i64, mult: f64| {
+        Trail::Atr {
+            atr: IndicatorKind::Atr(period.clamp(1, u16::MAX as i64) as _),
+            tf,
+            mult,
+        }
+    });
+    engine.register_fn("trailing", |trail: Trail| Triggers {
+        tp: None,
+        sl: None,
+        trail: Some(trail),
+    });
+    engine.register_fn("trailing", |tp: f64, trail: Trail| Triggers {
+        tp: Some(tp),
+        sl: None,
+        trail: Some(trail),
     });
 }
 
@@ -561,6 +901,7 @@ fn register_intent(engine: &mut Engine) {
     engine.register_type_with_name::<Intent>("Intent");
     engine.register_type_with_name::<Order>("Order");
     engine.register_type_with_name::<ReduceOrder>("ReduceOrder");
+    engine.register_type_with_name::<AddOrder>("AddOrder");
 
     engine.register_fn("open_market", |side: Side, size: SizeSpec| {
         Intent::open_market(side, size, None)
@@ -573,6 +914,9 @@ fn register_intent(engine: &mut Engine) {
     engine.register_fn("reduce_market", |size: SizeSpec| {
         Intent::reduce_market_order(size)
     });
+    engine.register_fn("add_market", |size: SizeSpec| {
+        Intent::add_market_order(size)
+    });
     engine.register_fn("abort", || Intent::Abort);
 
     engine.register_fn("open_limit", |side: Side, size: SizeSpec, limit_px: f64| {
@@ -596,6 +940,12 @@ fn register_intent(engine: &mut Engine) {
             Intent::open_limit(side, size, limit_px, Some(ttl), Some(trig))
         },
     );
+    engine.register_fn(
+        "add_limit",
+        |size: SizeSpec, limit_px: f64, expire_after: TimeDelta| {
+            Intent::add_limit_order(size, limit_px, expire_after)
+        },
+    );
     engine.register_fn("reduce_limit", |size: SizeSpec, limit_px: f64| {
         Intent::reduce_limit_order(size, limit_px, None)
     });
@@ -614,6 +964,10 @@ fn register_intent(engine: &mut Engine) {
 
     engine.register_fn("arm", |td: TimeDelta| Intent::Arm(td));
     engine.register_fn("disarm", || Intent::Disarm);
+
+    engine.register_fn("set_sl", |px: f64| Intent::SetSl(px));
+    engine.register_fn("set_tp", |px: f64| Intent::SetTp(px));
+    engine.register_fn("clear_triggers", || Intent::ClearTriggers);
 }
 
 fn register_busy_type(engine: &mut Engine) {
@@ -644,11 +998,70 @@ fn register_timeframe(engine: &mut Engine) {
     });
 }
 
+fn register_history(engine: &mut Engine) {
+    // `prev(key, n)`: the value `n` closes before the current one, or `()`.
+    engine.register_fn("prev", |history: &mut Map, key: &str, n: i64| -> Dynamic {
+        let Some(past) = history
+            .get(check_asset_fix(key).as_str())
+            .and_then(|v| v.read_lock::<Array>())
+        else {
+            return Dynamic::UNIT;
+        };
+        usize::try_from(n)
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|idx| past.get(idx).cloned())
+            .unwrap_or(Dynamic::UNIT)
+    });
+    // `history(key, n)`: up to `n` past closes, newest first.
+    engine.register_fn("history", |history: &mut Map, key: &str, n: i64| -> Array {
+        let Some(past) = history
+            .get(check_asset_fix(key).as_str())
+            .and_then(|v| v.read_lock::<Array>())
+        else {
+            return Array::new();
+        };
+        past.iter()
+            .take(usize::try_from(n).unwrap_or(0))
+            .cloned()
+            .collect()
+    });
+}
+
+fn register_candles(engine: &mut Engine) {
+    // `candles(asset, tf, n)`: up to `n` candles newest first, for any asset
+    // and timeframe with a configured indicator.
+    engine.register_fn(
+        "candles",
+        |ctx: NativeCallContext, asset: &str, tf: TimeFrame, n: i64| -> Array {
+            let limit = match ctx.engine().max_array_size() {
+                0 => usize::MAX,
+                max => max,
+            };
+            let n = usize::try_from(n).unwrap_or(0).min(limit);
+            SCRIPT_CANDLES.with(|cell| {
+                cell.borrow()
+                    .as_ref()
+                    .map(|candles| {
+                        candles
+                            .recent(asset, tf, n)
+                            .into_iter()
+                            .map(Dynamic::from)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+        },
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        StateDeclarations, expand_extract, is_valid_state_identifier, json_to_rhai_literal,
-        rhai_string_literal, validate_state_declarations,
+        ScriptLogKind, StateDeclarations, capture_script_log, capture_script_output,
+        compile_strategy, create_engine, dynamic_to_json, expand_extract, expand_history,
+        history_lookback, is_valid_state_identifier, json_to_rhai_literal, rhai_string_literal,
+        validate_state_declarations,
     };
     use std::collections::HashMap;
 
@@ -760,4 +1173,107 @@ mod tests {
 
         assert!(validate_state_declarations(&declarations).is_err());
     }
+
+    #[test]
+    fn capture_collects_prints_only_inside_the_closure() {
+        let engine = create_engine();
+        let ((), output) = capture_script_output(|| {
+            engine.run(r#"print("hello"); print(1 + 2);"#).unwrap();
+        });
+        assert_eq!(
+            output,
+            vec![
+                (ScriptLogKind::Print, "hello".to_string()),
+                (ScriptLogKind::Print, "3".to_string()),
+            ]
+        );
+        assert!(!capture_script_log(ScriptLogKind::Error, "not captured"));
+    }
+
+    #[test]
+    fn dynamic_to_json_converts_nested_state() {
+        let engine = create_engine();
+        let state = engine
+            .eval::<rhai::Dynamic>(r#"#{ count: 3, ratio: 0.5, tags: ["a", true], last: () }"#)
+            .unwrap();
+
+        assert_eq!(
+            dynamic_to_json(&state),
+            serde_json::json!({
+                "count": 3,
+                "ratio": 0.5,
+                "tags": ["a", true],
+                "last": null,
+            })
+        );
+    }
+
+    #[test]
+    fn lookback_calls_are_expanded_and_sized_from_literals() {
+        let src = r#"let before = prev("self_rsi_14_1h", 1);
+let window = history("BTC_rsi_14_1h", 5);
+let p = last_price.prev(2);"#;
+
+        assert_eq!(history_lookback(&[src, "", "prev(\"x\", 3)"]), Ok(5));
+        let expanded = expand_history(src);
+        assert!(expanded.contains(r#"prev(indicator_history, "self_rsi_14_1h", 1)"#));
+        assert!(expanded.contains(r#"history(indicator_history, "BTC_rsi_14_1h", 5)"#));
+        assert!(expanded.contains("last_price.prev(2)"));
+
+        assert!(history_lookback(&[r#"let n = 2; prev("self_rsi_14_1h", n)"#]).is_err());
+        assert!(history_lookback(&[r#"prev("self_rsi_14_1h", 5000)"#]).is_err());
+    }
+
+    #[test]
+    fn lookback_rewrites_skip_literals_comments_and_script_functions() {
+        let src = r#"// prev(x) is only a comment
+/* history(a, 9) /* nested */ prev(b) */
+let label = "history(log)";
+let call = prev("self_rsi_14_1h", 2);"#;
+        assert_eq!(history_lookback(&[src]), Ok(2));
+        let expanded = expand_history(src);
+        assert!(expanded.contains("// prev(x) is only a comment"));
+        assert!(expanded.contains("/* history(a, 9) /* nested */ prev(b) */"));
+        assert!(expanded.contains(r#""history(log)""#));
+        assert!(expanded.contains(r#"prev(indicator_history, "self_rsi_14_1h", 2)"#));
+
+        let own = r#"fn history(x) { x * 2 }
+let doubled = history(price);"#;
+        assert_eq!(history_lookback(&[own]), Ok(0));
+        assert_eq!(expand_history(own), own);
+    }
+
+    #[test]
+    fn prev_and_history_read_past_closes_newest_first() {
+        let engine = create_engine();
+        let compiled = compile_strategy(
+            &engine,
+            r#"if prev("self_rsi_14_1h", 1) == () { return; }
+let past = history("self_rsi_14_1h", 4);
+if past.len() != 2 || prev("self_rsi_14_1h", 3) != () { return; }
+return abort();"#,
+            "",
+            "",
+            None,
+        )
+        .unwrap();
+        assert_eq!(compiled.history_len, 4);
+
+        let mut scope = rhai::Scope::new();
+        let mut history = rhai::Map::new();
+        let past: rhai::Array = vec![rhai::Dynamic::from(1_i64), rhai::Dynamic::from(2_i64)];
+        history.insert("self_rsi_14_1h".into(), past.into());
+        scope.push("free_margin", 0.0_f64);
+        scope.push("lev", 0_i64);
+        scope.push("last_price", rhai::Dynamic::UNIT);
+        scope.push("indicators", rhai::Map::new());
+        scope.push("indicator_history", history);
+        scope.push("state", rhai::Map::new());
+        scope.push("is_armed", -1_i64);
+
+        let result = engine
+            .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, &compiled.ast_on_idle)
+            .unwrap();
+        assert!(result.is::<crate::strategy::Intent>());
+    }
 }
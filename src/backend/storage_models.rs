@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::backtest::BacktestResult;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TradeRow {
     pub id: uuid::Uuid,
@@ -42,3 +44,61 @@ pub struct StrategyRow {
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestRunRow {
+    pub id: String,
+    pub pubkey: String,
+    pub strategy_id: uuid::Uuid,
+    pub strategy_name: String,
+    pub asset: String,
+    pub resolution: String,
+    pub exchange: String,
+    pub market: String,
+    pub margin: f64,
+    pub lev: usize,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub net_pnl: f64,
+    pub return_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub total_trades: usize,
+    pub win_rate_pct: f64,
+    pub profit_factor: Option<f64>,
+    pub sharpe_ratio: Option<f64>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl BacktestRunRow {
+    pub fn from_result(pubkey: &str, strategy_name: String, result: &BacktestResult) -> Self {
+        let cfg = &result.config;
+        let summary = &result.summary;
+        Self {
+            id: result.run_id.clone(),
+            pubkey: pubkey.to_string(),
+            strategy_id: cfg.strategy_id,
+            strategy_name,
+            asset: cfg.asset.clone(),
+            resolution: cfg.resolution.as_str().to_string(),
+            exchange: cfg.source.exchange.name().to_string(),
+            market: cfg.source.market.as_str().to_string(),
+            margin: cfg.margin,
+            lev: cfg.lev,
+            start_time: cfg.start_time,
+            end_time: cfg.end_time,
+            net_pnl: summary.net_pnl,
+            return_pct: summary.return_pct,
+            max_drawdown_pct: summary.max_drawdown_pct,
+            total_trades: summary.total_trades,
+            win_rate_pct: summary.win_rate_pct,
+            profit_factor: summary.profit_factor,
+            sharpe_ratio: summary.sharpe_ratio,
+            started_at: result.started_at,
+            finished_at: result.finished_at,
+            created_at: chrono::Utc::now(),
+        }
+    }
+}
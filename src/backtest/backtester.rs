@@ -1,22 +1,34 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 
 use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rhai::Engine;
+use tokio_util::sync::CancellationToken;
 
+use super::benchmark;
+use super::candle_store::FundingRate;
 use super::downsample::{cap_snapshots, lttb_equity};
 use super::fetcher::{DataSource, Fetcher, RequestLimiter};
+use super::metrics::{self, SampledReturns};
+use super::monte_carlo;
+use super::stepper::StepIntent;
 use super::types::{
     BacktestProgress, BacktestResult, BacktestRunRequest, BacktestSummary, CandlePoint,
-    EquityPoint, PositionSnapshot, SnapshotReason,
+    EquityPoint, LatencyModel, LimitFillModel, PositionSnapshot, ScriptLogEntry, SlippageModel,
+    SnapshotReason, TouchFill, TradeExcursion,
 };
 use crate::backend::LocalStore;
 use crate::backend::app_state::StrategyCache;
+use crate::backend::scripting::{
+    CompiledStrategy, ScriptLogKind, StateDeclarations, capture_script_output, compile_strategy,
+};
 use crate::strategy::replace_self_with_asset;
 use crate::{
-    BtAction, BtIntent, BtOrder, CloseOrder, EngineOrder, Error, FillInfo, FillType, OpenOrder,
-    OpenPosInfo, OpenPositionLocal, PositionOp, Price, Side, SignalEngine, TimeFrame, TradeInfo,
-    TriggerKind, Triggers, get_time_now,
+    BtAction, BtIntent, BtOrder, CloseOrder, EngineOrder, Error, FillInfo, FillType, IndexId,
+    OpenOrder, OpenPosInfo, OpenPositionLocal, PositionOp, Price, Side, SignalEngine, TimeFrame,
+    TradeInfo, TriggerKind, TriggerReplace, Triggers, get_time_now,
 };
 
 const FUNDING_WINDOW_MS: u64 = 8 * 60 * 60 * 1000;
@@ -35,9 +47,51 @@ struct PositionState {
     funding: f64,
     realised_pnl: f64,
     fill_type: FillType,
+    /// Best and worst prices seen while open, for MAE/MFE.
+    best_px: f64,
+    worst_px: f64,
 }
 
 impl PositionState {
+    fn track_excursion(&mut self, high: f64, low: f64) {
+        let (best, worst) = match self.side {
+            Side::Long => (high, low),
+            Side::Short => (low, high),
+        };
+        if self.is_better(best, self.best_px) {
+            self.best_px = best;
+        }
+        if self.is_better(self.worst_px, worst) {
+            self.worst_px = worst;
+        }
+    }
+
+    fn is_better(&self, px: f64, than: f64) -> bool {
+        match self.side {
+            Side::Long => px > than,
+            Side::Short => px < than,
+        }
+    }
+
+    fn excursion(&self) -> TradeExcursion {
+        if self.entry_px <= EPSILON {
+            return TradeExcursion::default();
+        }
+        let pct = |px: f64| (px - self.entry_px).abs() / self.entry_px * 100.0;
+        TradeExcursion {
+            mae_pct: if self.is_better(self.worst_px, self.entry_px) {
+                0.0
+            } else {
+                pct(self.worst_px)
+            },
+            mfe_pct: if self.is_better(self.best_px, self.entry_px) {
+                pct(self.best_px)
+            } else {
+                0.0
+            },
+        }
+    }
+
     fn to_open_pos_info(self) -> OpenPosInfo {
         OpenPosInfo {
             side: self.side,
@@ -63,15 +117,59 @@ impl PositionState {
 
 #[derive(Clone, Copy, Debug)]
 enum RestingKind {
-    Open { triggers: Option<Triggers> },
+    /// `add` grows the position it was placed for and is dropped with it.
+    Open {
+        triggers: Option<Triggers>,
+        add: bool,
+    },
     Close,
 }
 
+/// Market order waiting for the next execution candle under `LatencyModel::NextOpen`.
+#[derive(Clone, Copy, Debug)]
+enum PendingMarket {
+    /// `add` grows the position it was sent for instead of opening a new one.
+    Open {
+        open: OpenOrder,
+        add: bool,
+    },
+    Close(Option<EngineOrder>),
+}
+
+#[derive(Clone, Debug, Default)]
+struct AtrWindow {
+    prev_close: Option<f64>,
+    ranges: VecDeque<f64>,
+}
+
+impl AtrWindow {
+    fn push(&mut self, candle: Price, period: usize) {
+        let range = match self.prev_close {
+            Some(prev) => (candle.high - candle.low)
+                .max((candle.high - prev).abs())
+                .max((candle.low - prev).abs()),
+            None => candle.high - candle.low,
+        };
+        self.prev_close = Some(candle.close);
+        self.ranges.push_back(range);
+        while self.ranges.len() > period.max(1) {
+            self.ranges.pop_front();
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        (!self.ranges.is_empty())
+            .then(|| self.ranges.iter().sum::<f64>() / self.ranges.len() as f64)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 struct RestingOrder {
     order: EngineOrder,
     kind: RestingKind,
     placed_at: u64,
+    /// Size already filled; `order.size` is what is left.
+    filled: f64,
 }
 
 enum FetchWindowEvent {
@@ -101,7 +199,7 @@ struct WorkerResult {
 }
 
 #[derive(Clone, Debug)]
-struct BacktestSeries {
+pub(super) struct BacktestSeries {
     asset: Arc<str>,
     tf: TimeFrame,
     prices: Vec<Price>,
@@ -109,11 +207,91 @@ struct BacktestSeries {
 }
 
 #[derive(Clone, Copy, Debug)]
-struct SeriesEvent {
+pub(super) struct SeriesEvent {
     series_idx: usize,
     price: Price,
 }
 
+/// Loaded series plus the replay position of one run.
+pub(super) struct SimCursor {
+    run_id: String,
+    series_data: Vec<BacktestSeries>,
+    primary_series_idx: usize,
+    next_indices: Vec<usize>,
+    current_execution_candle: Option<Price>,
+    last_sim_candle: Option<Price>,
+    sim_started: bool,
+    /// 1m candles of the execution asset, replayed inside ambiguous bars.
+    intrabar: Vec<Price>,
+    intrabar_idx: usize,
+    warmup_loaded: u64,
+    pub(super) loaded_candles: u64,
+    pub(super) sim_processed: u64,
+    pub(super) sim_total: u64,
+}
+
+impl SimCursor {
+    /// Execution candles simulated so far.
+    pub(super) fn sim_candles(&self) -> &[Price] {
+        let Some(last) = self.last_sim_candle.filter(|_| self.sim_started) else {
+            return &[];
+        };
+        let primary = &self.series_data[self.primary_series_idx];
+        let end = primary
+            .prices
+            .partition_point(|price| price.open_time <= last.open_time);
+        &primary.prices[primary.first_sim_idx.min(end)..end]
+    }
+
+    pub(super) fn peek_ts(&self) -> Option<u64> {
+        peek_next_ts(&self.series_data, &self.next_indices)
+    }
+
+    pub(super) fn next_batch_at(&mut self, ts: u64) -> Vec<SeriesEvent> {
+        next_event_batch_at(&self.series_data, &mut self.next_indices, ts)
+    }
+
+    pub(super) fn set_last_sim_candle(&mut self, candle: Price) {
+        self.last_sim_candle = Some(candle);
+    }
+
+    /// Cursor over `prices` alone, without warmup or intrabar data.
+    #[cfg(test)]
+    pub(super) fn from_prices(asset: &str, tf: TimeFrame, prices: Vec<Price>) -> Self {
+        let sim_total = prices.len() as u64;
+        Self {
+            run_id: "test".to_string(),
+            series_data: vec![BacktestSeries {
+                asset: Arc::from(asset),
+                tf,
+                prices,
+                first_sim_idx: 0,
+            }],
+            primary_series_idx: 0,
+            next_indices: vec![0],
+            current_execution_candle: None,
+            last_sim_candle: None,
+            sim_started: false,
+            intrabar: Vec::new(),
+            intrabar_idx: 0,
+            warmup_loaded: 0,
+            loaded_candles: sim_total,
+            sim_processed: 0,
+            sim_total,
+        }
+    }
+
+    /// Lower-timeframe candles inside `candle`; empty when intrabar data is off.
+    pub(super) fn intrabar_for(&mut self, candle: Price) -> &[Price] {
+        let start = self.intrabar_idx
+            + self.intrabar[self.intrabar_idx..]
+                .partition_point(|p| p.open_time < candle.open_time);
+        let len = self.intrabar[start..].partition_point(|p| p.open_time < candle.close_time);
+        self.intrabar_idx = start + len;
+        &self.intrabar[start..start + len]
+    }
+}
+
 pub struct Backtester {
     request: BacktestRunRequest,
     candle_store: Arc<super::candle_store::CandleStore>,
@@ -125,9 +303,33 @@ pub struct Backtester {
     position: Option<PositionState>,
     resting_orders: HashMap<u64, RestingOrder>,
     trades: Vec<TradeInfo>,
+    /// MAE/MFE of each closed trade, aligned with `trades`.
+    excursions: Vec<TradeExcursion>,
     equity_curve: Vec<EquityPoint>,
     snapshots: Vec<PositionSnapshot>,
     next_funding_time: Option<u64>,
+    /// Recorded settlements when `historical_funding` is on; empty falls back
+    /// to the flat `funding_rate_bps_per_8h`.
+    funding_rates: Vec<FundingRate>,
+    funding_idx: usize,
+    pending_market: Vec<PendingMarket>,
+    /// Trigger moves decided on the bar being settled, with the open time of
+    /// the position they were sent for. They apply once the bar has settled,
+    /// so the triggers they replace still guard it.
+    deferred_triggers: Vec<(TriggerReplace, u64)>,
+    atr: AtrWindow,
+    slippage_cost: f64,
+    intrabar_resolved_bars: usize,
+    script_logs: Vec<ScriptLogEntry>,
+    script_logs_dropped: usize,
+    cancel: CancellationToken,
+    /// Intents emitted since the last drain; only collected for stepping sessions.
+    intent_trace: Option<Vec<StepIntent>>,
+    /// Draws for `TouchFill::Probability`.
+    fill_rng: StdRng,
+    /// Margin the strategy sizes from when the book trades out of a shared
+    /// account; the book's own balance otherwise.
+    account_margin: Option<f64>,
 }
 
 impl Backtester {
@@ -138,9 +340,8 @@ impl Backtester {
         store: Arc<LocalStore>,
         candle_store: Arc<super::candle_store::CandleStore>,
     ) -> Result<Self, Error> {
+        request.config.validate().map_err(Error::Custom)?;
         let sid = request.config.strategy_id;
-        let margin = request.config.margin;
-        let lev = request.config.lev;
         // Cache is kept in sync on save/update/delete — prefer it over DB
         let (compiled, strat_indicators) = {
             let cached = {
@@ -158,12 +359,12 @@ impl Backtester {
                     .map_err(|e| Error::Custom(format!("local storage error: {e}")))?
                     .ok_or_else(|| Error::Custom(format!("strategy {sid} not found")))?;
 
-                let state_decls: Option<crate::backend::scripting::StateDeclarations> = row
+                let state_decls: Option<StateDeclarations> = row
                     .state_declarations
                     .as_ref()
                     .and_then(|v| serde_json::from_value(v.clone()).ok());
 
-                let compiled = crate::backend::scripting::compile_strategy(
+                let compiled = compile_strategy(
                     &rhai_engine,
                     &row.on_idle,
                     &row.on_open,
@@ -172,7 +373,7 @@ impl Backtester {
                 )
                 .map_err(|e| Error::Custom(format!("strategy {sid} failed to compile: {e}")))?;
 
-                let indicators: Vec<crate::IndexId> =
+                let indicators: Vec<IndexId> =
                     serde_json::from_value(row.indicators).unwrap_or_default();
 
                 {
@@ -192,6 +393,26 @@ impl Backtester {
             }
         };
 
+        Ok(Self::new(
+            request,
+            rhai_engine,
+            compiled,
+            strat_indicators,
+            candle_store,
+        ))
+    }
+
+    /// Build a backtester around an already compiled strategy, e.g. one with
+    /// parameter overrides applied by the optimizer.
+    pub fn new(
+        request: BacktestRunRequest,
+        rhai_engine: Arc<Engine>,
+        compiled: CompiledStrategy,
+        strat_indicators: Vec<IndexId>,
+        candle_store: Arc<super::candle_store::CandleStore>,
+    ) -> Self {
+        let margin = request.config.margin;
+        let lev = request.config.lev;
         let mut strat_indicators = strat_indicators;
         replace_self_with_asset(request.config.asset.as_str(), &mut strat_indicators);
         let required_series = collect_required_series(
@@ -208,8 +429,9 @@ impl Backtester {
             strat_indicators,
             request.config.asset.clone().into(),
         );
+        let fill_rng = limit_fill_rng(&request.config.fill_model.limit);
 
-        Ok(Self {
+        Self {
             request,
             candle_store,
             engine,
@@ -220,16 +442,36 @@ impl Backtester {
             position: None,
             resting_orders: HashMap::new(),
             trades: Vec::new(),
+            excursions: Vec::new(),
             equity_curve: Vec::new(),
             snapshots: Vec::new(),
             next_funding_time: None,
-        })
+            funding_rates: Vec::new(),
+            funding_idx: 0,
+            pending_market: Vec::new(),
+            deferred_triggers: Vec::new(),
+            atr: AtrWindow::default(),
+            slippage_cost: 0.0,
+            intrabar_resolved_bars: 0,
+            script_logs: Vec::new(),
+            script_logs_dropped: 0,
+            cancel: CancellationToken::new(),
+            intent_trace: None,
+            fill_rng,
+            account_margin: None,
+        }
     }
 
     pub fn request(&self) -> &BacktestRunRequest {
         &self.request
     }
 
+    /// Cancelling `token` stops the fetch workers or the candle loop and makes
+    /// the run return an error after reporting `BacktestProgress::Cancelled`.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
     pub async fn run(&mut self) -> Result<BacktestResult, Error> {
         self.run_with_progress(|_| {}).await
     }
@@ -241,17 +483,138 @@ impl Backtester {
     where
         F: FnMut(BacktestProgress),
     {
-        self.reset_runtime();
-        on_progress(BacktestProgress::Initializing);
-
         let started_at = get_time_now();
-        let cfg = self.request.config.clone();
         let run_id = self
             .request
             .run_id
             .clone()
             .filter(|id| !id.trim().is_empty())
-            .unwrap_or_else(|| format!("bt-{}-{started_at}", cfg.asset));
+            .unwrap_or_else(|| format!("bt-{}-{started_at}", self.request.config.asset));
+
+        let mut cursor = self.prepare(&run_id, &mut on_progress).await?;
+        let sim_total = cursor.sim_total;
+        let sim_log_step = (sim_total / 10).max(1);
+        let mut next_sim_log = sim_log_step;
+        let mut stopped_by_liquidation = false;
+
+        while let Some(batch) = next_event_batch(&cursor.series_data, &mut cursor.next_indices) {
+            if self.cancel.is_cancelled() {
+                return Err(self.cancelled(&run_id, &cursor, &mut on_progress));
+            }
+            if let Some(candle) = self.step(&mut cursor, batch) {
+                let liquidated = self.process_candle(candle, cursor.intrabar_for(candle));
+                cursor.last_sim_candle = Some(candle);
+                if liquidated {
+                    stopped_by_liquidation = true;
+                    info!(
+                        "backtest[{run_id}] liquidation stop at candle_ts={} processed={}",
+                        candle.open_time, cursor.sim_processed
+                    );
+                    break;
+                }
+            }
+
+            let sim_processed = cursor.sim_processed;
+            if sim_processed.is_multiple_of(200) || sim_processed >= sim_total {
+                on_progress(BacktestProgress::Simulating {
+                    processed: sim_processed,
+                    total: sim_total,
+                });
+                maybe_log_milestone(
+                    &run_id,
+                    "simulating",
+                    sim_processed,
+                    sim_total,
+                    &mut next_sim_log,
+                    sim_log_step,
+                );
+            }
+        }
+
+        if let Err(err) = self.finish(&cursor, stopped_by_liquidation) {
+            on_progress(BacktestProgress::Failed {
+                message: err.to_string(),
+            });
+            return Err(err);
+        }
+
+        on_progress(BacktestProgress::Simulating {
+            processed: cursor.sim_processed,
+            total: sim_total,
+        });
+        maybe_log_milestone(
+            &run_id,
+            "simulating",
+            cursor.sim_processed,
+            sim_total,
+            &mut next_sim_log,
+            sim_log_step,
+        );
+
+        on_progress(BacktestProgress::Finalizing);
+        info!(
+            "backtest[{run_id}] finalizing loaded={} processed={} trades={} open_position={} resting_orders={}",
+            cursor.loaded_candles,
+            cursor.sim_processed,
+            self.trades.len(),
+            self.position.is_some(),
+            self.resting_orders.len()
+        );
+
+        let finished_at = get_time_now();
+        let result = self.build_result(started_at, finished_at, &cursor);
+
+        info!(
+            "backtest[{run_id}] done loaded={} processed={} trades={} net_pnl={:.6} return_pct={:.4} snapshots={} equity_points={}",
+            result.candles_loaded,
+            result.candles_processed,
+            result.summary.total_trades,
+            result.summary.net_pnl,
+            result.summary.return_pct,
+            result.snapshots.len(),
+            result.equity_curve.len()
+        );
+        on_progress(BacktestProgress::Done);
+        Ok(result)
+    }
+
+    fn cancelled<F>(&self, run_id: &str, cursor: &SimCursor, on_progress: &mut F) -> Error
+    where
+        F: FnMut(BacktestProgress),
+    {
+        info!(
+            "backtest[{run_id}] cancelled processed={} total={}",
+            cursor.sim_processed, cursor.sim_total
+        );
+        on_progress(BacktestProgress::Cancelled {
+            processed: cursor.sim_processed,
+        });
+        Error::Custom("Backtest cancelled".to_string())
+    }
+
+    fn cancelled_before_sim<F>(&self, run_id: &str, on_progress: &mut F) -> Error
+    where
+        F: FnMut(BacktestProgress),
+    {
+        info!("backtest[{run_id}] cancelled while warming the engine");
+        on_progress(BacktestProgress::Cancelled { processed: 0 });
+        Error::Custom("Backtest cancelled".to_string())
+    }
+
+    /// Reset the simulator, load every required series and warm the engine.
+    /// The returned cursor sits on the first simulation candle.
+    pub(super) async fn prepare<F>(
+        &mut self,
+        run_id: &str,
+        on_progress: &mut F,
+    ) -> Result<SimCursor, Error>
+    where
+        F: FnMut(BacktestProgress),
+    {
+        self.reset_runtime();
+        on_progress(BacktestProgress::Initializing);
+
+        let cfg = self.request.config.clone();
         let execution_asset: Arc<str> = Arc::from(cfg.asset.as_str());
         let tf = cfg.resolution;
         let sim_start = cfg.start_time;
@@ -267,6 +630,8 @@ impl Backtester {
 
         let warmup_target = self.request.warmup_candles;
         let fetch_end = sim_end;
+        let intrabar_tf = (cfg.intrabar_resolution && tf_ms > TimeFrame::Min1.to_millis())
+            .then_some(TimeFrame::Min1);
         let fetch_total = self
             .required_series
             .iter()
@@ -277,6 +642,11 @@ impl Backtester {
                 estimate_candle_count(fetch_start, fetch_end, series_tf_ms).max(1)
             })
             .sum::<u64>()
+            .saturating_add(
+                intrabar_tf
+                    .map(|sub_tf| estimate_candle_count(sim_start, sim_end, sub_tf.to_millis()))
+                    .unwrap_or(0),
+            )
             .max(1);
         let sim_total = self
             .required_series
@@ -289,8 +659,6 @@ impl Backtester {
         let warmup_total = warmup_target.saturating_mul(self.required_series.len() as u64);
         let loading_log_step = (fetch_total / 10).max(1);
         let mut next_loading_log = loading_log_step;
-        let sim_log_step = (sim_total / 10).max(1);
-        let mut next_sim_log = sim_log_step;
 
         info!(
             "backtest[{run_id}] start asset={} source={:?} exec_tf={:?} sim={}..{} warmup={} required_series=[{}] est_fetch={} est_sim={} workers={} rps={}",
@@ -335,7 +703,7 @@ impl Backtester {
             let series_fetch_start =
                 sim_start.saturating_sub(warmup_target.saturating_mul(series_tf_ms));
             let series_prices = fetch_series_history_with_progress(
-                &run_id,
+                run_id,
                 cfg.source.clone(),
                 Arc::clone(series_asset),
                 *series_tf,
@@ -347,7 +715,8 @@ impl Backtester {
                 &mut loading_reported,
                 &mut next_loading_log,
                 loading_log_step,
-                &mut on_progress,
+                &self.cancel,
+                on_progress,
             )
             .await?;
 
@@ -383,6 +752,54 @@ impl Backtester {
             });
         }
 
+        let mut intrabar = Vec::new();
+        if let Some(sub_tf) = intrabar_tf {
+            intrabar = fetch_series_history_with_progress(
+                run_id,
+                cfg.source.clone(),
+                Arc::clone(&execution_asset),
+                sub_tf,
+                sim_start,
+                fetch_end,
+                loaded_candles,
+                fetch_total,
+                self.candle_store.clone(),
+                &mut loading_reported,
+                &mut next_loading_log,
+                loading_log_step,
+                &self.cancel,
+                on_progress,
+            )
+            .await?;
+            if intrabar.is_empty() {
+                warn!("backtest[{run_id}] intrabar resolution enabled but no 1m candles loaded");
+            }
+            loaded_candles = loaded_candles.saturating_add(intrabar.len() as u64);
+        }
+
+        if cfg.historical_funding {
+            let mut fetcher = Fetcher::new(cfg.source.clone(), self.candle_store.clone());
+            fetcher.set_request_limiter(RequestLimiter::from_requests_per_second(
+                MAX_FETCH_REQUESTS_PER_SEC,
+            ));
+            match fetcher.fetch_funding(&cfg.asset, sim_start, sim_end).await {
+                Ok(rates) if !rates.is_empty() => {
+                    info!(
+                        "backtest[{run_id}] loaded {} historical funding rates",
+                        rates.len()
+                    );
+                    self.funding_rates = rates;
+                }
+                Ok(_) => warn!(
+                    "backtest[{run_id}] no historical funding for {}; using flat rate",
+                    cfg.asset
+                ),
+                Err(err) => warn!(
+                    "backtest[{run_id}] historical funding unavailable ({err}); using flat rate"
+                ),
+            }
+        }
+
         let loaded_now = loaded_candles.min(fetch_total);
         if loaded_now > loading_reported {
             loading_reported = loaded_now;
@@ -392,7 +809,7 @@ impl Backtester {
             });
         }
         maybe_log_milestone(
-            &run_id,
+            run_id,
             "loading",
             loading_reported,
             fetch_total,
@@ -413,6 +830,9 @@ impl Backtester {
 
         let mut warmup_loaded = 0_u64;
         for series in &series_data {
+            if self.cancel.is_cancelled() {
+                return Err(self.cancelled_before_sim(run_id, on_progress));
+            }
             let warmup_start_idx = series.first_sim_idx.saturating_sub(warmup_target as usize);
             let warmup_slice = &series.prices[warmup_start_idx..series.first_sim_idx];
             if !warmup_slice.is_empty() {
@@ -428,6 +848,9 @@ impl Backtester {
                 });
             }
         }
+        if self.cancel.is_cancelled() {
+            return Err(self.cancelled_before_sim(run_id, on_progress));
+        }
         on_progress(BacktestProgress::WarmingEngine {
             loaded: warmup_loaded,
             total: warmup_total,
@@ -437,137 +860,122 @@ impl Backtester {
             total: sim_total,
         });
 
-        let mut sim_started = false;
-        let mut sim_processed = 0_u64;
-        let mut last_sim_candle = series_data[primary_series_idx]
+        let primary = &series_data[primary_series_idx];
+        let last_sim_candle = primary
             .prices
-            .get(
-                series_data[primary_series_idx]
-                    .first_sim_idx
-                    .saturating_sub(1),
-            )
+            .get(primary.first_sim_idx.saturating_sub(1))
             .copied();
-        let mut current_execution_candle = last_sim_candle;
-        let mut stopped_by_liquidation = false;
-        let mut next_indices: Vec<usize> = series_data
+        let next_indices = series_data
             .iter()
             .map(|series| series.first_sim_idx)
             .collect();
 
-        while let Some(batch) = next_event_batch(&series_data, &mut next_indices) {
-            let primary_candle = batch
-                .iter()
-                .find_map(|event| (event.series_idx == primary_series_idx).then_some(event.price));
-
-            if let Some(candle) = primary_candle {
-                current_execution_candle = Some(candle);
-                if !sim_started {
-                    self.init_funding(candle.open_time);
-                    info!(
-                        "backtest[{run_id}] simulation started warmup_loaded={} first_sim_ts={} sim_total={}",
-                        warmup_loaded, candle.open_time, sim_total
-                    );
-                    sim_started = true;
-                }
-                self.apply_funding_if_due(candle);
-                self.sync_engine_position();
-            }
+        Ok(SimCursor {
+            run_id: run_id.to_string(),
+            series_data,
+            primary_series_idx,
+            next_indices,
+            current_execution_candle: last_sim_candle,
+            last_sim_candle,
+            sim_started: false,
+            intrabar,
+            intrabar_idx: 0,
+            warmup_loaded,
+            loaded_candles,
+            sim_processed: 0,
+            sim_total,
+        })
+    }
 
-            let Some(execution_candle) = current_execution_candle else {
-                continue;
-            };
+    /// Feed one timestamp batch to the engine. Returns the execution candle when
+    /// the batch contains one; the caller settles it against the book.
+    pub(super) fn step(
+        &mut self,
+        cursor: &mut SimCursor,
+        batch: Vec<SeriesEvent>,
+    ) -> Option<Price> {
+        let primary_candle = batch.iter().find_map(|event| {
+            (event.series_idx == cursor.primary_series_idx).then_some(event.price)
+        });
 
-            for event in batch {
-                let series = &series_data[event.series_idx];
-                let actions = self.engine.tick_backtest(
-                    &series.asset,
-                    series.tf,
-                    event.price,
-                    execution_candle,
+        if let Some(candle) = primary_candle {
+            cursor.current_execution_candle = Some(candle);
+            if !cursor.sim_started {
+                self.init_funding(candle.open_time);
+                info!(
+                    "backtest[{}] simulation started warmup_loaded={} first_sim_ts={} sim_total={}",
+                    cursor.run_id, cursor.warmup_loaded, candle.open_time, cursor.sim_total
                 );
-                self.apply_engine_actions(actions, execution_candle);
-                sim_processed = sim_processed.saturating_add(1);
-            }
-
-            if let Some(candle) = primary_candle {
-                let liquidated = self.process_candle(candle);
-                last_sim_candle = Some(candle);
-                if liquidated {
-                    stopped_by_liquidation = true;
-                    info!(
-                        "backtest[{run_id}] liquidation stop at candle_ts={} processed={}",
-                        candle.open_time, sim_processed
-                    );
-                    break;
-                }
+                cursor.sim_started = true;
             }
-
-            if sim_processed.is_multiple_of(200) || sim_processed >= sim_total {
-                on_progress(BacktestProgress::Simulating {
-                    processed: sim_processed,
-                    total: sim_total,
-                });
-                maybe_log_milestone(
-                    &run_id,
-                    "simulating",
-                    sim_processed,
-                    sim_total,
-                    &mut next_sim_log,
-                    sim_log_step,
-                );
+            self.apply_funding_if_due(candle);
+            self.fill_pending_market(candle);
+            if let SlippageModel::Atr { period, .. } = self.request.config.fill_model.slippage {
+                self.atr.push(candle, period);
             }
+            self.sync_engine_position();
         }
 
-        if !sim_started {
-            let err = Error::Custom("No simulation candles left after warmup".to_string());
-            on_progress(BacktestProgress::Failed {
-                message: err.to_string(),
+        let execution_candle = cursor.current_execution_candle?;
+
+        for event in batch {
+            let series = &cursor.series_data[event.series_idx];
+            let (actions, output) = capture_script_output(|| {
+                self.engine
+                    .tick_backtest(&series.asset, series.tf, event.price, execution_candle)
             });
-            return Err(err);
+            self.record_script_output(output, execution_candle.open_time);
+            if let Some(trace) = self.intent_trace.as_mut()
+                && let Some(intent) = self.engine.last_intent()
+            {
+                trace.push(StepIntent::new(
+                    execution_candle.open_time,
+                    &series.asset,
+                    series.tf,
+                    intent,
+                ));
+            }
+            self.apply_engine_actions(actions, execution_candle);
+            cursor.sim_processed = cursor.sim_processed.saturating_add(1);
         }
-
-        if !stopped_by_liquidation {
-            self.finalize_open_position_at_end(last_sim_candle);
+        // Without an execution candle there is no bar left to settle first.
+        if primary_candle.is_none() {
+            self.apply_deferred_triggers(execution_candle);
         }
 
-        on_progress(BacktestProgress::Simulating {
-            processed: sim_processed,
-            total: sim_total,
-        });
-        maybe_log_milestone(
-            &run_id,
-            "simulating",
-            sim_processed,
-            sim_total,
-            &mut next_sim_log,
-            sim_log_step,
-        );
+        primary_candle
+    }
 
-        on_progress(BacktestProgress::Finalizing);
-        info!(
-            "backtest[{run_id}] finalizing loaded={} processed={} trades={} open_position={} resting_orders={}",
-            loaded_candles,
-            sim_processed,
-            self.trades.len(),
-            self.position.is_some(),
-            self.resting_orders.len()
+    fn record_script_output(&mut self, output: Vec<(ScriptLogKind, String)>, ts: u64) {
+        let room = self
+            .request
+            .config
+            .max_script_logs
+            .saturating_sub(self.script_logs.len());
+        self.script_logs_dropped += output.len().saturating_sub(room);
+        self.script_logs.extend(
+            output
+                .into_iter()
+                .take(room)
+                .map(|(kind, msg)| ScriptLogEntry { ts, kind, msg }),
         );
+    }
 
-        let finished_at = get_time_now();
-        let result = self.build_result(started_at, finished_at, loaded_candles, sim_processed);
-
-        info!(
-            "backtest[{run_id}] done loaded={} processed={} trades={} net_pnl={:.6} return_pct={:.4} snapshots={} equity_points={}",
-            result.candles_loaded,
-            result.candles_processed,
-            result.summary.total_trades,
-            result.summary.net_pnl,
-            result.summary.return_pct,
-            result.snapshots.len(),
-            result.equity_curve.len()
-        );
-        on_progress(BacktestProgress::Done);
-        Ok(result)
+    /// Close anything still open at the last simulated candle.
+    pub(super) fn finish(
+        &mut self,
+        cursor: &SimCursor,
+        stopped_by_liquidation: bool,
+    ) -> Result<(), Error> {
+        if !cursor.sim_started {
+            return Err(Error::Custom(
+                "No simulation candles left after warmup".to_string(),
+            ));
+        }
+        if !stopped_by_liquidation {
+            self.finalize_open_position_at_end(cursor.last_sim_candle);
+        }
+        Ok(())
     }
 
     fn reset_runtime(&mut self) {
@@ -575,12 +983,26 @@ impl Backtester {
         self.position = None;
         self.resting_orders.clear();
         self.trades.clear();
+        self.excursions.clear();
         self.equity_curve.clear();
         self.snapshots.clear();
+        self.script_logs.clear();
+        self.script_logs_dropped = 0;
+        if let Some(trace) = self.intent_trace.as_mut() {
+            trace.clear();
+        }
         self.next_order_id = 1;
         self.next_snapshot_id = 1;
         self.balance = self.request.config.margin;
         self.next_funding_time = None;
+        self.funding_rates.clear();
+        self.funding_idx = 0;
+        self.pending_market.clear();
+        self.deferred_triggers.clear();
+        self.atr = AtrWindow::default();
+        self.slippage_cost = 0.0;
+        self.intrabar_resolved_bars = 0;
+        self.fill_rng = limit_fill_rng(&self.request.config.fill_model.limit);
     }
 
     fn apply_engine_actions(&mut self, actions: Vec<BtAction>, execution_candle: Price) {
@@ -593,20 +1015,194 @@ impl Backtester {
         }
     }
 
-    fn process_candle(&mut self, candle: Price) -> bool {
-        let fills = self.fill_resting_orders(candle);
+    pub(super) fn process_candle(&mut self, candle: Price, intrabar: &[Price]) -> bool {
+        match self.settle_intrabar(candle, intrabar, true) {
+            Some(true) => return true,
+            Some(false) => {}
+            None => {
+                self.settle_candle(candle, candle.open_time);
+                if self.apply_liquidation_if_touched(candle) {
+                    return true;
+                }
+            }
+        }
+        self.apply_deferred_triggers(candle);
+        self.mark_candle(candle);
+        false
+    }
+
+    /// Settle a bar without the per-book liquidation check.
+    pub(super) fn settle_bar(&mut self, candle: Price, intrabar: &[Price]) {
+        if self.settle_intrabar(candle, intrabar, false).is_none() {
+            self.settle_candle(candle, candle.open_time);
+        }
+        self.apply_deferred_triggers(candle);
+    }
+
+    /// Apply the trigger moves held back while `candle` settled. Moves meant
+    /// for a position that has since closed are dropped.
+    fn apply_deferred_triggers(&mut self, candle: Price) {
+        for (replace, open_time) in std::mem::take(&mut self.deferred_triggers) {
+            if self.position.is_some_and(|pos| pos.open_time == open_time)
+                && self.replace_triggers(replace, candle.open_time)
+            {
+                self.capture_snapshot(candle, SnapshotReason::TriggerUpdate);
+            }
+        }
+    }
+
+    /// Replay an ambiguous bar on its lower-timeframe candles so the order of
+    /// trigger, limit and liquidation touches is known. Returns `None` when the
+    /// bar should be settled as a whole, otherwise whether it liquidated.
+    fn settle_intrabar(
+        &mut self,
+        candle: Price,
+        intrabar: &[Price],
+        check_liquidation: bool,
+    ) -> Option<bool> {
+        if intrabar.is_empty() || !self.bar_is_ambiguous(candle, check_liquidation) {
+            return None;
+        }
+        self.intrabar_resolved_bars += 1;
+        for sub in intrabar {
+            // Orders sent on this bar's signal came after all of its minutes.
+            self.settle_candle(*sub, candle.open_time);
+            if check_liquidation && self.apply_liquidation_if_touched(*sub) {
+                return Some(true);
+            }
+        }
+        Some(false)
+    }
+
+    /// More than one resting order (or an order and the liquidation price)
+    /// sits inside the bar's range.
+    fn bar_is_ambiguous(&self, candle: Price, check_liquidation: bool) -> bool {
+        let pos_side = self.position.map(|p| p.side);
+        let mut touched = self
+            .resting_orders
+            .values()
+            .filter(|resting| resting.placed_at < candle.open_time)
+            .filter(|resting| {
+                resting.order.limit.is_some_and(|limit| {
+                    let above = is_trigger_above_market(&resting.order, pos_side, candle.open);
+                    trigger_hit(candle, limit.limit_px, above)
+                })
+            })
+            .count();
+        if check_liquidation
+            && let Some(side) = pos_side
+            && let Some(liq_px) = self.liquidation_price()
+        {
+            let liq_touched = match side {
+                Side::Long => candle.low <= liq_px,
+                Side::Short => candle.high >= liq_px,
+            };
+            touched += liq_touched as usize;
+        }
+        touched >= 2
+    }
+
+    /// Fill resting orders touched by `candle`. Only orders placed before
+    /// `bar_open`, the open of the bar being settled, are eligible.
+    fn settle_candle(&mut self, candle: Price, bar_open: u64) {
+        let fills = self.fill_resting_orders(candle, bar_open);
         if fills > 0 {
             self.sync_engine_position();
             self.capture_snapshot(candle, SnapshotReason::Fill);
         }
+    }
 
-        if self.apply_liquidation_if_touched(candle) {
-            return true;
+    /// Record the post-candle equity point.
+    pub(super) fn mark_candle(&mut self, candle: Price) {
+        // A market entry fills at the bar's close; none of the bar's range
+        // happened while it was open.
+        if let Some(pos) = self.position.as_mut()
+            && pos.open_time < candle.close_time
+        {
+            pos.track_excursion(candle.high, candle.low);
         }
-
         self.sync_engine_position();
         self.push_equity_point(candle);
-        false
+    }
+
+    pub(super) fn balance(&self) -> f64 {
+        self.balance
+    }
+
+    pub(super) fn engine(&self) -> &SignalEngine {
+        &self.engine
+    }
+
+    pub(super) fn trades(&self) -> &[TradeInfo] {
+        &self.trades
+    }
+
+    pub(super) fn script_logs(&self) -> &[ScriptLogEntry] {
+        &self.script_logs
+    }
+
+    pub(super) fn open_position(&self) -> Option<OpenPositionLocal> {
+        self.position.map(|p| p.to_open_position_local())
+    }
+
+    /// Start recording every intent the strategy emits.
+    pub(super) fn trace_intents(&mut self) {
+        self.intent_trace.get_or_insert_with(Vec::new);
+    }
+
+    pub(super) fn take_traced_intents(&mut self) -> Vec<StepIntent> {
+        self.intent_trace
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// The candle extreme that hurts the open position most.
+    pub(super) fn adverse_px(&self, candle: Price) -> f64 {
+        match self.position.map(|pos| pos.side) {
+            Some(Side::Short) => candle.high,
+            _ => candle.low,
+        }
+    }
+
+    /// Initial margin of the open position at `mark_px` and the book's leverage.
+    pub(super) fn position_margin(&self, mark_px: f64) -> f64 {
+        let Some(pos) = self.position else {
+            return 0.0;
+        };
+        pos.size * mark_px / self.request.config.lev.max(1) as f64
+    }
+
+    /// Size the strategy from `margin` instead of the book's own balance,
+    /// for books drawing on a shared account.
+    pub(super) fn set_account_margin(&mut self, margin: f64) {
+        self.account_margin = Some(margin);
+    }
+
+    /// Unrealised PnL of closing the position at `mark_px`, net of the taker fee.
+    pub(super) fn close_out_upnl(&self, mark_px: f64) -> f64 {
+        let Some(pos) = self.position else {
+            return 0.0;
+        };
+        self.unrealised_pnl(mark_px) - self.calc_fee(mark_px, pos.size, FillType::Liquidation)
+    }
+
+    /// Close the position at the candle's adverse extreme as a liquidation.
+    /// Used when the account, not this book alone, runs out of margin.
+    pub(super) fn force_liquidate(&mut self, candle: Price) {
+        self.resting_orders.clear();
+        if self.position.is_none() {
+            return;
+        }
+        let px = self.adverse_px(candle);
+        if self
+            .fill_close_at_px(None, px, candle.open_time, FillType::Liquidation)
+            .is_some()
+        {
+            self.sync_engine_position();
+            self.capture_snapshot(candle, SnapshotReason::ForceClose);
+        }
+        self.push_equity_point(candle);
     }
 
     fn apply_action(&mut self, action: BtAction, candle: Price) {
@@ -625,7 +1221,7 @@ impl Backtester {
                 });
                 match order {
                     BtOrder::Open(open) => {
-                        if self.position.is_none() {
+                        if self.position.is_none() && !self.has_pending_open() {
                             self.submit_open_order(open, intent, candle);
                         }
                     }
@@ -637,23 +1233,24 @@ impl Backtester {
             }
             BtAction::ForceCloseMarket => {
                 self.resting_orders.clear();
-                let _ =
-                    self.fill_close_at_px(None, candle.close, candle.close_time, FillType::Market);
+                self.market_close(None, candle);
+            }
+            BtAction::ReplaceTriggers(replace) => {
+                if let Some(pos) = self.position {
+                    self.deferred_triggers.push((replace, pos.open_time));
+                }
+            }
+            BtAction::CancelResting(op) => {
+                self.resting_orders.retain(|_, resting| {
+                    resting.order.is_tpsl().is_some() || resting.order.action != op
+                });
             }
         }
     }
 
-    fn submit_open_order(&mut self, open: OpenOrder, _intent: BtIntent, candle: Price) {
+    fn submit_open_order(&mut self, open: OpenOrder, intent: BtIntent, candle: Price) {
         if open.order.limit.is_none() {
-            self.fill_open_at_px(
-                open.order,
-                candle.close,
-                candle.close_time,
-                FillType::Market,
-            );
-            if let Some(triggers) = open.triggers {
-                self.attach_triggers_after_open(triggers, open.order.size, candle.close_time);
-            }
+            self.market_open(open, intent == BtIntent::Add, candle);
             return;
         }
 
@@ -664,20 +1261,17 @@ impl Backtester {
                 order: open.order,
                 kind: RestingKind::Open {
                     triggers: open.triggers,
+                    add: intent == BtIntent::Add,
                 },
                 placed_at: candle.open_time,
+                filled: 0.0,
             },
         );
     }
 
     fn submit_close_order(&mut self, close: CloseOrder, _intent: BtIntent, candle: Price) {
         if close.order.limit.is_none() {
-            let _ = self.fill_close_at_px(
-                Some(close.order),
-                candle.close,
-                candle.close_time,
-                FillType::Market,
-            );
+            self.market_close(Some(close.order), candle);
             return;
         }
 
@@ -688,11 +1282,126 @@ impl Backtester {
                 order: close.order,
                 kind: RestingKind::Close,
                 placed_at: candle.open_time,
+                filled: 0.0,
             },
         );
     }
 
-    fn fill_resting_orders(&mut self, candle: Price) -> usize {
+    fn market_open(&mut self, open: OpenOrder, add: bool, candle: Price) {
+        if self.request.config.fill_model.latency == LatencyModel::NextOpen {
+            self.pending_market.push(PendingMarket::Open { open, add });
+            return;
+        }
+        self.fill_market_open(open, candle, candle.close, candle.close_time);
+    }
+
+    fn market_close(&mut self, order: Option<EngineOrder>, candle: Price) {
+        if self.request.config.fill_model.latency == LatencyModel::NextOpen {
+            self.pending_market.push(PendingMarket::Close(order));
+            return;
+        }
+        self.fill_market_close(order, candle, candle.close, candle.close_time);
+    }
+
+    fn fill_market_open(&mut self, open: OpenOrder, candle: Price, ref_px: f64, ts: u64) {
+        let buy = matches!(open.order.action, PositionOp::OpenLong);
+        let px = self.slipped_px(ref_px, buy, open.order.size, candle);
+        self.fill_open_at_px(open.order, px, ts, FillType::Market);
+        // Placed on the fill bar, so they guard every bar after it even when
+        // the fill is stamped with the close, which is the next bar's open.
+        if let Some(triggers) = open.triggers {
+            self.attach_triggers_after_open(triggers, open.order.size, candle.open_time);
+        }
+    }
+
+    fn fill_market_close(
+        &mut self,
+        order: Option<EngineOrder>,
+        candle: Price,
+        ref_px: f64,
+        ts: u64,
+    ) {
+        let Some(pos) = self.position else {
+            return;
+        };
+        let size = order.map(|o| o.size).unwrap_or(pos.size).min(pos.size);
+        let px = self.slipped_px(ref_px, pos.side == Side::Short, size, candle);
+        let _ = self.fill_close_at_px(order, px, ts, FillType::Market);
+    }
+
+    /// Orders delayed by `LatencyModel::NextOpen` fill at this candle's open.
+    fn fill_pending_market(&mut self, candle: Price) {
+        if self.pending_market.is_empty() {
+            return;
+        }
+        for pending in std::mem::take(&mut self.pending_market) {
+            match pending {
+                PendingMarket::Open { open, add } => {
+                    // Same rule `validate_trade` applied at signal time: a fresh
+                    // open needs a flat book, an add the position it was sent for.
+                    let fillable = match self.position {
+                        None => !add,
+                        Some(pos) => {
+                            add && matches!(
+                                (pos.side, open.order.action),
+                                (Side::Long, PositionOp::OpenLong)
+                                    | (Side::Short, PositionOp::OpenShort)
+                            )
+                        }
+                    };
+                    if fillable {
+                        self.fill_market_open(open, candle, candle.open, candle.open_time);
+                    } else {
+                        warn!(
+                            "Dropping delayed market open: position changed before the next open"
+                        );
+                    }
+                }
+                PendingMarket::Close(order) => {
+                    self.fill_market_close(order, candle, candle.open, candle.open_time);
+                }
+            }
+        }
+        self.capture_snapshot(candle, SnapshotReason::Fill);
+    }
+
+    fn has_pending_open(&self) -> bool {
+        self.pending_market
+            .iter()
+            .any(|pending| matches!(pending, PendingMarket::Open { .. }))
+    }
+
+    /// Apply the configured slippage against the taker and book its cost.
+    fn slipped_px(&mut self, ref_px: f64, buy: bool, size: f64, candle: Price) -> f64 {
+        let slip_frac = match self.request.config.fill_model.slippage {
+            SlippageModel::None => 0.0,
+            SlippageModel::FixedBps { bps } => bps / 10_000.0,
+            SlippageModel::Atr { multiplier, .. } => match self.atr.value() {
+                Some(atr) if ref_px > EPSILON => multiplier * atr / ref_px,
+                _ => 0.0,
+            },
+            SlippageModel::VolumeImpact { impact_bps } => {
+                let participation = if candle.vlm > EPSILON {
+                    (size / candle.vlm).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                impact_bps / 10_000.0 * participation.sqrt()
+            }
+        };
+        if slip_frac <= 0.0 || !slip_frac.is_finite() {
+            return ref_px;
+        }
+        let px = if buy {
+            ref_px * (1.0 + slip_frac)
+        } else {
+            ref_px * (1.0 - slip_frac).max(0.0)
+        };
+        self.slippage_cost += (px - ref_px).abs() * size.max(0.0);
+        px
+    }
+
+    fn fill_resting_orders(&mut self, candle: Price, bar_open: u64) -> usize {
         let ids: Vec<u64> = self.resting_orders.keys().copied().collect();
         let mut fill_count = 0usize;
 
@@ -706,8 +1415,8 @@ impl Backtester {
             };
 
             // Prevent retroactive fills: newly-placed resting orders can only fill
-            // from the next candle onward.
-            if resting.placed_at >= candle.open_time {
+            // from the next bar onward, including on that bar's intrabar replay.
+            if resting.placed_at >= bar_open {
                 continue;
             }
 
@@ -719,19 +1428,53 @@ impl Backtester {
             let fill_px = trigger_fill_px(candle, limit.limit_px, above);
 
             let fill_type = order_fill_type(resting.order);
-            match resting.kind {
-                RestingKind::Open { triggers } => {
+            let fill_size = if fill_type == FillType::Limit {
+                limit_fill_size(
+                    &self.request.config.fill_model.limit,
+                    candle,
+                    limit.limit_px,
+                    above,
+                    resting.order.size,
+                    &mut self.fill_rng,
+                )
+            } else {
+                resting.order.size
+            };
+            if fill_size <= EPSILON {
+                continue;
+            }
+            let fill_order = EngineOrder {
+                size: fill_size,
+                ..resting.order
+            };
+
+            // Partial fills keep the rest of the order resting.
+            let remaining = resting.order.size - fill_size;
+            match self.resting_orders.get_mut(&id) {
+                Some(entry) if remaining > EPSILON => {
+                    entry.order.size = remaining;
+                    entry.filled += fill_size;
+                }
+                _ => {
                     let _ = self.resting_orders.remove(&id);
-                    self.fill_open_at_px(resting.order, fill_px, candle.open_time, fill_type);
+                }
+            }
+
+            match resting.kind {
+                RestingKind::Open { triggers, .. } => {
+                    self.fill_open_at_px(fill_order, fill_px, candle.open_time, fill_type);
                     if let Some(t) = triggers {
-                        self.attach_triggers_after_open(t, resting.order.size, candle.open_time);
+                        if resting.filled <= EPSILON {
+                            self.attach_triggers_after_open(t, fill_size, candle.open_time);
+                        } else {
+                            self.resize_triggers_to_position();
+                        }
                     }
                     fill_count += 1;
                 }
                 RestingKind::Close => {
-                    let _ = self.resting_orders.remove(&id);
                     if self
-                        .fill_close_at_px(Some(resting.order), fill_px, candle.open_time, fill_type)
+                        .fill_close_at_px(Some(fill_order), fill_px, candle.open_time, fill_type)
                         .is_some()
                     {
                         fill_count += 1;
@@ -775,6 +1518,8 @@ impl Backtester {
                 pos.realised_pnl -= fee;
                 pos.fees += fee;
                 self.position = Some(pos);
+                // Adds grow the position under the existing TP/SL.
+                self.resize_triggers_to_position();
             }
             None => {
                 self.position = Some(PositionState {
@@ -787,6 +1532,8 @@ impl Backtester {
                     // Opening fee is immediately realized.
                     realised_pnl: -fee,
                     fill_type,
+                    best_px: px,
+                    worst_px: px,
                 });
             }
         }
@@ -825,6 +1572,8 @@ impl Backtester {
             return None;
         }
 
+        pos.track_excursion(px, px);
+        self.excursions.push(pos.excursion());
         let total_pnl = pos.realised_pnl + pos.funding;
         let trade = TradeInfo {
             side: pos.side,
@@ -848,8 +1597,11 @@ impl Backtester {
 
         self.trades.push(trade.clone());
         self.position = None;
-        self.resting_orders
-            .retain(|_, order| matches!(order.kind, RestingKind::Open { .. }));
+        // The unfilled rest of an entry that built this position, and any add
+        // meant to grow it, go with it.
+        self.resting_orders.retain(|_, order| {
+            matches!(order.kind, RestingKind::Open { add: false, .. }) && order.filled <= EPSILON
+        });
 
         Some(trade)
     }
@@ -877,6 +1629,7 @@ impl Backtester {
                     order,
                     kind: RestingKind::Close,
                     placed_at,
+                    filled: 0.0,
                 },
             );
         }
@@ -891,9 +1644,68 @@ impl Backtester {
                     order,
                     kind: RestingKind::Close,
                     placed_at,
+                    filled: 0.0,
+                },
+            );
+        }
+
+        if let Some(trail) = triggers.trail {
+            self.sync_engine_position();
+            if let Some(replace) = self.engine.start_backtest_trailing(trail) {
+                let _ = self.replace_triggers(replace, placed_at);
+            }
+        }
+    }
+
+    /// Mirror the executor's cancel-and-replace of resting TP/SL orders.
+    /// Returns whether the book changed; a replace with the trigger that is
+    /// already resting is skipped.
+    fn replace_triggers(&mut self, replace: TriggerReplace, placed_at: u64) -> bool {
+        let Some(pos) = self.position else {
+            return false;
+        };
+        if let Some(order) = replace.order {
+            let mut stale = self
+                .resting_orders
+                .values()
+                .filter(|resting| replace.cancels(resting.order.is_tpsl()));
+            if let (Some(resting), None) = (stale.next(), stale.next())
+                && resting.order.is_tpsl() == order.is_tpsl()
+                && resting.order.limit.map(|l| l.limit_px) == order.limit.map(|l| l.limit_px)
+                && (resting.order.size - pos.size).abs() <= EPSILON
+            {
+                return false;
+            }
+        }
+        self.resting_orders
+            .retain(|_, resting| !replace.cancels(resting.order.is_tpsl()));
+
+        if let Some(mut order) = replace.order {
+            order.size = pos.size;
+            let id = self.next_id();
+            self.resting_orders.insert(
+                id,
+                RestingOrder {
+                    order,
+                    kind: RestingKind::Close,
+                    placed_at,
+                    filled: 0.0,
                 },
             );
         }
+        true
+    }
+
+    /// Keep TP/SL covering the whole position while a partially filled entry grows it.
+    fn resize_triggers_to_position(&mut self) {
+        let Some(pos) = self.position else {
+            return;
+        };
+        for resting in self.resting_orders.values_mut() {
+            if resting.order.is_tpsl().is_some() {
+                resting.order.size = pos.size;
+            }
+        }
     }
 
     fn reconcile_close_order_sizes(&mut self, max_size: f64) {
@@ -907,10 +1719,22 @@ impl Backtester {
     fn sync_engine_position(&mut self) {
         let open_pos = self.position.map(|p| p.to_open_pos_info());
         self.engine.set_backtest_open_position(open_pos);
-        self.engine.set_backtest_margin(self.balance.max(0.0));
+        self.engine
+            .set_backtest_margin(self.account_margin.unwrap_or(self.balance).max(0.0));
     }
 
     fn apply_funding_if_due(&mut self, candle: Price) {
+        if !self.funding_rates.is_empty() {
+            // Recorded settlements are charged at the first candle open at or after them.
+            while let Some(settlement) = self.funding_rates.get(self.funding_idx).copied()
+                && settlement.time <= candle.open_time
+            {
+                self.charge_funding(settlement.rate, candle.open);
+                self.funding_idx += 1;
+            }
+            return;
+        }
+
         let rate_bps = self.request.config.funding_rate_bps_per_8h;
         let mut next = match self.next_funding_time {
             Some(ts) => ts,
@@ -918,27 +1742,35 @@ impl Backtester {
         };
 
         while candle.open_time >= next {
-            if let Some(mut pos) = self.position
-                && rate_bps != 0.0
-            {
-                let rate = rate_bps / 10_000.0;
-                let notional = pos.size * candle.open;
-                let signed = match pos.side {
-                    Side::Long => -1.0,
-                    Side::Short => 1.0,
-                };
-                let funding = notional * rate * signed;
-                pos.funding += funding;
-                self.balance += funding;
-                self.position = Some(pos);
+            if rate_bps != 0.0 {
+                self.charge_funding(rate_bps / 10_000.0, candle.open);
             }
             next = next.saturating_add(FUNDING_WINDOW_MS);
         }
         self.next_funding_time = Some(next);
     }
 
+    /// Positive `rate` means longs pay shorts, matching the venue convention.
+    fn charge_funding(&mut self, rate: f64, mark_px: f64) {
+        let Some(mut pos) = self.position else {
+            return;
+        };
+        let notional = pos.size * mark_px;
+        let signed = match pos.side {
+            Side::Long => -1.0,
+            Side::Short => 1.0,
+        };
+        let funding = notional * rate * signed;
+        pos.funding += funding;
+        self.balance += funding;
+        self.position = Some(pos);
+    }
+
     fn init_funding(&mut self, first_ts: u64) {
         self.next_funding_time = Some(next_time_boundary(first_ts, FUNDING_WINDOW_MS));
+        self.funding_idx = self
+            .funding_rates
+            .partition_point(|settlement| settlement.time < first_ts);
     }
 
     fn push_equity_point(&mut self, candle: Price) {
@@ -971,7 +1803,7 @@ impl Backtester {
         self.snapshots.push(snapshot);
     }
 
-    fn unrealised_pnl(&self, mark_px: f64) -> f64 {
+    pub(super) fn unrealised_pnl(&self, mark_px: f64) -> f64 {
         let Some(pos) = self.position else {
             return 0.0;
         };
@@ -1063,6 +1895,7 @@ impl Backtester {
         let Some(candle) = last_candle else {
             return;
         };
+        self.pending_market.clear();
         if self.position.is_none() {
             self.resting_orders.clear();
             return;
@@ -1089,14 +1922,13 @@ impl Backtester {
         self.resting_orders.clear();
     }
 
-    fn build_result(
+    pub(super) fn build_result(
         &self,
         started_at: u64,
         finished_at: u64,
-        candles_loaded: u64,
-        candles_processed: u64,
+        cursor: &SimCursor,
     ) -> BacktestResult {
-        let summary = build_summary(
+        let mut summary = build_summary(
             self.request.config.margin,
             self.equity_curve
                 .last()
@@ -1116,6 +1948,30 @@ impl Backtester {
             );
         }
 
+        summary.slippage_cost = self.slippage_cost;
+        summary.intrabar_resolved_bars = self.intrabar_resolved_bars;
+        summary.set_trade_excursions(self.excursions.clone());
+        summary.monte_carlo = self
+            .request
+            .monte_carlo
+            .as_ref()
+            .map(|mc| monte_carlo::simulate(mc, self.request.config.margin, &self.trades));
+
+        let cfg = &self.request.config;
+        let benchmark = benchmark::benchmark_summary(
+            cfg.margin,
+            cfg.lev,
+            cfg.taker_fee_bps,
+            cursor.sim_candles(),
+            &self.equity_curve,
+            summary.return_pct,
+            cfg.resolution,
+        )
+        .map(|mut benchmark| {
+            benchmark.equity_curve = lttb_equity(&benchmark.equity_curve, cfg.max_equity_points);
+            benchmark
+        });
+
         let equity_curve = lttb_equity(&self.equity_curve, self.request.config.max_equity_points);
         let snapshots = cap_snapshots(&self.snapshots, self.request.config.max_snapshots);
 
@@ -1123,13 +1979,16 @@ impl Backtester {
             run_id: format!("bt-{}-{}", self.request.config.asset, started_at),
             started_at,
             finished_at,
-            candles_loaded,
-            candles_processed,
+            candles_loaded: cursor.loaded_candles,
+            candles_processed: cursor.sim_processed,
             config: self.request.config.clone(),
             summary,
             trades: self.trades.clone(),
             equity_curve,
             snapshots,
+            benchmark,
+            script_logs: self.script_logs.clone(),
+            script_logs_dropped: self.script_logs_dropped,
         }
     }
 
@@ -1449,6 +2308,7 @@ async fn fetch_series_history_with_progress<F>(
     loading_reported: &mut u64,
     next_loading_log: &mut u64,
     loading_log_step: u64,
+    cancel: &CancellationToken,
     on_progress: &mut F,
 ) -> Result<Vec<Price>, Error>
 where
@@ -1490,6 +2350,13 @@ where
 
     while !producer_done {
         tokio::select! {
+            _ = cancel.cancelled() => {
+                // Dropping the producer's JoinSet aborts the window workers.
+                producer.abort();
+                info!("backtest[{run_id}] cancelled while fetching {asset} {:?}", tf);
+                on_progress(BacktestProgress::Cancelled { processed: 0 });
+                return Err(Error::Custom("Backtest cancelled".to_string()));
+            }
             maybe_loading = loading_rx.recv(), if !loading_rx.is_closed() => {
                 if let Some((loaded, total)) = maybe_loading {
                     let global_loaded = loaded_offset
@@ -1616,7 +2483,12 @@ fn next_event_batch(
     series_data: &[BacktestSeries],
     next_indices: &mut [usize],
 ) -> Option<Vec<SeriesEvent>> {
-    let next_ts = series_data
+    let next_ts = peek_next_ts(series_data, next_indices)?;
+    Some(next_event_batch_at(series_data, next_indices, next_ts))
+}
+
+fn peek_next_ts(series_data: &[BacktestSeries], next_indices: &[usize]) -> Option<u64> {
+    series_data
         .iter()
         .enumerate()
         .filter_map(|(idx, series)| {
@@ -1625,8 +2497,14 @@ fn next_event_batch(
                 .get(next_indices[idx])
                 .map(|price| price.open_time)
         })
-        .min()?;
+        .min()
+}
 
+fn next_event_batch_at(
+    series_data: &[BacktestSeries],
+    next_indices: &mut [usize],
+    next_ts: u64,
+) -> Vec<SeriesEvent> {
     let mut batch = Vec::new();
     for (idx, series) in series_data.iter().enumerate() {
         let Some(price) = series.prices.get(next_indices[idx]).copied() else {
@@ -1651,7 +2529,7 @@ fn next_event_batch(
             .cmp(series_b.asset.as_ref())
             .then_with(|| series_a.tf.as_str().cmp(series_b.tf.as_str()))
     });
-    Some(batch)
+    batch
 }
 
 fn maybe_log_milestone(
@@ -1679,7 +2557,7 @@ fn maybe_log_milestone(
     }
 }
 
-fn build_summary(
+pub(super) fn build_summary(
     initial_equity: f64,
     final_equity: f64,
     equity_curve: &[EquityPoint],
@@ -1747,7 +2625,12 @@ fn build_summary(
     } else {
         0.0
     };
-    let sharpe_ratio = compute_sharpe_ratio(initial_equity, equity_curve, resolution);
+    let sampled = metrics::sampled_returns(equity_curve, resolution);
+    let sharpe_ratio = sampled.as_ref().and_then(compute_sharpe_ratio);
+    let sortino_ratio = sampled.as_ref().and_then(metrics::sortino_ratio);
+    let span_ms = metrics::curve_span_ms(equity_curve, resolution);
+    let (avg_trade_duration_ms, median_trade_duration_ms) = metrics::trade_durations(trades);
+    let (longest_win_streak, longest_loss_streak) = metrics::streaks(trades);
 
     BacktestSummary {
         initial_equity,
@@ -1767,60 +2650,30 @@ fn build_summary(
         profit_factor,
         expectancy,
         sharpe_ratio,
+        monte_carlo: None,
+        slippage_cost: 0.0,
+        intrabar_resolved_bars: 0,
+        sortino_ratio,
+        calmar_ratio: metrics::calmar_ratio(
+            initial_equity,
+            final_equity,
+            span_ms,
+            max_drawdown_pct,
+        ),
+        time_in_market_pct: metrics::time_in_market_pct(trades, span_ms),
+        avg_trade_duration_ms,
+        median_trade_duration_ms,
+        longest_win_streak,
+        longest_loss_streak,
+        avg_mae_pct: 0.0,
+        avg_mfe_pct: 0.0,
+        trade_excursions: Vec::new(),
+        monthly_returns: metrics::monthly_returns(initial_equity, equity_curve),
     }
 }
 
-fn compute_sharpe_ratio(
-    _initial_equity: f64,
-    equity_curve: &[EquityPoint],
-    resolution: TimeFrame,
-) -> Option<f64> {
-    if equity_curve.len() < 2 {
-        return None;
-    }
-
-    // Keep one point per timestamp (latest point wins) to avoid duplicate-time noise.
-    let mut points: Vec<(u64, f64)> = Vec::with_capacity(equity_curve.len());
-    for point in equity_curve {
-        match points.last_mut() {
-            Some((last_ts, last_equity)) if *last_ts == point.ts => {
-                *last_equity = point.equity;
-            }
-            _ => points.push((point.ts, point.equity)),
-        }
-    }
-
-    if points.len() < 2 {
-        return None;
-    }
-
-    let mut returns: Vec<f64> = Vec::with_capacity(points.len().saturating_sub(1));
-    let mut delta_secs: Vec<f64> = Vec::with_capacity(points.len().saturating_sub(1));
-
-    for w in points.windows(2) {
-        let (prev_ts, prev_equity) = w[0];
-        let (ts, equity) = w[1];
-
-        if prev_equity.abs() <= EPSILON {
-            continue;
-        }
-
-        let dt_ms = ts.saturating_sub(prev_ts);
-        if dt_ms == 0 {
-            continue;
-        }
-
-        let ret = (equity - prev_equity) / prev_equity;
-        if ret.is_finite() {
-            returns.push(ret);
-            delta_secs.push((dt_ms as f64) / 1000.0);
-        }
-    }
-
-    if returns.len() < 2 {
-        return None;
-    }
-
+fn compute_sharpe_ratio(sampled: &SampledReturns) -> Option<f64> {
+    let returns = &sampled.returns;
     let n = returns.len() as f64;
     let mean = returns.iter().sum::<f64>() / n;
     let variance = returns
@@ -1836,33 +2689,7 @@ fn compute_sharpe_ratio(
     }
 
     let std_dev = variance.sqrt();
-
-    // Annualize by observed sampling cadence (fallback to base resolution if needed).
-    let mut sorted_dt = delta_secs;
-    sorted_dt.sort_by(|a, b| a.total_cmp(b));
-    let median_dt_secs = if sorted_dt.len() % 2 == 1 {
-        sorted_dt[sorted_dt.len() / 2]
-    } else {
-        let upper = sorted_dt.len() / 2;
-        (sorted_dt[upper - 1] + sorted_dt[upper]) / 2.0
-    };
-
-    let fallback_dt = resolution.to_secs() as f64;
-    let sample_dt_secs = if median_dt_secs > 0.0 {
-        median_dt_secs
-    } else {
-        fallback_dt
-    };
-    if !sample_dt_secs.is_finite() || sample_dt_secs <= 0.0 {
-        return None;
-    }
-
-    let periods_per_year = (365.0 * 24.0 * 60.0 * 60.0) / sample_dt_secs;
-    if !periods_per_year.is_finite() || periods_per_year <= 0.0 {
-        return None;
-    }
-
-    let sharpe = (mean / std_dev) * periods_per_year.sqrt();
+    let sharpe = (mean / std_dev) * sampled.periods_per_year.sqrt();
     sharpe.is_finite().then_some(sharpe)
 }
 
@@ -1903,6 +2730,49 @@ fn trigger_fill_px(candle: Price, limit_px: f64, is_above_market: bool) -> f64 {
     }
 }
 
+/// Size of a touched limit order that fills on `candle`; zero leaves it resting.
+fn limit_fill_size(
+    model: &LimitFillModel,
+    candle: Price,
+    limit_px: f64,
+    is_above_market: bool,
+    remaining: f64,
+    rng: &mut StdRng,
+) -> f64 {
+    let traded_through = |bps: f64| {
+        let margin = limit_px * bps / 10_000.0;
+        if is_above_market {
+            candle.high > limit_px + margin
+        } else {
+            candle.low < limit_px - margin
+        }
+    };
+    let fills = match model.touch {
+        TouchFill::Fill => true,
+        TouchFill::TradeThrough { bps } => traded_through(bps),
+        // Validation keeps `probability` in [0, 1]; clamp anyway, since
+        // `gen_bool` panics outside it.
+        TouchFill::Probability { probability, .. } => {
+            traded_through(0.0) || (probability > 0.0 && rng.gen_bool(probability.min(1.0)))
+        }
+    };
+    if !fills {
+        return 0.0;
+    }
+    match model.max_volume_fraction {
+        Some(fraction) if candle.vlm > 0.0 => remaining.min(fraction * candle.vlm),
+        _ => remaining,
+    }
+}
+
+fn limit_fill_rng(model: &LimitFillModel) -> StdRng {
+    let seed = match model.touch {
+        TouchFill::Probability { seed, .. } => seed,
+        _ => 0,
+    };
+    StdRng::seed_from_u64(seed)
+}
+
 /// Determine whether a resting order's trigger sits above the current market.
 fn is_trigger_above_market(order: &EngineOrder, pos_side: Option<Side>, candle_open: f64) -> bool {
     if let Some(tk) = order.is_tpsl()
@@ -1960,12 +2830,16 @@ fn snapshot_reason_from_action(action: BtAction) -> Option<SnapshotReason> {
         BtAction::Submit { intent, .. } | BtAction::ForceTaker { intent, .. } => {
             Some(match intent {
                 BtIntent::Open => SnapshotReason::Open,
+                BtIntent::Add => SnapshotReason::Add,
                 BtIntent::Reduce => SnapshotReason::Reduce,
                 BtIntent::Flatten => SnapshotReason::Flatten,
             })
         }
         BtAction::CancelAllResting => Some(SnapshotReason::CancelResting),
         BtAction::ForceCloseMarket => Some(SnapshotReason::ForceClose),
+        // Captured once the deferred replace is applied.
+        BtAction::ReplaceTriggers(_) => None,
+        BtAction::CancelResting(_) => Some(SnapshotReason::CancelResting),
     }
 }
 
@@ -1973,8 +2847,22 @@ fn snapshot_reason_from_action(action: BtAction) -> Option<SnapshotReason> {
 mod tests {
     use std::sync::Arc;
 
-    use super::{BacktestSeries, SeriesEvent, collect_required_series, next_event_batch};
-    use crate::{IndicatorKind, Price, TimeFrame};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::{
+        AtrWindow, BacktestSeries, Backtester, EngineOrder, FillType, LatencyModel, LimitFillModel,
+        OpenOrder, PositionState, RestingKind, RestingOrder, SeriesEvent, TouchFill,
+        collect_required_series, limit_fill_size, next_event_batch,
+    };
+    use crate::backend::scripting::{compile_strategy, create_engine};
+    use crate::backtest::candle_store::CandleStore;
+    use crate::backtest::types::{BacktestConfig, BacktestRunRequest, FillModel};
+    use crate::backtest::{DataSource, Exchange, MarketType};
+    use crate::{
+        BtAction, BtIntent, IndicatorKind, Price, Side, TimeFrame, Trail, TriggerKind,
+        TriggerReplace, Triggers,
+    };
 
     fn price(ts: u64, close: f64) -> Price {
         Price {
@@ -1988,6 +2876,294 @@ mod tests {
         }
     }
 
+    fn backtester(on_idle: &str, on_open: &str, fill_model: FillModel) -> Backtester {
+        let rhai_engine = Arc::new(create_engine());
+        let compiled = compile_strategy(&rhai_engine, on_idle, on_open, "()", None)
+            .expect("strategy compiles");
+        let request = BacktestRunRequest {
+            run_id: None,
+            config: BacktestConfig {
+                asset: "BTC".to_string(),
+                source: DataSource::new(Exchange::Hyperliquid, MarketType::Futures),
+                strategy_id: uuid::Uuid::nil(),
+                resolution: TimeFrame::Min1,
+                margin: 1_000.0,
+                lev: 1,
+                taker_fee_bps: 10,
+                maker_fee_bps: 0,
+                funding_rate_bps_per_8h: 0.0,
+                historical_funding: false,
+                start_time: 0,
+                end_time: 3_600_000,
+                snapshot_interval_candles: 0,
+                max_equity_points: 100,
+                max_snapshots: 0,
+                max_script_logs: 0,
+                fill_model,
+                intrabar_resolution: false,
+            },
+            warmup_candles: 0,
+            monte_carlo: None,
+        };
+        let candle_store = CandleStore::open(std::env::temp_dir().join("kwant-backtester-tests"))
+            .expect("candle store opens");
+        Backtester::new(
+            request,
+            rhai_engine,
+            compiled,
+            Vec::new(),
+            Arc::new(candle_store),
+        )
+    }
+
+    fn long_position(size: f64, entry_px: f64) -> PositionState {
+        PositionState {
+            side: Side::Long,
+            size,
+            entry_px,
+            open_time: 0,
+            fees: 0.0,
+            funding: 0.0,
+            realised_pnl: 0.0,
+            fill_type: FillType::Market,
+            best_px: entry_px,
+            worst_px: entry_px,
+        }
+    }
+
+    #[test]
+    fn delayed_open_is_dropped_once_a_position_exists() {
+        let fill_model = FillModel {
+            latency: LatencyModel::NextOpen,
+            ..FillModel::default()
+        };
+        let mut bt = backtester("()", "()", fill_model);
+        let open = OpenOrder {
+            order: EngineOrder::new_market_open(Side::Long, 1.0),
+            triggers: None,
+        };
+        bt.position = Some(long_position(1.0, 100.0));
+
+        bt.market_open(open, false, price(0, 100.0));
+        bt.fill_pending_market(price(60_000, 100.0));
+        assert_eq!(bt.position.map(|pos| pos.size), Some(1.0));
+
+        bt.market_open(open, true, price(60_000, 100.0));
+        bt.fill_pending_market(price(120_000, 100.0));
+        assert_eq!(bt.position.map(|pos| pos.size), Some(2.0));
+    }
+
+    #[test]
+    fn intrabar_replay_skips_orders_placed_on_the_same_bar() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        let bar_open = 300_000;
+        let rest = |bt: &mut Backtester, px: f64, placed_at: u64| {
+            let id = bt.next_id();
+            bt.resting_orders.insert(
+                id,
+                RestingOrder {
+                    order: EngineOrder::new_limit_open(Side::Long, 1.0, px, None),
+                    kind: RestingKind::Open {
+                        triggers: None,
+                        add: false,
+                    },
+                    placed_at,
+                    filled: 0.0,
+                },
+            );
+            id
+        };
+        rest(&mut bt, 95.0, 0);
+        rest(&mut bt, 94.0, 0);
+        let same_bar = rest(&mut bt, 99.5, bar_open);
+
+        let minute = |idx: u64, low: f64| Price {
+            low,
+            ..price(bar_open + idx * 60_000, 100.0)
+        };
+        let intrabar = [
+            minute(0, 100.0),
+            minute(1, 99.0),
+            minute(2, 93.0),
+            minute(3, 100.0),
+            minute(4, 100.0),
+        ];
+        let bar = Price {
+            close_time: bar_open + 300_000,
+            low: 93.0,
+            ..price(bar_open, 100.0)
+        };
+        bt.settle_bar(bar, &intrabar);
+
+        assert_eq!(bt.intrabar_resolved_bars, 1);
+        assert_eq!(bt.position.map(|pos| pos.size), Some(2.0));
+        assert!(bt.resting_orders.contains_key(&same_bar));
+    }
+
+    fn rest_stop(bt: &mut Backtester, size: f64, px: f64, placed_at: u64) -> u64 {
+        let id = bt.next_id();
+        bt.resting_orders.insert(
+            id,
+            RestingOrder {
+                order: EngineOrder::new_sl(size, px),
+                kind: RestingKind::Close,
+                placed_at,
+                filled: 0.0,
+            },
+        );
+        id
+    }
+
+    fn stop_px(bt: &Backtester) -> Option<(f64, u64)> {
+        bt.resting_orders.values().find_map(|resting| {
+            let limit = resting.order.limit?;
+            (resting.order.is_tpsl() == Some(TriggerKind::Sl))
+                .then_some((limit.limit_px, resting.placed_at))
+        })
+    }
+
+    #[test]
+    fn trigger_moves_apply_after_the_bar_they_were_decided_on_settles() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        let move_stop = |px| {
+            vec![BtAction::ReplaceTriggers(TriggerReplace::new(
+                TriggerKind::Sl,
+                1.0,
+                px,
+            ))]
+        };
+
+        // The old stop is hit on the bar the move was decided on.
+        bt.position = Some(long_position(1.0, 100.0));
+        rest_stop(&mut bt, 1.0, 95.0, 0);
+        let hit = Price {
+            low: 94.0,
+            ..price(60_000, 96.0)
+        };
+        bt.apply_engine_actions(move_stop(90.0), hit);
+        assert_eq!(stop_px(&bt), Some((95.0, 0)));
+        bt.process_candle(hit, &[]);
+        assert!(bt.position.is_none());
+        assert_eq!(bt.trades.len(), 1);
+        assert_eq!(stop_px(&bt), None);
+
+        // Otherwise the new stop rests from the next bar on.
+        bt.position = Some(long_position(1.0, 100.0));
+        rest_stop(&mut bt, 1.0, 95.0, 0);
+        let quiet = price(120_000, 101.0);
+        bt.apply_engine_actions(move_stop(97.0), quiet);
+        bt.process_candle(quiet, &[]);
+        assert_eq!(stop_px(&bt), Some((97.0, 120_000)));
+    }
+
+    #[test]
+    fn replacing_a_trigger_with_itself_keeps_the_resting_order() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        bt.position = Some(long_position(1.0, 100.0));
+        let id = rest_stop(&mut bt, 1.0, 95.0, 0);
+
+        assert!(!bt.replace_triggers(TriggerReplace::new(TriggerKind::Sl, 1.0, 95.0), 60_000));
+        assert!(bt.resting_orders.contains_key(&id));
+        assert!(bt.replace_triggers(TriggerReplace::new(TriggerKind::Sl, 1.0, 96.0), 60_000));
+        assert_eq!(stop_px(&bt), Some((96.0, 60_000)));
+    }
+
+    #[test]
+    fn resting_add_is_dropped_when_take_profit_closes_on_the_same_bar() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        bt.position = Some(long_position(1.0, 100.0));
+        let tp = bt.next_id();
+        bt.resting_orders.insert(
+            tp,
+            RestingOrder {
+                order: EngineOrder::new_tp(1.0, 110.0),
+                kind: RestingKind::Close,
+                placed_at: 0,
+                filled: 0.0,
+            },
+        );
+        let add = OpenOrder {
+            order: EngineOrder::new_limit_open(Side::Long, 1.0, 95.0, None),
+            triggers: None,
+        };
+        bt.submit_open_order(add, BtIntent::Add, price(0, 100.0));
+
+        let both_hit = Price {
+            high: 111.0,
+            low: 94.0,
+            ..price(60_000, 100.0)
+        };
+        bt.process_candle(both_hit, &[]);
+        assert!(bt.position.is_none());
+        assert_eq!(bt.trades.len(), 1);
+        assert!(bt.resting_orders.is_empty());
+    }
+
+    #[test]
+    fn trailing_stop_rests_from_the_bar_after_a_market_entry() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        let open = OpenOrder {
+            order: EngineOrder::new_market_open(Side::Long, 1.0),
+            triggers: Some(Triggers {
+                tp: None,
+                sl: None,
+                trail: Some(Trail::Pct(5.0)),
+            }),
+        };
+
+        bt.market_open(open, false, price(0, 100.0));
+        let (px, placed_at) = stop_px(&bt).expect("trailing stop placed at fill");
+        assert!(px < 100.0);
+        assert_eq!(placed_at, 0);
+
+        let drop = Price {
+            low: px - 1.0,
+            ..price(60_000, 100.0)
+        };
+        bt.process_candle(drop, &[]);
+        assert!(bt.position.is_none());
+        assert_eq!(bt.trades.len(), 1);
+    }
+
+    #[test]
+    fn excursions_skip_the_bar_a_market_entry_filled_at_the_close_of() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        let bar = Price {
+            high: 120.0,
+            low: 80.0,
+            ..price(0, 100.0)
+        };
+        bt.position = Some(PositionState {
+            open_time: bar.close_time,
+            ..long_position(1.0, 100.0)
+        });
+        bt.mark_candle(bar);
+        let pos = bt.position.unwrap();
+        assert_eq!((pos.best_px, pos.worst_px), (100.0, 100.0));
+
+        let next = Price {
+            high: 104.0,
+            low: 97.0,
+            ..price(bar.close_time, 100.0)
+        };
+        bt.mark_candle(next);
+        let pos = bt.position.unwrap();
+        assert_eq!((pos.best_px, pos.worst_px), (104.0, 97.0));
+    }
+
+    #[test]
+    fn close_out_upnl_charges_the_taker_fee() {
+        let mut bt = backtester("()", "()", FillModel::default());
+        bt.position = Some(long_position(2.0, 100.0));
+        let candle = Price {
+            low: 90.0,
+            ..price(0, 95.0)
+        };
+        // 2 * (90 - 100) less 10 bps on 180 of notional.
+        assert!((bt.close_out_upnl(bt.adverse_px(candle)) - (-20.0 - 0.18)).abs() < 1e-9);
+        assert!((bt.close_out_upnl(110.0) - (20.0 - 0.22)).abs() < 1e-9);
+    }
+
     #[test]
     fn collect_required_series_includes_execution_series_first() {
         let execution_asset = Arc::<str>::from("BTC");
@@ -2054,4 +3230,148 @@ mod tests {
         assert_eq!(third.len(), 1);
         assert_eq!(third[0].price.open_time, 180_000);
     }
+
+    #[test]
+    fn atr_window_uses_true_range_over_period() {
+        let mut atr = AtrWindow::default();
+        assert_eq!(atr.value(), None);
+
+        let bar = |ts: u64, high: f64, low: f64, close: f64| Price {
+            high,
+            low,
+            close,
+            ..price(ts, close)
+        };
+        atr.push(bar(0, 105.0, 95.0, 100.0), 2);
+        // Gap up: true range reaches back to the previous close.
+        atr.push(bar(60_000, 112.0, 108.0, 110.0), 2);
+        assert_eq!(atr.value(), Some((10.0 + 12.0) / 2.0));
+        atr.push(bar(120_000, 111.0, 109.0, 110.0), 2);
+        assert_eq!(atr.value(), Some((12.0 + 2.0) / 2.0));
+    }
+
+    #[test]
+    fn limit_fill_size_needs_trade_through_and_caps_by_volume() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // Buy limit at 99 below a candle that dips to exactly 99.
+        let touch = Price {
+            low: 99.0,
+            vlm: 10.0,
+            ..price(0, 100.0)
+        };
+        let through = Price { low: 98.5, ..touch };
+        let model = LimitFillModel {
+            touch: TouchFill::TradeThrough { bps: 10.0 },
+            max_volume_fraction: Some(0.2),
+        };
+
+        assert_eq!(
+            limit_fill_size(&model, touch, 99.0, false, 5.0, &mut rng),
+            0.0
+        );
+        assert_eq!(
+            limit_fill_size(&model, through, 99.0, false, 5.0, &mut rng),
+            2.0
+        );
+        assert_eq!(
+            limit_fill_size(&model, through, 99.0, false, 1.5, &mut rng),
+            1.5
+        );
+        assert_eq!(
+            limit_fill_size(
+                &LimitFillModel::default(),
+                touch,
+                99.0,
+                false,
+                5.0,
+                &mut rng
+            ),
+            5.0
+        );
+    }
+
+    #[test]
+    fn limit_fill_size_clamps_out_of_range_probabilities() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let touch = Price {
+            low: 99.0,
+            ..price(0, 100.0)
+        };
+        let model = |probability| LimitFillModel {
+            touch: TouchFill::Probability {
+                probability,
+                seed: 0,
+            },
+            max_volume_fraction: None,
+        };
+
+        assert_eq!(
+            limit_fill_size(&model(1.5), touch, 99.0, false, 5.0, &mut rng),
+            5.0
+        );
+        assert_eq!(
+            limit_fill_size(&model(-0.5), touch, 99.0, false, 5.0, &mut rng),
+            0.0
+        );
+        assert_eq!(
+            limit_fill_size(&model(f64::NAN), touch, 99.0, false, 5.0, &mut rng),
+            0.0
+        );
+    }
+
+    #[test]
+    fn limit_fill_size_touch_probability_is_seeded() {
+        let touch = Price {
+            high: 101.0,
+            ..price(0, 100.0)
+        };
+        let model = LimitFillModel {
+            touch: TouchFill::Probability {
+                probability: 0.5,
+                seed: 7,
+            },
+            max_volume_fraction: None,
+        };
+        let draws = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..64)
+                .map(|_| limit_fill_size(&model, touch, 101.0, true, 1.0, &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        let first = draws(7);
+        assert_eq!(first, draws(7));
+        assert!(first.contains(&0.0) && first.contains(&1.0));
+    }
+
+    #[test]
+    fn intrabar_for_returns_minutes_inside_each_bar() {
+        let mut cursor = SimCursor {
+            run_id: "test".to_string(),
+            series_data: Vec::new(),
+            primary_series_idx: 0,
+            next_indices: Vec::new(),
+            current_execution_candle: None,
+            last_sim_candle: None,
+            sim_started: false,
+            intrabar: (0..10).map(|i| price(i * 60_000, 100.0)).collect(),
+            intrabar_idx: 0,
+            warmup_loaded: 0,
+            loaded_candles: 0,
+            sim_processed: 0,
+            sim_total: 0,
+        };
+        let bar = |ts: u64| Price {
+            close_time: ts + 300_000 - 1,
+            ..price(ts, 100.0)
+        };
+
+        let first = cursor.intrabar_for(bar(0)).to_vec();
+        assert_eq!(first.len(), 5);
+        assert_eq!(first[4].open_time, 240_000);
+        let second = cursor.intrabar_for(bar(300_000)).to_vec();
+        assert_eq!(second.first().map(|p| p.open_time), Some(300_000));
+        assert_eq!(second.len(), 5);
+        assert!(cursor.intrabar_for(bar(600_000)).is_empty());
+    }
 }
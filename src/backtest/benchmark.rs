@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::types::EquityPoint;
+use crate::{Price, TimeFrame};
+
+const EPSILON: f64 = 1e-12;
+const YEAR_MS: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Long-only buy-and-hold over the same candles, margin and leverage as the run.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkSummary {
+    pub initial_equity: f64,
+    pub final_equity: f64,
+    pub net_pnl: f64,
+    pub return_pct: f64,
+    pub max_drawdown_pct: f64,
+    pub liquidated: bool,
+    /// Strategy return minus benchmark return, in percentage points.
+    pub excess_return_pct: f64,
+    /// Annualised Jensen's alpha of the strategy against the benchmark, in percent.
+    pub alpha_pct: Option<f64>,
+    pub beta: Option<f64>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+/// Equity of a position opened with `margin * lev` notional at the first
+/// candle's open and closed at the last candle's close. Taker fees are paid on
+/// both legs; the curve stops at zero if the move wipes out the margin.
+pub fn buy_and_hold_curve(
+    margin: f64,
+    lev: usize,
+    taker_fee_bps: u32,
+    candles: &[Price],
+) -> (Vec<EquityPoint>, bool) {
+    let Some(first) = candles.first() else {
+        return (Vec::new(), false);
+    };
+    if first.open <= EPSILON || margin <= EPSILON {
+        return (Vec::new(), false);
+    }
+
+    let fee_rate = taker_fee_bps as f64 / 10_000.0;
+    let size = margin * lev.max(1) as f64 / first.open;
+    let balance = margin - size * first.open * fee_rate;
+    let mut curve = Vec::with_capacity(candles.len());
+    for candle in candles {
+        if balance + size * (candle.low - first.open) <= 0.0 {
+            curve.push(EquityPoint {
+                ts: candle.close_time,
+                equity: 0.0,
+                balance: 0.0,
+                upnl: 0.0,
+            });
+            return (curve, true);
+        }
+        let upnl = size * (candle.close - first.open);
+        curve.push(EquityPoint {
+            ts: candle.close_time,
+            equity: balance + upnl,
+            balance,
+            upnl,
+        });
+    }
+
+    if let (Some(last), Some(last_candle)) = (curve.last_mut(), candles.last()) {
+        let exit_fee = size * last_candle.close * fee_rate;
+        last.balance = (last.equity - exit_fee).max(0.0);
+        last.equity = last.balance;
+        last.upnl = 0.0;
+    }
+    (curve, false)
+}
+
+pub fn benchmark_summary(
+    margin: f64,
+    lev: usize,
+    taker_fee_bps: u32,
+    candles: &[Price],
+    strategy_curve: &[EquityPoint],
+    strategy_return_pct: f64,
+    resolution: TimeFrame,
+) -> Option<BenchmarkSummary> {
+    let (equity_curve, liquidated) = buy_and_hold_curve(margin, lev, taker_fee_bps, candles);
+    let final_equity = equity_curve.last()?.equity;
+    let net_pnl = final_equity - margin;
+    let return_pct = net_pnl / margin * 100.0;
+
+    let mut peak = margin;
+    let mut max_drawdown_pct = 0.0_f64;
+    for point in &equity_curve {
+        peak = peak.max(point.equity);
+        if peak > EPSILON {
+            max_drawdown_pct = max_drawdown_pct.max((peak - point.equity) / peak * 100.0);
+        }
+    }
+
+    let (alpha_pct, beta) = alpha_beta(strategy_curve, &equity_curve, resolution)
+        .map_or((None, None), |(alpha, beta)| (Some(alpha), Some(beta)));
+
+    Some(BenchmarkSummary {
+        initial_equity: margin,
+        final_equity,
+        net_pnl,
+        return_pct,
+        max_drawdown_pct,
+        liquidated,
+        excess_return_pct: strategy_return_pct - return_pct,
+        alpha_pct,
+        beta,
+        equity_curve,
+    })
+}
+
+/// Regress strategy returns on benchmark returns over the timestamps both
+/// curves share. Alpha is annualised from the execution resolution.
+fn alpha_beta(
+    strategy: &[EquityPoint],
+    benchmark: &[EquityPoint],
+    resolution: TimeFrame,
+) -> Option<(f64, f64)> {
+    let by_ts = benchmark
+        .iter()
+        .map(|point| (point.ts, point.equity))
+        .collect::<HashMap<_, _>>();
+    let paired = strategy
+        .iter()
+        .filter_map(|point| by_ts.get(&point.ts).map(|bench| (point.equity, *bench)))
+        .collect::<Vec<_>>();
+
+    let returns = paired
+        .windows(2)
+        .filter(|w| w[0].0.abs() > EPSILON && w[0].1.abs() > EPSILON)
+        .map(|w| ((w[1].0 - w[0].0) / w[0].0, (w[1].1 - w[0].1) / w[0].1))
+        .collect::<Vec<_>>();
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let n = returns.len() as f64;
+    let mean_s = returns.iter().map(|r| r.0).sum::<f64>() / n;
+    let mean_b = returns.iter().map(|r| r.1).sum::<f64>() / n;
+    let cov = returns
+        .iter()
+        .map(|(s, b)| (s - mean_s) * (b - mean_b))
+        .sum::<f64>()
+        / (n - 1.0);
+    let var_b = returns
+        .iter()
+        .map(|(_, b)| (b - mean_b).powi(2))
+        .sum::<f64>()
+        / (n - 1.0);
+    if !var_b.is_finite() || var_b <= EPSILON {
+        return None;
+    }
+
+    let beta = cov / var_b;
+    let periods_per_year = YEAR_MS / resolution.to_millis().max(1) as f64;
+    let alpha_pct = (mean_s - beta * mean_b) * periods_per_year * 100.0;
+    (alpha_pct.is_finite() && beta.is_finite()).then_some((alpha_pct, beta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(ts: u64, open: f64, low: f64, close: f64) -> Price {
+        Price {
+            open_time: ts,
+            close_time: ts + 60_000,
+            open,
+            high: open.max(close),
+            low,
+            close,
+            vlm: 1.0,
+        }
+    }
+
+    #[test]
+    fn buy_and_hold_applies_leverage_fees_and_liquidation() {
+        let candles = [
+            candle(0, 100.0, 99.0, 105.0),
+            candle(60_000, 105.0, 104.0, 110.0),
+        ];
+        let (curve, liquidated) = buy_and_hold_curve(1_000.0, 2, 10, &candles);
+        assert!(!liquidated);
+        // 20 units, 2.0 entry fee, 10% move doubled.
+        assert!((curve[0].equity - (1_000.0 - 2.0 + 100.0)).abs() < 1e-9);
+        assert!((curve[1].equity - (1_000.0 - 2.0 + 200.0 - 2.2)).abs() < 1e-9);
+
+        let crash = [
+            candle(0, 100.0, 40.0, 60.0),
+            candle(60_000, 60.0, 50.0, 70.0),
+        ];
+        let (curve, liquidated) = buy_and_hold_curve(1_000.0, 2, 0, &crash);
+        assert!(liquidated);
+        assert_eq!(curve.len(), 1);
+        assert_eq!(curve[0].equity, 0.0);
+    }
+
+    #[test]
+    fn strategy_tracking_the_benchmark_has_unit_beta() {
+        let bench = [100.0, 110.0, 99.0, 120.0, 118.0]
+            .iter()
+            .enumerate()
+            .map(|(i, equity)| EquityPoint {
+                ts: i as u64 * 60_000,
+                equity: *equity,
+                balance: *equity,
+                upnl: 0.0,
+            })
+            .collect::<Vec<_>>();
+        let (alpha, beta) = alpha_beta(&bench, &bench, TimeFrame::Min1).unwrap();
+        assert!((beta - 1.0).abs() < 1e-9);
+        assert!(alpha.abs() < 1e-6);
+    }
+}
@@ -155,6 +155,85 @@ fn write_prices(path: &Path, prices: &[Price]) -> Result<(), String> {
     Ok(())
 }
 
+/// Schema for funding-rate parquet files.
+fn funding_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("ts", DataType::UInt64, false),
+        Field::new("rate", DataType::Float64, false),
+    ])
+}
+
+/// Read all funding rows from a parquet file.
+fn read_all_funding(path: &Path) -> Vec<FundingRate> {
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+
+    let reader = match ParquetRecordBatchReaderBuilder::try_new(file).and_then(|b| b.build()) {
+        Ok(r) => r,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut rates = Vec::new();
+    for batch in reader.flatten() {
+        let ts = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        let rate = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+
+        for i in 0..batch.num_rows() {
+            rates.push(FundingRate {
+                time: ts.value(i),
+                rate: rate.value(i),
+            });
+        }
+    }
+    rates
+}
+
+/// Write `rates` to a parquet file (overwrites if exists).
+fn write_funding(path: &Path, rates: &[FundingRate]) -> Result<(), String> {
+    if rates.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create candle directory: {e}"))?;
+    }
+
+    let batch = RecordBatch::try_new(
+        Arc::new(funding_schema()),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(rates.iter().map(|r| r.time))),
+            Arc::new(Float64Array::from_iter_values(rates.iter().map(|r| r.rate))),
+        ],
+    )
+    .expect("schema matches arrays");
+    let props = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::try_new(3).unwrap()))
+        .build();
+
+    let file = fs::File::create(path).map_err(|e| format!("failed to create parquet file: {e}"))?;
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))
+        .map_err(|e| format!("failed to create arrow writer: {e}"))?;
+    writer
+        .write(&batch)
+        .map_err(|e| format!("failed to write batch: {e}"))?;
+    writer
+        .close()
+        .map_err(|e| format!("failed to close writer: {e}"))?;
+
+    Ok(())
+}
+
 /// Per-key state: serialises writes and broadcasts fetch progress to waiters.
 struct KeyState {
     /// Held for the duration of a fetch — only one fetcher per key at a time.
@@ -175,7 +254,8 @@ impl KeyState {
 }
 
 /// Persistent candle cache backed by Parquet files.
-/// One file per (exchange, market, asset, quote, timeframe) combination.
+/// One file per (exchange, market, asset, quote, timeframe) combination,
+/// plus one funding-rate file per perpetual market.
 ///
 /// Thread safety: per-key locking serialises writes. When a second fetcher
 /// requests the same key that is already being fetched, it subscribes to
@@ -322,6 +402,26 @@ impl CandleStore {
         }
     }
 
+    /// Replace every candle stored under `key` with `prices`.
+    /// Caller should hold the key lock via `acquire_key`.
+    pub fn replace_all(&self, key: &CandleKey, prices: &[Price]) {
+        let path = self.file_path(key);
+        let result = if prices.is_empty() {
+            fs::remove_file(&path).or_else(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    Ok(())
+                } else {
+                    Err(e.to_string())
+                }
+            })
+        } else {
+            write_prices(&path, prices)
+        };
+        if let Err(e) = result {
+            log::warn!("candle_store replace_all failed: {e}");
+        }
+    }
+
     /// Return all cached candles in `[start, end)` ordered by timestamp.
     pub fn range_to_vec(&self, key: &CandleKey, start: u64, end: u64) -> Vec<Price> {
         let path = self.file_path(key);
@@ -412,6 +512,37 @@ impl CandleStore {
             cached_in_range,
         }
     }
+
+    /// Insert funding rates, merging with existing data and deduplicating by timestamp.
+    /// Caller should hold the key lock via `acquire_key`.
+    pub fn insert_funding(&self, key: &CandleKey, new_rates: &[FundingRate]) {
+        if new_rates.is_empty() {
+            return;
+        }
+
+        let path = self.file_path(key);
+        let mut all = read_all_funding(&path);
+        let existing_ts: BTreeSet<u64> = all.iter().map(|r| r.time).collect();
+        all.extend(
+            new_rates
+                .iter()
+                .filter(|r| !existing_ts.contains(&r.time))
+                .copied(),
+        );
+        all.sort_unstable_by_key(|r| r.time);
+
+        if let Err(e) = write_funding(&path, &all) {
+            log::warn!("candle_store insert_funding failed: {e}");
+        }
+    }
+
+    /// Return all cached funding rates in `[start, end)` ordered by timestamp.
+    pub fn funding_range(&self, key: &CandleKey, start: u64, end: u64) -> Vec<FundingRate> {
+        read_all_funding(&self.file_path(key))
+            .into_iter()
+            .filter(|r| r.time >= start && r.time < end)
+            .collect()
+    }
 }
 
 /// RAII guard returned by `acquire_key`.
@@ -443,6 +574,13 @@ pub struct CandleKey {
     pub tf: String,
 }
 
+/// One funding settlement: `rate` is the fraction of notional longs pay shorts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FundingRate {
+    pub time: u64,
+    pub rate: f64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MissingSegment {
     pub start: u64,
@@ -0,0 +1,290 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::types::{BacktestConfig, BacktestResult, BacktestSummary, EquityPoint};
+use crate::TradeInfo;
+
+pub const MAX_COMPARE_RUNS: usize = 8;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompareRequest {
+    /// Stored run ids; the first one is the baseline for deltas.
+    pub run_ids: Vec<String>,
+}
+
+impl CompareRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.run_ids.len() < 2 || self.run_ids.len() > MAX_COMPARE_RUNS {
+            return Err(format!(
+                "runIds must contain between 2 and {MAX_COMPARE_RUNS} runs"
+            ));
+        }
+        let unique = self.run_ids.iter().collect::<BTreeSet<_>>();
+        if unique.len() != self.run_ids.len() {
+            return Err("runIds must not repeat".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparedRun {
+    pub run_id: String,
+    pub config: BacktestConfig,
+    pub summary: BacktestSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricDelta {
+    pub metric: String,
+    pub baseline: Option<f64>,
+    pub value: Option<f64>,
+    /// `value - baseline`; `None` when either side is undefined.
+    pub delta: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryDiff {
+    pub run_id: String,
+    pub metrics: Vec<MetricDelta>,
+}
+
+/// Equity curves resampled onto the union of their timestamps. Each series
+/// carries its last known equity forward and is `None` before its first point.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlignedEquity {
+    pub ts: Vec<u64>,
+    pub series: Vec<Vec<Option<f64>>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UniqueTrades {
+    pub run_id: String,
+    pub trades: Vec<TradeInfo>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestComparison {
+    pub baseline_run_id: String,
+    pub runs: Vec<ComparedRun>,
+    /// One entry per non-baseline run.
+    pub summary_diffs: Vec<SummaryDiff>,
+    pub equity: AlignedEquity,
+    /// Per run, trades whose open time no other compared run shares.
+    pub unique_trades: Vec<UniqueTrades>,
+}
+
+/// Diff `results` against the first one.
+pub fn compare(results: &[BacktestResult]) -> Option<BacktestComparison> {
+    let (baseline, others) = results.split_first()?;
+
+    let summary_diffs = others
+        .iter()
+        .map(|result| SummaryDiff {
+            run_id: result.run_id.clone(),
+            metrics: summary_deltas(&baseline.summary, &result.summary),
+        })
+        .collect();
+
+    Some(BacktestComparison {
+        baseline_run_id: baseline.run_id.clone(),
+        runs: results
+            .iter()
+            .map(|result| ComparedRun {
+                run_id: result.run_id.clone(),
+                config: result.config.clone(),
+                summary: result.summary.clone(),
+            })
+            .collect(),
+        summary_diffs,
+        equity: align_equity(
+            &results
+                .iter()
+                .map(|result| result.equity_curve.as_slice())
+                .collect::<Vec<_>>(),
+        ),
+        unique_trades: results
+            .iter()
+            .zip(unique_trades(
+                &results
+                    .iter()
+                    .map(|result| result.trades.as_slice())
+                    .collect::<Vec<_>>(),
+            ))
+            .map(|(result, trades)| UniqueTrades {
+                run_id: result.run_id.clone(),
+                trades,
+            })
+            .collect(),
+    })
+}
+
+fn summary_metrics(summary: &BacktestSummary) -> [(&'static str, Option<f64>); 15] {
+    [
+        ("finalEquity", Some(summary.final_equity)),
+        ("netPnl", Some(summary.net_pnl)),
+        ("returnPct", Some(summary.return_pct)),
+        ("maxDrawdownPct", Some(summary.max_drawdown_pct)),
+        ("totalTrades", Some(summary.total_trades as f64)),
+        ("winRatePct", Some(summary.win_rate_pct)),
+        ("avgWin", Some(summary.avg_win)),
+        ("avgLoss", Some(summary.avg_loss)),
+        ("profitFactor", summary.profit_factor),
+        ("expectancy", Some(summary.expectancy)),
+        ("sharpeRatio", summary.sharpe_ratio),
+        ("sortinoRatio", summary.sortino_ratio),
+        ("calmarRatio", summary.calmar_ratio),
+        ("timeInMarketPct", Some(summary.time_in_market_pct)),
+        (
+            "avgTradeDurationMs",
+            Some(summary.avg_trade_duration_ms as f64),
+        ),
+    ]
+}
+
+fn summary_deltas(baseline: &BacktestSummary, other: &BacktestSummary) -> Vec<MetricDelta> {
+    summary_metrics(baseline)
+        .into_iter()
+        .zip(summary_metrics(other))
+        .map(|((metric, baseline), (_, value))| MetricDelta {
+            metric: metric.to_string(),
+            baseline,
+            value,
+            delta: baseline.zip(value).map(|(b, v)| v - b),
+        })
+        .collect()
+}
+
+fn align_equity(curves: &[&[EquityPoint]]) -> AlignedEquity {
+    let ts = curves
+        .iter()
+        .flat_map(|curve| curve.iter().map(|point| point.ts))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let series = curves
+        .iter()
+        .map(|curve| {
+            let mut idx = 0;
+            let mut last = None;
+            ts.iter()
+                .map(|ts| {
+                    while let Some(point) = curve.get(idx)
+                        && point.ts <= *ts
+                    {
+                        last = Some(point.equity);
+                        idx += 1;
+                    }
+                    last
+                })
+                .collect()
+        })
+        .collect();
+
+    AlignedEquity { ts, series }
+}
+
+/// Trades whose open time appears in exactly one of `runs`.
+fn unique_trades(runs: &[&[TradeInfo]]) -> Vec<Vec<TradeInfo>> {
+    let mut runs_per_open: HashMap<u64, usize> = HashMap::new();
+    for trades in runs {
+        let opens = trades
+            .iter()
+            .map(|trade| trade.open.time)
+            .collect::<BTreeSet<_>>();
+        for open in opens {
+            *runs_per_open.entry(open).or_default() += 1;
+        }
+    }
+
+    runs.iter()
+        .map(|trades| {
+            trades
+                .iter()
+                .filter(|trade| runs_per_open.get(&trade.open.time) == Some(&1))
+                .cloned()
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FillInfo, FillType, Side};
+
+    fn point(ts: u64, equity: f64) -> EquityPoint {
+        EquityPoint {
+            ts,
+            equity,
+            balance: equity,
+            upnl: 0.0,
+        }
+    }
+
+    fn trade(open: u64) -> TradeInfo {
+        let fill = |time| FillInfo {
+            time,
+            price: 100.0,
+            fill_type: FillType::Market,
+        };
+        TradeInfo {
+            side: Side::Long,
+            size: 1.0,
+            pnl: 0.0,
+            total_pnl: 0.0,
+            fees: 0.0,
+            funding: 0.0,
+            open: fill(open),
+            close: fill(open + 10),
+            strategy: None,
+        }
+    }
+
+    #[test]
+    fn align_equity_carries_last_value_forward() {
+        let a = [point(10, 100.0), point(30, 110.0)];
+        let b = [point(20, 200.0), point(30, 190.0), point(40, 180.0)];
+        let aligned = align_equity(&[&a, &b]);
+        assert_eq!(aligned.ts, vec![10, 20, 30, 40]);
+        assert_eq!(
+            aligned.series[0],
+            vec![Some(100.0), Some(100.0), Some(110.0), Some(110.0)]
+        );
+        assert_eq!(
+            aligned.series[1],
+            vec![None, Some(200.0), Some(190.0), Some(180.0)]
+        );
+    }
+
+    #[test]
+    fn unique_trades_match_on_open_time() {
+        let a = [trade(0), trade(100), trade(200)];
+        let b = [trade(100), trade(300)];
+        let c = [trade(200)];
+        let unique = unique_trades(&[&a, &b, &c]);
+        let opens = |trades: &[TradeInfo]| trades.iter().map(|t| t.open.time).collect::<Vec<_>>();
+        assert_eq!(opens(&unique[0]), vec![0]);
+        assert_eq!(opens(&unique[1]), vec![300]);
+        assert!(unique[2].is_empty());
+    }
+
+    #[test]
+    fn compare_request_bounds_run_count() {
+        let request = |ids: &[&str]| CompareRequest {
+            run_ids: ids.iter().map(|id| id.to_string()).collect(),
+        };
+        assert!(request(&["a"]).validate().is_err());
+        assert!(request(&["a", "a"]).validate().is_err());
+        assert!(request(&["a", "b"]).validate().is_ok());
+    }
+}
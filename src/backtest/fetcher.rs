@@ -9,13 +9,15 @@ use serde_json::Value;
 use tokio::sync::Mutex;
 use tokio::time::{Instant, sleep};
 
-use super::candle_store::{CandleKey, CandleStore};
-use crate::{Error, Price, TimeFrame};
+use super::candle_store::{CandleKey, CandleStore, FundingRate};
+use crate::{Error, HL_MAX_CANDLES, Price, TimeFrame, check_asset_fix};
 
 const MAX_HTTP_RETRIES: usize = 5;
 const RETRY_BASE_DELAY_MS: u64 = 500;
 const RETRY_MAX_DELAY_MS: u64 = 20_000;
 const RETRY_JITTER_MS: u64 = 250;
+const HYPERLIQUID_INFO_URL: &str = "https://api.hyperliquid.xyz/info";
+const FUNDING_KEY_TF: &str = "FUNDING";
 
 #[derive(Clone)]
 pub(crate) struct RequestLimiter {
@@ -76,6 +78,11 @@ pub enum Exchange {
     Binance,
     Bybit,
     Htx,
+    Hyperliquid,
+    /// Candles imported from local files; never touches the network.
+    Local,
+    /// Candles written by the synthetic generator; never touches the network.
+    Synthetic,
 }
 
 impl Exchange {
@@ -84,8 +91,16 @@ impl Exchange {
             Exchange::Binance => "Binance",
             Exchange::Bybit => "Bybit",
             Exchange::Htx => "HTX",
+            Exchange::Hyperliquid => "Hyperliquid",
+            Exchange::Local => "Local",
+            Exchange::Synthetic => "Synthetic",
         }
     }
+
+    /// Offline sources are only ever read from the candle store.
+    pub fn is_offline(&self) -> bool {
+        matches!(self, Exchange::Local | Exchange::Synthetic)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -140,13 +155,114 @@ impl DataSource {
             market: self.market.as_str().to_uppercase(),
             asset_quote: format!(
                 "{}_{}",
-                asset.trim().to_uppercase(),
+                check_asset_fix(&self.normalize_asset(asset)),
                 self.quote_asset.to_uppercase()
             ),
             tf: tf.to_string().to_uppercase(),
         }
     }
 
+    /// Funding rates share the candle key layout with a fixed `FUNDING` suffix.
+    pub fn funding_key(&self, asset: &str) -> CandleKey {
+        CandleKey {
+            tf: FUNDING_KEY_TF.to_string(),
+            ..self.candle_key(asset, TimeFrame::Hour1)
+        }
+    }
+
+    /// Nominal spacing between funding settlements; `None` for markets without funding.
+    pub fn funding_interval_ms(&self) -> Option<u64> {
+        if self.market == MarketType::Spot {
+            return None;
+        }
+        match self.exchange {
+            Exchange::Hyperliquid => Some(TimeFrame::Hour1.to_millis()),
+            Exchange::Binance
+            | Exchange::Bybit
+            | Exchange::Htx
+            | Exchange::Local
+            | Exchange::Synthetic => Some(8 * TimeFrame::Hour1.to_millis()),
+        }
+    }
+
+    /// Page size of the venue's funding history endpoint.
+    fn funding_request_limit(&self) -> usize {
+        match self.exchange {
+            Exchange::Binance => 1000,
+            Exchange::Bybit => 200,
+            _ => 500,
+        }
+    }
+
+    fn build_funding_request(
+        &self,
+        asset: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<(String, Option<Value>), Error> {
+        let symbol = self.format_asset(asset)?;
+        match self.exchange {
+            Exchange::Binance => Ok((
+                format!(
+                    "https://fapi.binance.com/fapi/v1/fundingRate?symbol={symbol}&startTime={start}&endTime={end}&limit=1000"
+                ),
+                None,
+            )),
+            Exchange::Bybit => Ok((
+                format!(
+                    "https://api.bybit.com/v5/market/funding/history?category=linear&symbol={symbol}&startTime={start}&endTime={end}&limit=200"
+                ),
+                None,
+            )),
+            Exchange::Hyperliquid => Ok((
+                HYPERLIQUID_INFO_URL.to_string(),
+                Some(serde_json::json!({
+                    "type": "fundingHistory",
+                    "coin": symbol,
+                    "startTime": start,
+                    "endTime": end,
+                })),
+            )),
+            Exchange::Htx | Exchange::Local | Exchange::Synthetic => Err(Error::Custom(format!(
+                "Historical funding is not available for {}",
+                self.exchange.name()
+            ))),
+        }
+    }
+
+    fn parse_funding(&self, body: &str) -> Result<Vec<FundingRate>, Error> {
+        let json: Value =
+            serde_json::from_str(body).map_err(|e| Error::Custom(format!("Invalid JSON: {e}")))?;
+
+        match self.exchange {
+            Exchange::Binance => parse_funding_list(&json, "fundingTime", "fundingRate"),
+            Exchange::Bybit => parse_funding_list(
+                json.get("result")
+                    .and_then(|v| v.get("list"))
+                    .ok_or_else(|| Error::Custom("Missing result.list".to_string()))?,
+                "fundingRateTimestamp",
+                "fundingRate",
+            ),
+            Exchange::Hyperliquid => parse_funding_list(&json, "time", "fundingRate"),
+            Exchange::Htx | Exchange::Local | Exchange::Synthetic => Err(Error::Custom(format!(
+                "Historical funding is not available for {}",
+                self.exchange.name()
+            ))),
+        }
+    }
+
+    /// Hyperliquid coin names are case-sensitive (`kPEPE`, `xyz:XYZ100`), other venues are not.
+    pub fn normalize_asset(&self, asset: &str) -> String {
+        let asset = asset.trim();
+        match self.exchange {
+            Exchange::Hyperliquid => match asset.split_once(':') {
+                Some((dex, coin)) => format!("{}:{}", dex.to_lowercase(), coin),
+                None => asset.to_string(),
+            },
+            _ => asset.to_uppercase(),
+        }
+    }
+
     fn normalize_quote(quote_asset: String) -> String {
         let trimmed = quote_asset.trim();
         if trimmed.is_empty() {
@@ -239,10 +355,26 @@ impl DataSource {
             (TimeFrame::Week, "1week"),
             (TimeFrame::Month, "1mon"),
         ];
+        const HYPERLIQUID: &[(TimeFrame, &str)] = &[
+            (TimeFrame::Min1, "1m"),
+            (TimeFrame::Min3, "3m"),
+            (TimeFrame::Min5, "5m"),
+            (TimeFrame::Min15, "15m"),
+            (TimeFrame::Min30, "30m"),
+            (TimeFrame::Hour1, "1h"),
+            (TimeFrame::Hour2, "2h"),
+            (TimeFrame::Hour4, "4h"),
+            (TimeFrame::Hour12, "12h"),
+            (TimeFrame::Day1, "1d"),
+            (TimeFrame::Day3, "3d"),
+            (TimeFrame::Week, "1w"),
+            (TimeFrame::Month, "1M"),
+        ];
         let map = match self.exchange {
-            Exchange::Binance => BINANCE,
+            Exchange::Binance | Exchange::Local | Exchange::Synthetic => BINANCE,
             Exchange::Bybit => BYBIT,
             Exchange::Htx => HTX,
+            Exchange::Hyperliquid => HYPERLIQUID,
         };
 
         Ok(map)
@@ -253,6 +385,7 @@ impl DataSource {
             (Exchange::Binance, MarketType::Spot) => Some(1000),
             (Exchange::Binance, MarketType::Futures) => Some(1500),
             (Exchange::Bybit, _) => Some(1000),
+            (Exchange::Hyperliquid, _) => Some(HL_MAX_CANDLES as usize),
             _ => None,
         }
     }
@@ -301,16 +434,56 @@ impl DataSource {
                 };
                 format!("{base_url}?{symbol_key}={symbol}&period={interval}&size={size}")
             }
+            Exchange::Hyperliquid => HYPERLIQUID_INFO_URL.to_string(),
+            Exchange::Local | Exchange::Synthetic => {
+                return Err(Error::Custom(format!(
+                    "{} data source has no remote endpoint",
+                    self.exchange.name()
+                )));
+            }
         };
 
         Ok(url)
     }
 
+    /// JSON body for venues queried over POST; `None` means a plain GET.
+    fn build_payload(
+        &self,
+        asset: &str,
+        interval: &'static str,
+        start: u64,
+        end: u64,
+    ) -> Result<Option<Value>, Error> {
+        match self.exchange {
+            Exchange::Hyperliquid => Ok(Some(serde_json::json!({
+                "type": "candleSnapshot",
+                "req": {
+                    "coin": self.format_asset(asset)?,
+                    "interval": interval,
+                    "startTime": start,
+                    "endTime": end,
+                }
+            }))),
+            _ => Ok(None),
+        }
+    }
+
     fn format_asset(&self, asset: &str) -> Result<String, Error> {
         let (separator, lowercase) = match (self.exchange, self.market) {
-            (Exchange::Binance, _) | (Exchange::Bybit, _) => ("", false),
+            (Exchange::Binance, _)
+            | (Exchange::Bybit, _)
+            | (Exchange::Local, _)
+            | (Exchange::Synthetic, _) => ("", false),
             (Exchange::Htx, MarketType::Spot) => ("", true),
             (Exchange::Htx, MarketType::Futures) => ("-", false),
+            (Exchange::Hyperliquid, MarketType::Futures) => {
+                return Ok(self.normalize_asset(asset));
+            }
+            (Exchange::Hyperliquid, MarketType::Spot) => {
+                return Err(Error::Custom(
+                    "Hyperliquid spot candles are not supported for backtests".to_string(),
+                ));
+            }
         };
 
         let base = asset.trim().to_uppercase();
@@ -343,6 +516,11 @@ impl DataSource {
             Exchange::Binance => parse_binance_like(&json, interval_ms),
             Exchange::Bybit => parse_bybit(&json, interval_ms),
             Exchange::Htx => parse_htx(&json, interval_ms),
+            Exchange::Hyperliquid => parse_hyperliquid(&json, interval_ms),
+            Exchange::Local | Exchange::Synthetic => Err(Error::Custom(format!(
+                "{} data source has no remote response to parse",
+                self.exchange.name()
+            ))),
         }
     }
 
@@ -411,7 +589,7 @@ impl Fetcher {
     where
         F: FnMut(u64, u64),
     {
-        let asset = asset.trim().to_uppercase();
+        let asset = self.current_source.normalize_asset(asset);
         if asset.is_empty() {
             return Ok(Vec::new());
         }
@@ -505,6 +683,97 @@ impl Fetcher {
         Ok(out)
     }
 
+    /// Historical funding settlements in `[start, end)`, served from the candle
+    /// store when it already covers the range. Spot markets have no funding.
+    pub async fn fetch_funding(
+        &mut self,
+        asset: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<FundingRate>, Error> {
+        let asset = self.current_source.normalize_asset(asset);
+        let Some(interval_ms) = self.current_source.funding_interval_ms() else {
+            return Ok(Vec::new());
+        };
+        if asset.is_empty() || end <= start {
+            return Ok(Vec::new());
+        }
+
+        let key = self.current_source.funding_key(&asset);
+        let _guard = self.store.acquire_key(&key, |_, _| {}).await;
+        if self.current_source.exchange.is_offline() {
+            return Ok(self.store.funding_range(&key, start, end));
+        }
+
+        // Settlements are not perfectly regular, so only the uncovered head and
+        // tail of the range are refetched.
+        let end = end.min(now_ms());
+        let cached = self.store.funding_range(&key, start, end);
+        let mut missing = Vec::new();
+        match (cached.first(), cached.last()) {
+            (Some(first), Some(last)) => {
+                if first.time >= start.saturating_add(interval_ms) {
+                    missing.push((start, first.time));
+                }
+                if last.time.saturating_add(interval_ms) < end {
+                    missing.push((last.time + 1, end));
+                }
+            }
+            _ => missing.push((start, end)),
+        }
+        if missing.is_empty() {
+            return Ok(cached);
+        }
+
+        for (seg_start, seg_end) in missing {
+            let rates = self
+                .fetch_funding_segment(&asset, seg_start, seg_end)
+                .await?;
+            self.store.insert_funding(&key, &rates);
+        }
+        Ok(self.store.funding_range(&key, start, end))
+    }
+
+    async fn fetch_funding_segment(
+        &self,
+        asset: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<FundingRate>, Error> {
+        let limit = self.current_source.funding_request_limit();
+        // Bybit pages backwards from `endTime`; the others page forwards from `startTime`.
+        let backwards = self.current_source.exchange == Exchange::Bybit;
+        let mut lo = start;
+        let mut hi = end;
+        let mut out = Vec::new();
+        while lo < hi {
+            let (url, payload) = self.current_source.build_funding_request(asset, lo, hi)?;
+            let body = self.request_body(&url, payload.as_ref()).await?;
+            let page = self.current_source.parse_funding(&body)?;
+            let count = page.len();
+            let (Some(min), Some(max)) = (
+                page.iter().map(|r| r.time).min(),
+                page.iter().map(|r| r.time).max(),
+            ) else {
+                break;
+            };
+            out.extend(page);
+            if count < limit {
+                break;
+            }
+            if backwards {
+                hi = min.saturating_sub(1);
+            } else {
+                lo = max + 1;
+            }
+        }
+
+        out.retain(|r| r.time >= start && r.time < end);
+        out.sort_unstable_by_key(|r| r.time);
+        out.dedup_by_key(|r| r.time);
+        Ok(out)
+    }
+
     async fn fetch_segment<F>(
         &self,
         asset: &str,
@@ -516,6 +785,12 @@ impl Fetcher {
     where
         F: FnMut(u64),
     {
+        if self.current_source.exchange.is_offline() {
+            // Gaps in offline data stay gaps; only the importer and generator write these keys.
+            on_segment_progress(0);
+            return Ok(Vec::new());
+        }
+
         let plan = self.current_source.interval_plan(tf)?;
         let base_interval_ms = plan.base_tf.to_millis();
 
@@ -582,6 +857,26 @@ impl Fetcher {
                 on_segment_progress(out.len() as u64);
                 out
             }
+            Exchange::Local | Exchange::Synthetic => Vec::new(),
+            Exchange::Hyperliquid => {
+                // candleSnapshot returns at most HL_MAX_CANDLES per call, so page
+                // through fixed windows instead of relying on a limit parameter.
+                let window_ms = base_interval_ms.saturating_mul(HL_MAX_CANDLES);
+                let mut cursor = start;
+                let mut out = Vec::new();
+                let mut loaded = 0_u64;
+                while cursor < end {
+                    let window_end = cursor.saturating_add(window_ms).min(end);
+                    let data = self
+                        .fetch_once(asset, plan.base_tf, plan.interval, cursor, window_end)
+                        .await?;
+                    loaded = loaded.saturating_add(data.len() as u64);
+                    out.extend(data);
+                    on_segment_progress(loaded);
+                    cursor = window_end;
+                }
+                out
+            }
         };
 
         collected.retain(|p| p.close_time > start && p.open_time < end);
@@ -610,18 +905,25 @@ impl Fetcher {
         let url = self
             .current_source
             .build_url(asset, base_tf, interval, start, end)?;
+        let payload = self
+            .current_source
+            .build_payload(asset, interval, start, end)?;
 
-        let body = self.request_body(&url).await?;
+        let body = self.request_body(&url, payload.as_ref()).await?;
         self.current_source.parse_candles(&body, base_tf)
     }
 
-    async fn request_body(&self, url: &str) -> Result<String, Error> {
+    async fn request_body(&self, url: &str, payload: Option<&Value>) -> Result<String, Error> {
         for attempt in 0..=MAX_HTTP_RETRIES {
             if let Some(limiter) = &self.request_limiter {
                 limiter.acquire().await;
             }
 
-            let response = match self.client.get(url).send().await {
+            let request = match payload {
+                Some(payload) => self.client.post(url).json(payload),
+                None => self.client.get(url),
+            };
+            let response = match request.send().await {
                 Ok(response) => response,
                 Err(e) => {
                     if attempt < MAX_HTTP_RETRIES {
@@ -722,7 +1024,7 @@ fn estimate_points_in_range(start: u64, end: u64, step: u64) -> u64 {
     std::cmp::max(1, div_ceil(end - start, step))
 }
 
-fn aggregate_prices(prices: &[Price], target_ms: u64) -> Vec<Price> {
+pub(super) fn aggregate_prices(prices: &[Price], target_ms: u64) -> Vec<Price> {
     if prices.is_empty() {
         return Vec::new();
     }
@@ -932,6 +1234,66 @@ fn parse_htx(json: &Value, interval_ms: u64) -> Result<Vec<Price>, Error> {
     Ok(out)
 }
 
+fn parse_hyperliquid(json: &Value, interval_ms: u64) -> Result<Vec<Price>, Error> {
+    let list = json
+        .as_array()
+        .ok_or_else(|| Error::Custom("Expected array response".to_string()))?;
+
+    let mut out = Vec::with_capacity(list.len());
+    for item in list {
+        let obj = item
+            .as_object()
+            .ok_or_else(|| Error::Custom("Expected object candle".to_string()))?;
+        let field = |key: &str| {
+            obj.get(key)
+                .ok_or_else(|| Error::Custom(format!("Missing {key}")))
+        };
+        let start = parse_u64(field("t")?)?;
+        let open = parse_f64(field("o")?)?;
+        let high = parse_f64(field("h")?)?;
+        let low = parse_f64(field("l")?)?;
+        let close = parse_f64(field("c")?)?;
+        let volume = parse_f64(field("v")?)?;
+        let close_time = obj.get("T").and_then(|v| parse_u64(v).ok());
+        out.push(build_price(
+            start,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            interval_ms,
+            close_time,
+        ));
+    }
+
+    Ok(out)
+}
+
+fn parse_funding_list(
+    json: &Value,
+    time_key: &str,
+    rate_key: &str,
+) -> Result<Vec<FundingRate>, Error> {
+    let list = json
+        .as_array()
+        .ok_or_else(|| Error::Custom("Expected array response".to_string()))?;
+
+    let mut out = Vec::with_capacity(list.len());
+    for item in list {
+        let field = |key: &str| {
+            item.get(key)
+                .ok_or_else(|| Error::Custom(format!("Missing {key}")))
+        };
+        out.push(FundingRate {
+            time: parse_u64(field(time_key)?)?,
+            rate: parse_f64(field(rate_key)?)?,
+        });
+    }
+
+    Ok(out)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn build_price(
     start: u64,
@@ -1067,4 +1429,85 @@ mod tests {
         assert_eq!(price.high, 2.0);
         assert_eq!(price.vlm, 10.0);
     }
+
+    #[test]
+    fn test_parse_hyperliquid() {
+        let json = json!([
+            {
+                "t": 1_700_000_000_000_u64,
+                "T": 1_700_003_599_999_u64,
+                "s": "BTC",
+                "i": "1h",
+                "o": "35000.0",
+                "c": "35100.5",
+                "h": "35200.0",
+                "l": "34900.0",
+                "v": "12.34",
+                "n": 1500
+            }
+        ]);
+        let out = parse_hyperliquid(&json, 3_600_000).unwrap();
+        assert_eq!(out.len(), 1);
+        let price = out[0];
+        assert_eq!(price.open_time, 1_700_000_000_000);
+        assert_eq!(price.close_time, 1_700_003_599_999);
+        assert_eq!(price.open, 35_000.0);
+        assert_eq!(price.high, 35_200.0);
+        assert_eq!(price.low, 34_900.0);
+        assert_eq!(price.close, 35_100.5);
+        assert_eq!(price.vlm, 12.34);
+
+        assert!(parse_hyperliquid(&json!([{"t": 0, "o": "1"}]), 60_000).is_err());
+    }
+
+    #[test]
+    fn test_parse_funding() {
+        let hl = DataSource::new(Exchange::Hyperliquid, MarketType::Futures);
+        let body =
+            r#"[{"coin":"BTC","fundingRate":"0.0000125","premium":"0.0001","time":1700000000000}]"#;
+        let out = hl.parse_funding(body).unwrap();
+        assert_eq!(
+            out,
+            vec![FundingRate {
+                time: 1_700_000_000_000,
+                rate: 0.0000125
+            }]
+        );
+
+        let bybit = DataSource::new(Exchange::Bybit, MarketType::Futures);
+        let body = r#"{"result":{"list":[{"symbol":"BTCUSDT","fundingRate":"-0.0002","fundingRateTimestamp":"1700028800000"}]}}"#;
+        let out = bybit.parse_funding(body).unwrap();
+        assert_eq!(out[0].time, 1_700_028_800_000);
+        assert_eq!(out[0].rate, -0.0002);
+
+        let spot = DataSource::new(Exchange::Binance, MarketType::Spot);
+        assert_eq!(spot.funding_interval_ms(), None);
+        assert_eq!(spot.funding_key("btc").tf, "FUNDING");
+    }
+
+    #[test]
+    fn test_hyperliquid_hip3_assets() {
+        let source = DataSource::with_quote(Exchange::Hyperliquid, MarketType::Futures, "usdc");
+        assert_eq!(source.normalize_asset(" XYZ:XYZ100 "), "xyz:XYZ100");
+        assert_eq!(source.format_asset("kPEPE").unwrap(), "kPEPE");
+
+        let key = source.candle_key("xyz:XYZ100", TimeFrame::Hour1);
+        assert_eq!(key.exchange, "HYPERLIQUID");
+        assert_eq!(key.asset_quote, "xyz_XYZ100_USDC");
+        assert_eq!(
+            source.candle_key("kPEPE", TimeFrame::Hour1).asset_quote,
+            "kPEPE_USDC"
+        );
+
+        let payload = source
+            .build_payload("xyz:XYZ100", "1h", 0, 3_600_000)
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload["type"], "candleSnapshot");
+        assert_eq!(payload["req"]["coin"], "xyz:XYZ100");
+        assert_eq!(payload["req"]["endTime"], 3_600_000);
+
+        let spot = DataSource::new(Exchange::Hyperliquid, MarketType::Spot);
+        assert!(spot.format_asset("BTC").is_err());
+    }
 }
@@ -0,0 +1,551 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use arrow::array::{Array, Float64Array, Int64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Int64Type, TimeUnit};
+use log::info;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use super::candle_store::CandleStore;
+use super::fetcher::{DataSource, aggregate_prices};
+use crate::{Error, Price, TimeFrame};
+
+/// Timestamps below this are treated as unix seconds rather than milliseconds.
+const SECONDS_TS_THRESHOLD: u64 = 100_000_000_000;
+/// Timestamps at or above this are microseconds (and nanoseconds past `NANOS_TS_THRESHOLD`).
+const MICROS_TS_THRESHOLD: u64 = 100_000_000_000_000;
+const NANOS_TS_THRESHOLD: u64 = 100_000_000_000_000_000;
+
+const OPEN_TIME_COLUMNS: &[&str] = &["open_time", "ts", "timestamp", "time", "t"];
+const OPEN_COLUMNS: &[&str] = &["open", "o"];
+const HIGH_COLUMNS: &[&str] = &["high", "h"];
+const LOW_COLUMNS: &[&str] = &["low", "l"];
+const CLOSE_COLUMNS: &[&str] = &["close", "c"];
+const VOLUME_COLUMNS: &[&str] = &["volume", "vlm", "vol", "v"];
+const CLOSE_TIME_COLUMNS: &[&str] = &["close_time"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("csv") => Ok(ImportFormat::Csv),
+            Some("parquet") | Some("pq") => Ok(ImportFormat::Parquet),
+            _ => Err(Error::Custom(format!(
+                "Unsupported candle file {}: expected .csv or .parquet",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportSummary {
+    pub rows_read: usize,
+    pub source_interval_ms: u64,
+    pub first_open_time: u64,
+    pub last_open_time: u64,
+    /// Candles written per resampled timeframe.
+    pub written: Vec<(TimeFrame, usize)>,
+}
+
+/// Import candles from a local CSV/Parquet file into `store` under `source`'s keys.
+///
+/// When `timeframes` is empty every supported timeframe that is a whole multiple of
+/// the file's candle interval is written, so indicator series on other timeframes
+/// can be served offline as well.
+pub async fn import_candles(
+    store: &CandleStore,
+    source: &DataSource,
+    asset: &str,
+    path: &Path,
+    timeframes: &[TimeFrame],
+) -> Result<ImportSummary, Error> {
+    let asset = source.normalize_asset(asset);
+    if asset.is_empty() {
+        return Err(Error::Custom("asset must not be empty".to_string()));
+    }
+
+    let rows = match ImportFormat::from_path(path)? {
+        ImportFormat::Csv => read_csv(path)?,
+        ImportFormat::Parquet => read_parquet(path)?,
+    };
+    let rows_read = rows.len();
+    let (prices, interval_ms) = validate_rows(rows)?;
+    let targets = target_timeframes(interval_ms, timeframes)?;
+
+    let mut written = Vec::with_capacity(targets.len());
+    for tf in targets {
+        let resampled = resample(&prices, interval_ms, tf);
+        let key = source.candle_key(&asset, tf);
+        let _guard = store.acquire_key(&key, |_, _| {}).await;
+        store.insert_many(&key, &resampled);
+        written.push((tf, resampled.len()));
+    }
+
+    let summary = ImportSummary {
+        rows_read,
+        source_interval_ms: interval_ms,
+        first_open_time: prices.first().map(|p| p.open_time).unwrap_or_default(),
+        last_open_time: prices.last().map(|p| p.open_time).unwrap_or_default(),
+        written,
+    };
+    info!(
+        "imported {} candles for {} from {} ({:?})",
+        summary.rows_read,
+        asset,
+        path.display(),
+        summary.written
+    );
+    Ok(summary)
+}
+
+/// Timeframes `interval_ms` candles are written to: the requested ones, or
+/// every supported whole multiple when none are given.
+pub(super) fn target_timeframes(
+    interval_ms: u64,
+    timeframes: &[TimeFrame],
+) -> Result<Vec<TimeFrame>, Error> {
+    let targets: Vec<TimeFrame> = if timeframes.is_empty() {
+        TimeFrame::available_tfs()
+            .into_iter()
+            .filter(|tf| tf.to_millis().is_multiple_of(interval_ms))
+            .collect()
+    } else {
+        for tf in timeframes {
+            if !tf.to_millis().is_multiple_of(interval_ms) {
+                return Err(Error::Custom(format!(
+                    "Cannot resample {interval_ms}ms candles to {tf}"
+                )));
+            }
+        }
+        timeframes.to_vec()
+    };
+    if targets.is_empty() {
+        return Err(Error::Custom(format!(
+            "Candle interval {interval_ms}ms does not map onto any supported timeframe"
+        )));
+    }
+    Ok(targets)
+}
+
+pub(super) fn resample(prices: &[Price], interval_ms: u64, tf: TimeFrame) -> Vec<Price> {
+    if tf.to_millis() == interval_ms {
+        prices.to_vec()
+    } else {
+        aggregate_prices(prices, tf.to_millis())
+    }
+}
+
+/// Raw candle row before timestamp normalisation.
+#[derive(Debug, Clone, Copy)]
+struct CandleRow {
+    open_time: u64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    close_time: Option<u64>,
+}
+
+fn find_column<'a, I>(names: I, aliases: &[&str]) -> Option<usize>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let names: Vec<String> = names
+        .into_iter()
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    aliases
+        .iter()
+        .find_map(|alias| names.iter().position(|name| name == alias))
+}
+
+fn required_column<'a, I>(names: I, aliases: &[&str]) -> Result<usize, Error>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    find_column(names, aliases)
+        .ok_or_else(|| Error::Custom(format!("Missing required column {}", aliases[0])))
+}
+
+fn read_csv(path: &Path) -> Result<Vec<CandleRow>, Error> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| Error::Custom(format!("read {}: {e}", path.display())))?;
+    parse_csv(&text)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<CandleRow>, Error> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| Error::Custom("CSV file is empty".to_string()))?;
+    let header: Vec<&str> = split_csv_line(header);
+
+    let ts_idx = required_column(header.iter().copied(), OPEN_TIME_COLUMNS)?;
+    let open_idx = required_column(header.iter().copied(), OPEN_COLUMNS)?;
+    let high_idx = required_column(header.iter().copied(), HIGH_COLUMNS)?;
+    let low_idx = required_column(header.iter().copied(), LOW_COLUMNS)?;
+    let close_idx = required_column(header.iter().copied(), CLOSE_COLUMNS)?;
+    let volume_idx = find_column(header.iter().copied(), VOLUME_COLUMNS);
+    let close_time_idx = find_column(header.iter().copied(), CLOSE_TIME_COLUMNS);
+
+    let mut out = Vec::new();
+    for (line_no, line) in lines {
+        let fields = split_csv_line(line);
+        let field = |idx: usize| {
+            fields.get(idx).copied().ok_or_else(|| {
+                Error::Custom(format!("line {}: expected column {}", line_no + 1, idx + 1))
+            })
+        };
+        let num = |idx: usize| -> Result<f64, Error> {
+            field(idx)?.parse::<f64>().map_err(|_| {
+                Error::Custom(format!(
+                    "line {}: invalid number in column {}",
+                    line_no + 1,
+                    idx + 1
+                ))
+            })
+        };
+        let ts = |idx: usize| -> Result<u64, Error> {
+            let raw = field(idx)?;
+            raw.parse::<u64>()
+                .or_else(|_| {
+                    raw.parse::<f64>()
+                        .ok()
+                        .filter(|v| v.is_finite() && *v >= 0.0)
+                        .map(|v| v as u64)
+                        .ok_or(())
+                })
+                .map_err(|_| {
+                    Error::Custom(format!("line {}: invalid timestamp {raw:?}", line_no + 1))
+                })
+        };
+
+        out.push(CandleRow {
+            open_time: ts(ts_idx)?,
+            open: num(open_idx)?,
+            high: num(high_idx)?,
+            low: num(low_idx)?,
+            close: num(close_idx)?,
+            volume: volume_idx.map(num).transpose()?.unwrap_or(0.0),
+            close_time: close_time_idx.map(ts).transpose()?,
+        });
+    }
+
+    Ok(out)
+}
+
+fn split_csv_line(line: &str) -> Vec<&str> {
+    line.split(',')
+        .map(|field| field.trim().trim_matches('"'))
+        .collect()
+}
+
+fn read_parquet(path: &Path) -> Result<Vec<CandleRow>, Error> {
+    let file =
+        fs::File::open(path).map_err(|e| Error::Custom(format!("open {}: {e}", path.display())))?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| Error::Custom(format!("read parquet {}: {e}", path.display())))?;
+
+    let schema = builder.schema().clone();
+    let names = || schema.fields().iter().map(|field| field.name().as_str());
+    let ts_idx = required_column(names(), OPEN_TIME_COLUMNS)?;
+    let open_idx = required_column(names(), OPEN_COLUMNS)?;
+    let high_idx = required_column(names(), HIGH_COLUMNS)?;
+    let low_idx = required_column(names(), LOW_COLUMNS)?;
+    let close_idx = required_column(names(), CLOSE_COLUMNS)?;
+    let volume_idx = find_column(names(), VOLUME_COLUMNS);
+    let close_time_idx = find_column(names(), CLOSE_TIME_COLUMNS);
+
+    let reader = builder
+        .build()
+        .map_err(|e| Error::Custom(format!("read parquet {}: {e}", path.display())))?;
+
+    let mut out = Vec::new();
+    for batch in reader {
+        let batch =
+            batch.map_err(|e| Error::Custom(format!("read parquet {}: {e}", path.display())))?;
+        let ts = int_column(batch.column(ts_idx))?;
+        let open = float_column(batch.column(open_idx))?;
+        let high = float_column(batch.column(high_idx))?;
+        let low = float_column(batch.column(low_idx))?;
+        let close = float_column(batch.column(close_idx))?;
+        let volume = volume_idx
+            .map(|idx| float_column(batch.column(idx)))
+            .transpose()?;
+        let close_time = close_time_idx
+            .map(|idx| int_column(batch.column(idx)))
+            .transpose()?;
+
+        for i in 0..batch.num_rows() {
+            if ts.is_null(i)
+                || open.is_null(i)
+                || high.is_null(i)
+                || low.is_null(i)
+                || close.is_null(i)
+            {
+                return Err(Error::Custom(format!("row {i}: null candle value")));
+            }
+            let open_time = u64::try_from(ts.value(i))
+                .map_err(|_| Error::Custom(format!("row {i}: negative timestamp")))?;
+            out.push(CandleRow {
+                open_time,
+                open: open.value(i),
+                high: high.value(i),
+                low: low.value(i),
+                close: close.value(i),
+                volume: volume
+                    .as_ref()
+                    .filter(|col| !col.is_null(i))
+                    .map(|col| col.value(i))
+                    .unwrap_or(0.0),
+                close_time: close_time
+                    .as_ref()
+                    .filter(|col| !col.is_null(i))
+                    .and_then(|col| u64::try_from(col.value(i)).ok()),
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+fn int_column(column: &dyn Array) -> Result<Int64Array, Error> {
+    // Timestamp columns cast to their raw integer value in the column's own unit,
+    // so typed timestamps are scaled to ms here; plain integers are scaled by
+    // magnitude in `validate_rows`.
+    let casted = cast(column, &DataType::Int64)
+        .map_err(|e| Error::Custom(format!("timestamp column is not an integer: {e}")))?;
+    let raw = casted
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .cloned()
+        .expect("cast to Int64 yields Int64Array");
+    Ok(match column.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            raw.unary::<_, Int64Type>(|v| v.saturating_mul(1000))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => raw.unary::<_, Int64Type>(|v| v / 1000),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            raw.unary::<_, Int64Type>(|v| v / 1_000_000)
+        }
+        _ => raw,
+    })
+}
+
+fn float_column(column: &dyn Array) -> Result<Float64Array, Error> {
+    let casted = cast(column, &DataType::Float64)
+        .map_err(|e| Error::Custom(format!("price column is not numeric: {e}")))?;
+    Ok(casted
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .cloned()
+        .expect("cast to Float64 yields Float64Array"))
+}
+
+/// Pick the ms conversion for an integer timestamp from its magnitude.
+fn ts_unit_scale(ts: u64) -> fn(u64) -> u64 {
+    if ts < SECONDS_TS_THRESHOLD {
+        |ts| ts.saturating_mul(1000)
+    } else if ts < MICROS_TS_THRESHOLD {
+        |ts| ts
+    } else if ts < NANOS_TS_THRESHOLD {
+        |ts| ts / 1000
+    } else {
+        |ts| ts / 1_000_000
+    }
+}
+
+/// Sort, sanity-check and convert rows into `Price`s. Returns the detected candle interval.
+fn validate_rows(mut rows: Vec<CandleRow>) -> Result<(Vec<Price>, u64), Error> {
+    if rows.is_empty() {
+        return Err(Error::Custom("Candle file contains no rows".to_string()));
+    }
+
+    for row in rows.iter_mut() {
+        let to_ms = ts_unit_scale(row.open_time);
+        row.open_time = to_ms(row.open_time);
+        row.close_time = row.close_time.map(to_ms);
+    }
+    rows.sort_unstable_by_key(|row| row.open_time);
+
+    let mut seen = BTreeSet::new();
+    for row in &rows {
+        if !seen.insert(row.open_time) {
+            return Err(Error::Custom(format!(
+                "Duplicate candle timestamp {}",
+                row.open_time
+            )));
+        }
+        let values = [row.open, row.high, row.low, row.close, row.volume];
+        if values.iter().any(|v| !v.is_finite()) || row.volume < 0.0 {
+            return Err(Error::Custom(format!(
+                "Candle at {} has non-finite or negative values",
+                row.open_time
+            )));
+        }
+        if row.low <= 0.0
+            || row.high < row.low
+            || row.high < row.open.max(row.close)
+            || row.low > row.open.min(row.close)
+        {
+            return Err(Error::Custom(format!(
+                "Candle at {} has inconsistent OHLC values",
+                row.open_time
+            )));
+        }
+    }
+
+    let interval_ms = rows
+        .windows(2)
+        .map(|pair| pair[1].open_time - pair[0].open_time)
+        .min()
+        .ok_or_else(|| {
+            Error::Custom("At least two candles are needed to detect the interval".to_string())
+        })?;
+    let first = rows[0].open_time;
+    if let Some(row) = rows
+        .iter()
+        .find(|row| !(row.open_time - first).is_multiple_of(interval_ms))
+    {
+        return Err(Error::Custom(format!(
+            "Candle at {} is not aligned to the {interval_ms}ms interval",
+            row.open_time
+        )));
+    }
+
+    let prices = rows
+        .into_iter()
+        .map(|row| Price {
+            open: row.open,
+            high: row.high,
+            low: row.low,
+            close: row.close,
+            open_time: row.open_time,
+            close_time: row
+                .close_time
+                .filter(|ts| *ts > row.open_time)
+                .unwrap_or(row.open_time + interval_ms),
+            vlm: row.volume,
+        })
+        .collect();
+
+    Ok((prices, interval_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{Exchange, MarketType};
+
+    const CSV: &str = "timestamp,open,high,low,close,volume\n\
+                       0,1,2,0.5,1.5,10\n\
+                       60,1.5,3,1.0,2.5,5\n\
+                       120,2.5,2.6,2.0,2.1,1\n";
+
+    #[test]
+    fn parse_csv_reads_header_aliases_and_scales_seconds() {
+        let rows = parse_csv(CSV).unwrap();
+        let (prices, interval_ms) = validate_rows(rows).unwrap();
+        assert_eq!(interval_ms, 60_000);
+        assert_eq!(prices.len(), 3);
+        assert_eq!(prices[1].open_time, 60_000);
+        assert_eq!(prices[1].close_time, 120_000);
+        assert_eq!(prices[2].vlm, 1.0);
+    }
+
+    #[test]
+    fn validate_rows_scales_micro_and_nanosecond_timestamps() {
+        let micros = "ts,open,high,low,close\n\
+                      1700000000000000,1,1,1,1\n\
+                      1700000060000000,1,1,1,1\n";
+        let (prices, interval_ms) = validate_rows(parse_csv(micros).unwrap()).unwrap();
+        assert_eq!(interval_ms, 60_000);
+        assert_eq!(prices[0].open_time, 1_700_000_000_000);
+
+        let nanos = "ts,open,high,low,close\n\
+                     1700000000000000000,1,1,1,1\n\
+                     1700000060000000000,1,1,1,1\n";
+        let (prices, _) = validate_rows(parse_csv(nanos).unwrap()).unwrap();
+        assert_eq!(prices[1].open_time, 1_700_000_060_000);
+    }
+
+    #[test]
+    fn int_column_scales_typed_timestamps_to_ms() {
+        use arrow::array::{TimestampMicrosecondArray, TimestampNanosecondArray};
+
+        let micros = TimestampMicrosecondArray::from(vec![1_700_000_000_000_000]);
+        assert_eq!(int_column(&micros).unwrap().value(0), 1_700_000_000_000);
+        let nanos = TimestampNanosecondArray::from(vec![1_700_000_000_000_000_000]);
+        assert_eq!(int_column(&nanos).unwrap().value(0), 1_700_000_000_000);
+    }
+
+    #[test]
+    fn validate_rows_rejects_bad_candles() {
+        let misaligned = "ts,open,high,low,close\n0,1,1,1,1\n60000,1,1,1,1\n90000,1,1,1,1\n";
+        assert!(validate_rows(parse_csv(misaligned).unwrap()).is_err());
+
+        let duplicate = "ts,open,high,low,close\n0,1,1,1,1\n0,1,1,1,1\n";
+        assert!(validate_rows(parse_csv(duplicate).unwrap()).is_err());
+
+        let inverted = "ts,open,high,low,close\n0,1,0.5,2,1\n60000,1,1,1,1\n";
+        assert!(validate_rows(parse_csv(inverted).unwrap()).is_err());
+
+        assert!(parse_csv("ts,open,high,close\n0,1,1,1\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn import_candles_resamples_into_candle_store() {
+        let dir = std::env::temp_dir().join(format!("kwant-import-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("btc.csv");
+        fs::write(&file, CSV).unwrap();
+
+        let store = CandleStore::open(dir.join("candles")).unwrap();
+        let source = DataSource::new(Exchange::Local, MarketType::Futures);
+        let summary = import_candles(
+            &store,
+            &source,
+            "btc",
+            &file,
+            &[TimeFrame::Min1, TimeFrame::Min3],
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.rows_read, 3);
+        assert_eq!(
+            summary.written,
+            vec![(TimeFrame::Min1, 3), (TimeFrame::Min3, 1)]
+        );
+
+        let key = source.candle_key("BTC", TimeFrame::Min3);
+        let candles = store.range_to_vec(&key, 0, 180_000);
+        assert_eq!(candles.len(), 1);
+        assert_eq!(candles[0].high, 3.0);
+        assert_eq!(candles[0].low, 0.5);
+        assert_eq!(candles[0].close, 2.1);
+        assert_eq!(candles[0].vlm, 16.0);
+
+        assert!(
+            import_candles(&store, &source, "btc", &file, &[TimeFrame::Min1])
+                .await
+                .is_ok()
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+use chrono::{DateTime, Datelike};
+
+use super::types::{EquityPoint, MonthlyReturn};
+use crate::{TimeFrame, TradeInfo};
+
+const EPSILON: f64 = 1e-12;
+const YEAR_SECS: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// Per-sample equity returns plus how many samples make up a year.
+pub(super) struct SampledReturns {
+    pub returns: Vec<f64>,
+    pub periods_per_year: f64,
+}
+
+/// Returns between consecutive equity points, annualised by the observed
+/// sampling cadence (falling back to `resolution`).
+pub(super) fn sampled_returns(
+    equity_curve: &[EquityPoint],
+    resolution: TimeFrame,
+) -> Option<SampledReturns> {
+    if equity_curve.len() < 2 {
+        return None;
+    }
+
+    // Keep one point per timestamp (latest point wins) to avoid duplicate-time noise.
+    let mut points: Vec<(u64, f64)> = Vec::with_capacity(equity_curve.len());
+    for point in equity_curve {
+        match points.last_mut() {
+            Some((last_ts, last_equity)) if *last_ts == point.ts => {
+                *last_equity = point.equity;
+            }
+            _ => points.push((point.ts, point.equity)),
+        }
+    }
+
+    if points.len() < 2 {
+        return None;
+    }
+
+    let mut returns: Vec<f64> = Vec::with_capacity(points.len().saturating_sub(1));
+    let mut delta_secs: Vec<f64> = Vec::with_capacity(points.len().saturating_sub(1));
+    for w in points.windows(2) {
+        let (prev_ts, prev_equity) = w[0];
+        let (ts, equity) = w[1];
+
+        if prev_equity.abs() <= EPSILON {
+            continue;
+        }
+
+        let dt_ms = ts.saturating_sub(prev_ts);
+        if dt_ms == 0 {
+            continue;
+        }
+
+        let ret = (equity - prev_equity) / prev_equity;
+        if ret.is_finite() {
+            returns.push(ret);
+            delta_secs.push((dt_ms as f64) / 1000.0);
+        }
+    }
+
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let mut sorted_dt = delta_secs;
+    sorted_dt.sort_by(|a, b| a.total_cmp(b));
+    let median_dt_secs = if sorted_dt.len() % 2 == 1 {
+        sorted_dt[sorted_dt.len() / 2]
+    } else {
+        let upper = sorted_dt.len() / 2;
+        (sorted_dt[upper - 1] + sorted_dt[upper]) / 2.0
+    };
+
+    let fallback_dt = resolution.to_secs() as f64;
+    let sample_dt_secs = if median_dt_secs > 0.0 {
+        median_dt_secs
+    } else {
+        fallback_dt
+    };
+    if !sample_dt_secs.is_finite() || sample_dt_secs <= 0.0 {
+        return None;
+    }
+
+    let periods_per_year = YEAR_SECS / sample_dt_secs;
+    if !periods_per_year.is_finite() || periods_per_year <= 0.0 {
+        return None;
+    }
+
+    Some(SampledReturns {
+        returns,
+        periods_per_year,
+    })
+}
+
+/// Like Sharpe, but only penalises returns below zero.
+pub(super) fn sortino_ratio(sampled: &SampledReturns) -> Option<f64> {
+    let n = sampled.returns.len() as f64;
+    let mean = sampled.returns.iter().sum::<f64>() / n;
+    let downside = sampled
+        .returns
+        .iter()
+        .map(|r| r.min(0.0).powi(2))
+        .sum::<f64>()
+        / n;
+    if !downside.is_finite() || downside <= EPSILON {
+        return None;
+    }
+
+    let sortino = mean / downside.sqrt() * sampled.periods_per_year.sqrt();
+    sortino.is_finite().then_some(sortino)
+}
+
+/// Compound annual growth over the curve's span divided by max drawdown.
+pub(super) fn calmar_ratio(
+    initial_equity: f64,
+    final_equity: f64,
+    span_ms: u64,
+    max_drawdown_pct: f64,
+) -> Option<f64> {
+    if initial_equity <= EPSILON || span_ms == 0 || max_drawdown_pct <= EPSILON {
+        return None;
+    }
+
+    let years = span_ms as f64 / 1000.0 / YEAR_SECS;
+    let growth = (final_equity / initial_equity).max(0.0);
+    let cagr_pct = (growth.powf(1.0 / years) - 1.0) * 100.0;
+    let calmar = cagr_pct / max_drawdown_pct;
+    calmar.is_finite().then_some(calmar)
+}
+
+/// Simulated span covered by the equity curve. Points are stamped at candle
+/// close, so the first candle's own duration is added back.
+pub(super) fn curve_span_ms(equity_curve: &[EquityPoint], resolution: TimeFrame) -> u64 {
+    match (equity_curve.first(), equity_curve.last()) {
+        (Some(first), Some(last)) => last
+            .ts
+            .saturating_sub(first.ts)
+            .saturating_add(resolution.to_millis()),
+        _ => 0,
+    }
+}
+
+/// Percent of `span_ms` with at least one trade open. Overlapping trades
+/// (portfolio legs) are only counted once.
+pub(super) fn time_in_market_pct(trades: &[TradeInfo], span_ms: u64) -> f64 {
+    if span_ms == 0 {
+        return 0.0;
+    }
+
+    let mut intervals = trades
+        .iter()
+        .map(|t| (t.open.time, t.close.time.max(t.open.time)))
+        .collect::<Vec<_>>();
+    intervals.sort_unstable();
+
+    let mut covered = 0_u64;
+    let mut current: Option<(u64, u64)> = None;
+    for (start, end) in intervals {
+        match current.as_mut() {
+            Some((_, cur_end)) if start <= *cur_end => *cur_end = (*cur_end).max(end),
+            _ => {
+                if let Some((s, e)) = current.replace((start, end)) {
+                    covered += e - s;
+                }
+            }
+        }
+    }
+    if let Some((s, e)) = current {
+        covered += e - s;
+    }
+
+    (covered as f64 / span_ms as f64 * 100.0).min(100.0)
+}
+
+/// Average and median holding time.
+pub(super) fn trade_durations(trades: &[TradeInfo]) -> (u64, u64) {
+    if trades.is_empty() {
+        return (0, 0);
+    }
+
+    let mut durations = trades
+        .iter()
+        .map(|t| t.close.time.saturating_sub(t.open.time))
+        .collect::<Vec<_>>();
+    durations.sort_unstable();
+    let avg = durations.iter().sum::<u64>() / durations.len() as u64;
+    let mid = durations.len() / 2;
+    let median = if durations.len() % 2 == 1 {
+        durations[mid]
+    } else {
+        (durations[mid - 1] + durations[mid]) / 2
+    };
+    (avg, median)
+}
+
+/// Longest runs of consecutive winning and losing trades. Flat trades break both.
+pub(super) fn streaks(trades: &[TradeInfo]) -> (usize, usize) {
+    let (mut wins, mut losses) = (0_usize, 0_usize);
+    let (mut longest_win, mut longest_loss) = (0_usize, 0_usize);
+    for trade in trades {
+        if trade.pnl > 0.0 {
+            wins += 1;
+            losses = 0;
+        } else if trade.pnl < 0.0 {
+            losses += 1;
+            wins = 0;
+        } else {
+            wins = 0;
+            losses = 0;
+        }
+        longest_win = longest_win.max(wins);
+        longest_loss = longest_loss.max(losses);
+    }
+    (longest_win, longest_loss)
+}
+
+/// Calendar-month (UTC) returns. Each month starts from the previous month's
+/// closing equity; the first starts from `initial_equity`.
+pub(super) fn monthly_returns(
+    initial_equity: f64,
+    equity_curve: &[EquityPoint],
+) -> Vec<MonthlyReturn> {
+    let mut out: Vec<MonthlyReturn> = Vec::new();
+    let mut start_equity = initial_equity;
+    for point in equity_curve {
+        let Some(date) = DateTime::from_timestamp_millis(point.ts as i64) else {
+            continue;
+        };
+        let (year, month) = (date.year(), date.month());
+        match out.last_mut() {
+            Some(last) if last.year == year && last.month == month => {
+                last.end_equity = point.equity;
+            }
+            last => {
+                if let Some(last) = last {
+                    start_equity = last.end_equity;
+                }
+                out.push(MonthlyReturn {
+                    year,
+                    month,
+                    start_equity,
+                    end_equity: point.equity,
+                    pnl: 0.0,
+                    return_pct: 0.0,
+                });
+            }
+        }
+    }
+
+    for month in &mut out {
+        month.pnl = month.end_equity - month.start_equity;
+        month.return_pct = if month.start_equity.abs() > EPSILON {
+            month.pnl / month.start_equity * 100.0
+        } else {
+            0.0
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FillInfo, FillType, Side};
+
+    fn trade(open: u64, close: u64, pnl: f64) -> TradeInfo {
+        let fill = |time| FillInfo {
+            time,
+            price: 100.0,
+            fill_type: FillType::Market,
+        };
+        TradeInfo {
+            side: Side::Long,
+            size: 1.0,
+            pnl,
+            total_pnl: pnl,
+            fees: 0.0,
+            funding: 0.0,
+            open: fill(open),
+            close: fill(close),
+            strategy: None,
+        }
+    }
+
+    fn point(ts: u64, equity: f64) -> EquityPoint {
+        EquityPoint {
+            ts,
+            equity,
+            balance: equity,
+            upnl: 0.0,
+        }
+    }
+
+    #[test]
+    fn trade_stats_merge_overlaps_and_track_streaks() {
+        let trades = [
+            trade(0, 100, 5.0),
+            trade(50, 150, 2.0),
+            trade(300, 400, -1.0),
+            trade(500, 900, -3.0),
+            trade(900, 1_000, 0.0),
+        ];
+        // Covered: 0..150, 300..400, 500..1000.
+        assert!((time_in_market_pct(&trades, 1_000) - 75.0).abs() < 1e-9);
+        assert_eq!(trade_durations(&trades), (160, 100));
+        assert_eq!(streaks(&trades), (2, 2));
+    }
+
+    #[test]
+    fn monthly_returns_chain_month_boundaries() {
+        // 2024-01-15, 2024-01-31, 2024-02-10, 2024-03-01 (UTC).
+        let curve = [
+            point(1_705_276_800_000, 1_050.0),
+            point(1_706_659_200_000, 1_100.0),
+            point(1_707_523_200_000, 990.0),
+            point(1_709_251_200_000, 1_089.0),
+        ];
+        let months = monthly_returns(1_000.0, &curve);
+        assert_eq!(months.len(), 3);
+        assert_eq!((months[0].year, months[0].month), (2024, 1));
+        assert!((months[0].return_pct - 10.0).abs() < 1e-9);
+        assert_eq!(months[1].start_equity, 1_100.0);
+        assert!((months[1].return_pct + 10.0).abs() < 1e-9);
+        assert!((months[2].return_pct - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn calmar_uses_annualised_growth() {
+        let year_ms = (YEAR_SECS * 1000.0) as u64;
+        let calmar = calmar_ratio(1_000.0, 1_200.0, year_ms, 10.0).unwrap();
+        assert!((calmar - 2.0).abs() < 1e-9);
+        assert!(calmar_ratio(1_000.0, 1_200.0, year_ms, 0.0).is_none());
+    }
+}
@@ -1,13 +1,44 @@
 pub mod backtester;
+pub mod benchmark;
 pub mod candle_store;
+pub mod compare;
 pub mod downsample;
 pub mod fetcher;
+pub mod importer;
+pub mod metrics;
+pub mod monte_carlo;
+pub mod optimizer;
+pub mod portfolio;
+pub mod report;
+pub mod stepper;
+pub mod synthetic;
 pub mod types;
+pub mod walk_forward;
 
 pub use backtester::Backtester;
-pub use candle_store::CandleStore;
+pub use benchmark::BenchmarkSummary;
+pub use candle_store::{CandleStore, FundingRate};
+pub use compare::{BacktestComparison, CompareRequest};
 pub use fetcher::{DataSource, Exchange, Fetcher, MarketType};
+pub use importer::{ImportFormat, ImportSummary, import_candles};
+pub use monte_carlo::{MonteCarloConfig, MonteCarloMode, MonteCarloSummary, Percentiles};
+pub use optimizer::{
+    OptimizeCandidate, OptimizeMetric, OptimizeRequest, OptimizeResult, Optimizer, ParamRange,
+    ParamTarget, SearchMode,
+};
+pub use portfolio::{
+    PortfolioBacktester, PortfolioConfig, PortfolioLeg, PortfolioLegResult, PortfolioResult,
+    PortfolioRunRequest,
+};
+pub use report::ReportFormat;
+pub use stepper::{Breakpoint, StepCommand, StepEvent, StepSession, StopReason};
+pub use synthetic::{PriceModel, Scenario, SyntheticConfig, SyntheticSummary, write_synthetic};
 pub use types::{
-    BacktestConfig, BacktestProgress, BacktestResult, BacktestRunRequest, BacktestSim,
-    BacktestSummary, CandlePoint, EquityPoint, PnlTracker, PositionSnapshot, SnapshotReason,
+    BacktestCheckpoint, BacktestConfig, BacktestProgress, BacktestResult, BacktestRunRequest,
+    BacktestSim, BacktestSummary, CandlePoint, CheckpointStatus, EquityPoint, FillModel,
+    LatencyModel, LimitFillModel, MonthlyReturn, PnlTracker, PositionSnapshot, ScriptLogEntry,
+    SlippageModel, SnapshotReason, TouchFill, TradeExcursion,
+};
+pub use walk_forward::{
+    WalkForward, WalkForwardRequest, WalkForwardResult, WalkForwardSplit, WalkForwardWindow,
 };
@@ -0,0 +1,264 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::TradeInfo;
+
+pub const MAX_MONTE_CARLO_ITERATIONS: usize = 20_000;
+const DEFAULT_ITERATIONS: usize = 1_000;
+const DEFAULT_RUIN_LOSS_PCT: f64 = 50.0;
+const EPSILON: f64 = 1e-12;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MonteCarloMode {
+    /// Reorder the realised trades; every path has the same trades.
+    #[default]
+    Shuffle,
+    /// Draw trades with replacement; paths may repeat or skip trades.
+    Bootstrap,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonteCarloConfig {
+    #[serde(default = "default_iterations")]
+    pub iterations: usize,
+    #[serde(default)]
+    pub mode: MonteCarloMode,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// A path is ruined once equity falls this far below the starting margin.
+    #[serde(default = "default_ruin_loss_pct")]
+    pub ruin_loss_pct: f64,
+}
+
+fn default_iterations() -> usize {
+    DEFAULT_ITERATIONS
+}
+
+fn default_ruin_loss_pct() -> f64 {
+    DEFAULT_RUIN_LOSS_PCT
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            mode: MonteCarloMode::default(),
+            seed: None,
+            ruin_loss_pct: DEFAULT_RUIN_LOSS_PCT,
+        }
+    }
+}
+
+impl MonteCarloConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.iterations == 0 || self.iterations > MAX_MONTE_CARLO_ITERATIONS {
+            return Err(format!(
+                "monteCarlo.iterations must be between 1 and {MAX_MONTE_CARLO_ITERATIONS}"
+            ));
+        }
+        if !self.ruin_loss_pct.is_finite()
+            || self.ruin_loss_pct <= 0.0
+            || self.ruin_loss_pct > 100.0
+        {
+            return Err("monteCarlo.ruinLossPct must be within (0, 100]".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Percentiles {
+    pub p5: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub mean: f64,
+}
+
+impl Percentiles {
+    fn from_samples(samples: &mut [f64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_by(f64::total_cmp);
+        let at = |pct: f64| {
+            let rank = pct / 100.0 * (samples.len() - 1) as f64;
+            let lo = rank.floor() as usize;
+            let hi = rank.ceil() as usize;
+            samples[lo] + (samples[hi] - samples[lo]) * (rank - lo as f64)
+        };
+        Self {
+            p5: at(5.0),
+            p25: at(25.0),
+            p50: at(50.0),
+            p75: at(75.0),
+            p95: at(95.0),
+            mean: samples.iter().sum::<f64>() / samples.len() as f64,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonteCarloSummary {
+    pub iterations: usize,
+    pub mode: MonteCarloMode,
+    pub seed: Option<u64>,
+    pub margin: f64,
+    pub ruin_equity: f64,
+    pub final_equity: Percentiles,
+    pub max_drawdown_pct: Percentiles,
+    pub worst_losing_streak: Percentiles,
+    /// Share of paths, in percent, whose equity touched `ruin_equity`.
+    pub risk_of_ruin_pct: f64,
+}
+
+/// Replay `trades` in resampled order. Each trade is applied as a return on the
+/// equity it was opened with, so sizing follows the path the way margin-based
+/// sizing does in the simulator. Trade PnL already carries the run's leverage.
+pub fn simulate(config: &MonteCarloConfig, margin: f64, trades: &[TradeInfo]) -> MonteCarloSummary {
+    let returns = trade_returns(margin, trades);
+    let ruin_equity = margin * (1.0 - config.ruin_loss_pct / 100.0);
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let iterations = config.iterations.max(1);
+    let mut final_equity = Vec::with_capacity(iterations);
+    let mut max_drawdown = Vec::with_capacity(iterations);
+    let mut losing_streak = Vec::with_capacity(iterations);
+    let mut ruined = 0_usize;
+    let mut path = returns.clone();
+
+    for _ in 0..iterations {
+        match config.mode {
+            MonteCarloMode::Shuffle => path.shuffle(&mut rng),
+            MonteCarloMode::Bootstrap => {
+                for slot in path.iter_mut() {
+                    *slot = returns[rng.gen_range(0..returns.len())];
+                }
+            }
+        }
+        let stats = replay(margin, ruin_equity, &path);
+        final_equity.push(stats.final_equity);
+        max_drawdown.push(stats.max_drawdown_pct);
+        losing_streak.push(stats.worst_losing_streak as f64);
+        ruined += stats.ruined as usize;
+    }
+
+    MonteCarloSummary {
+        iterations,
+        mode: config.mode,
+        seed: config.seed,
+        margin,
+        ruin_equity,
+        final_equity: Percentiles::from_samples(&mut final_equity),
+        max_drawdown_pct: Percentiles::from_samples(&mut max_drawdown),
+        worst_losing_streak: Percentiles::from_samples(&mut losing_streak),
+        risk_of_ruin_pct: ruined as f64 / iterations as f64 * 100.0,
+    }
+}
+
+fn trade_returns(margin: f64, trades: &[TradeInfo]) -> Vec<f64> {
+    let mut equity = margin;
+    trades
+        .iter()
+        .map(|trade| {
+            let ret = if equity > EPSILON {
+                trade.pnl / equity
+            } else {
+                0.0
+            };
+            equity += trade.pnl;
+            ret
+        })
+        .collect()
+}
+
+struct PathStats {
+    final_equity: f64,
+    max_drawdown_pct: f64,
+    worst_losing_streak: usize,
+    ruined: bool,
+}
+
+fn replay(margin: f64, ruin_equity: f64, returns: &[f64]) -> PathStats {
+    let mut equity = margin;
+    let mut peak = margin;
+    let mut max_drawdown_pct = 0.0_f64;
+    let mut streak = 0_usize;
+    let mut worst_losing_streak = 0_usize;
+    let mut ruined = false;
+
+    for ret in returns {
+        equity = (equity * (1.0 + ret)).max(0.0);
+        peak = peak.max(equity);
+        if peak > EPSILON {
+            max_drawdown_pct = max_drawdown_pct.max((peak - equity) / peak * 100.0);
+        }
+        if *ret < 0.0 {
+            streak += 1;
+            worst_losing_streak = worst_losing_streak.max(streak);
+        } else {
+            streak = 0;
+        }
+        if equity <= ruin_equity {
+            ruined = true;
+            break;
+        }
+    }
+
+    PathStats {
+        final_equity: equity,
+        max_drawdown_pct,
+        worst_losing_streak,
+        ruined,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_interpolate_between_samples() {
+        let mut samples = (0..=100).rev().map(f64::from).collect::<Vec<_>>();
+        let pct = Percentiles::from_samples(&mut samples);
+        assert_eq!(pct.p5, 5.0);
+        assert_eq!(pct.p50, 50.0);
+        assert_eq!(pct.p95, 95.0);
+        assert_eq!(pct.mean, 50.0);
+    }
+
+    #[test]
+    fn replay_tracks_drawdown_streak_and_ruin() {
+        let stats = replay(1_000.0, 500.0, &[0.1, -0.2, -0.1, 0.05]);
+        assert!((stats.final_equity - 1_000.0 * 1.1 * 0.8 * 0.9 * 1.05).abs() < 1e-9);
+        assert!((stats.max_drawdown_pct - 28.0).abs() < 1e-9);
+        assert_eq!(stats.worst_losing_streak, 2);
+        assert!(!stats.ruined);
+
+        let ruined = replay(1_000.0, 500.0, &[-0.3, -0.3, 0.5]);
+        assert!(ruined.ruined);
+        assert!((ruined.final_equity - 490.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shuffled_paths_keep_the_same_final_equity() {
+        let returns = [0.1, -0.05, 0.2, -0.1];
+        let mut rng = StdRng::seed_from_u64(3);
+        let baseline = replay(1_000.0, 0.0, &returns).final_equity;
+        for _ in 0..20 {
+            let mut path = returns.to_vec();
+            path.shuffle(&mut rng);
+            assert!((replay(1_000.0, 0.0, &path).final_equity - baseline).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,713 @@
+use std::sync::Arc;
+
+use log::{info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rhai::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tokio_util::sync::CancellationToken;
+
+use super::backtester::Backtester;
+use super::candle_store::CandleStore;
+use super::types::{BacktestProgress, BacktestRunRequest, BacktestSummary};
+use crate::backend::LocalStore;
+use crate::backend::scripting::{CompiledStrategy, StateDeclarations, compile_strategy};
+use crate::backend::storage_models::StrategyRow;
+use crate::{Error, IndexId, IndicatorKind, check_asset_fix, get_time_now};
+
+/// Hard cap on the number of backtests a single optimization may expand into.
+pub const MAX_OPTIMIZE_RUNS: usize = 500;
+const MAX_OPTIMIZE_WORKERS: usize = 4;
+const DEFAULT_TOP_N: usize = 10;
+const RANGE_EPSILON: f64 = 1e-9;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum ParamTarget {
+    /// Default value of a declared state variable.
+    State { name: String },
+    /// Numeric parameter of the strategy indicator at `index`. `field` selects
+    /// the parameter of multi-parameter kinds (e.g. `short` for `emaCross`).
+    Indicator {
+        index: usize,
+        #[serde(default)]
+        field: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamRange {
+    pub target: ParamTarget,
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+impl ParamRange {
+    fn validate(&self) -> Result<(), String> {
+        if !self.start.is_finite() || !self.end.is_finite() || !self.step.is_finite() {
+            return Err("parameter ranges must be finite".to_string());
+        }
+        if self.step <= 0.0 {
+            return Err("parameter step must be greater than zero".to_string());
+        }
+        if self.end < self.start {
+            return Err("parameter end must not be below start".to_string());
+        }
+        if let ParamTarget::Indicator { .. } = self.target
+            && self.start < 1.0
+        {
+            return Err("indicator parameters must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    fn value_count(&self) -> usize {
+        ((self.end - self.start) / self.step + RANGE_EPSILON).floor() as usize + 1
+    }
+
+    fn values(&self) -> Vec<f64> {
+        (0..self.value_count())
+            .map(|idx| self.start + self.step * idx as f64)
+            .collect()
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        let idx = rng.gen_range(0..self.value_count());
+        self.start + self.step * idx as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum SearchMode {
+    Grid,
+    Random {
+        samples: usize,
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OptimizeMetric {
+    NetPnl,
+    ReturnPct,
+    SharpeRatio,
+    ProfitFactor,
+    WinRatePct,
+    Expectancy,
+    MaxDrawdownPct,
+}
+
+impl OptimizeMetric {
+    /// Higher is better for every metric; drawdown is negated.
+    pub fn score(&self, summary: &BacktestSummary) -> Option<f64> {
+        let score = match self {
+            OptimizeMetric::NetPnl => Some(summary.net_pnl),
+            OptimizeMetric::ReturnPct => Some(summary.return_pct),
+            OptimizeMetric::SharpeRatio => summary.sharpe_ratio,
+            OptimizeMetric::ProfitFactor => summary.profit_factor,
+            OptimizeMetric::WinRatePct => Some(summary.win_rate_pct),
+            OptimizeMetric::Expectancy => Some(summary.expectancy),
+            OptimizeMetric::MaxDrawdownPct => Some(-summary.max_drawdown_pct),
+        };
+        score.filter(|value| value.is_finite())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeRequest {
+    #[serde(default)]
+    pub run_id: Option<String>,
+    pub base: BacktestRunRequest,
+    pub params: Vec<ParamRange>,
+    pub search: SearchMode,
+    pub metric: OptimizeMetric,
+    #[serde(default = "default_top_n")]
+    pub top_n: usize,
+}
+
+fn default_top_n() -> usize {
+    DEFAULT_TOP_N
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParamValue {
+    pub target: ParamTarget,
+    pub value: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeCandidate {
+    pub run_id: String,
+    pub params: Vec<ParamValue>,
+    pub score: Option<f64>,
+    pub summary: BacktestSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeResult {
+    pub run_id: String,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub metric: OptimizeMetric,
+    pub total_runs: usize,
+    pub failed_runs: usize,
+    pub top: Vec<OptimizeCandidate>,
+}
+
+/// A strategy with one parameter set applied, ready to hand to `Backtester::new`.
+struct PreparedCandidate {
+    idx: usize,
+    params: Vec<ParamValue>,
+    request: BacktestRunRequest,
+    backtester: Backtester,
+}
+
+pub struct Optimizer {
+    run_id: String,
+    request: OptimizeRequest,
+    strategy: StrategyRow,
+    rhai_engine: Arc<Engine>,
+    candle_store: Arc<CandleStore>,
+    candidates: Vec<Vec<ParamValue>>,
+    cancel: CancellationToken,
+}
+
+impl Optimizer {
+    pub async fn from_request(
+        request: OptimizeRequest,
+        rhai_engine: Arc<Engine>,
+        store: Arc<LocalStore>,
+        candle_store: Arc<CandleStore>,
+    ) -> Result<Self, Error> {
+        let sid = request.base.config.strategy_id;
+        let strategy = store
+            .strategy(sid)
+            .await
+            .map_err(|e| Error::Custom(format!("local storage error: {e}")))?
+            .ok_or_else(|| Error::Custom(format!("strategy {sid} not found")))?;
+        Self::new(request, strategy, rhai_engine, candle_store)
+    }
+
+    /// Build an optimizer around an already loaded strategy row.
+    pub fn new(
+        request: OptimizeRequest,
+        strategy: StrategyRow,
+        rhai_engine: Arc<Engine>,
+        candle_store: Arc<CandleStore>,
+    ) -> Result<Self, Error> {
+        let started_at = get_time_now();
+        let run_id = request
+            .run_id
+            .clone()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| format!("opt-{}-{started_at}", request.base.config.asset));
+
+        let candidates = expand_candidates(&request).map_err(Error::Custom)?;
+        let optimizer = Self {
+            run_id,
+            request,
+            strategy,
+            rhai_engine,
+            candle_store,
+            candidates,
+            cancel: CancellationToken::new(),
+        };
+        // Surface bad targets before any candles are fetched.
+        if let Some(first) = optimizer.candidates.first() {
+            optimizer.apply_params(first)?;
+        }
+        Ok(optimizer)
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    pub fn total_runs(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Cancelling `token` stops the candidate runs in flight, skips the rest
+    /// and makes the optimization return an error.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    pub async fn run_with_progress<F>(
+        &mut self,
+        mut on_progress: F,
+    ) -> Result<OptimizeResult, Error>
+    where
+        F: FnMut(BacktestProgress),
+    {
+        let started_at = get_time_now();
+        let total = self.candidates.len();
+        info!(
+            "optimize[{}] start strategy={} runs={} metric={:?}",
+            self.run_id, self.strategy.id, total, self.request.metric
+        );
+        on_progress(BacktestProgress::Optimizing {
+            completed: 0,
+            total: total as u64,
+        });
+
+        let mut pending = Vec::with_capacity(total);
+        for (idx, params) in self.candidates.iter().enumerate() {
+            let mut request = self.request.base.clone();
+            request.run_id = Some(format!("{}-{idx}", self.run_id));
+            let backtester = self.backtester(params, request.clone())?;
+            pending.push(PreparedCandidate {
+                idx,
+                params: params.clone(),
+                request,
+                backtester,
+            });
+        }
+        // Workers pop from the back; keep candidate order.
+        pending.reverse();
+
+        let mut joinset = tokio::task::JoinSet::new();
+        let mut finished: Vec<OptimizeCandidate> = Vec::with_capacity(total);
+        let mut failed_runs = 0_usize;
+        let mut completed = 0_usize;
+        let metric = self.request.metric;
+
+        loop {
+            while joinset.len() < MAX_OPTIMIZE_WORKERS
+                && let Some(mut candidate) = pending.pop()
+            {
+                joinset.spawn(async move {
+                    let result = candidate.backtester.run().await;
+                    (candidate.idx, candidate.params, candidate.request, result)
+                });
+            }
+
+            let Some(joined) = joinset.join_next().await else {
+                break;
+            };
+            if self.cancel.is_cancelled() {
+                joinset.abort_all();
+                info!(
+                    "optimize[{}] cancelled completed={completed} total={total}",
+                    self.run_id
+                );
+                on_progress(BacktestProgress::Cancelled {
+                    processed: completed as u64,
+                });
+                return Err(Error::Custom("Optimization cancelled".to_string()));
+            }
+            completed += 1;
+            match joined {
+                Ok((_, params, request, Ok(result))) => finished.push(OptimizeCandidate {
+                    run_id: request.run_id.unwrap_or(result.run_id),
+                    params,
+                    score: metric.score(&result.summary),
+                    summary: result.summary,
+                }),
+                Ok((idx, _, _, Err(err))) => {
+                    failed_runs += 1;
+                    warn!("optimize[{}] candidate {idx} failed: {err}", self.run_id);
+                }
+                Err(err) => {
+                    failed_runs += 1;
+                    warn!("optimize[{}] candidate task failed: {err}", self.run_id);
+                }
+            }
+            on_progress(BacktestProgress::Optimizing {
+                completed: completed as u64,
+                total: total as u64,
+            });
+        }
+
+        if finished.is_empty() {
+            let err = Error::Custom("Every optimization run failed".to_string());
+            on_progress(BacktestProgress::Failed {
+                message: err.to_string(),
+            });
+            return Err(err);
+        }
+
+        rank_candidates(&mut finished);
+        finished.truncate(self.request.top_n.max(1));
+        on_progress(BacktestProgress::Done);
+
+        let result = OptimizeResult {
+            run_id: self.run_id.clone(),
+            started_at,
+            finished_at: get_time_now(),
+            metric,
+            total_runs: total,
+            failed_runs,
+            top: finished,
+        };
+        info!(
+            "optimize[{}] done runs={} failed={} best_score={:?}",
+            result.run_id,
+            result.total_runs,
+            result.failed_runs,
+            result.top.first().and_then(|c| c.score)
+        );
+        Ok(result)
+    }
+
+    /// Backtester for `request` running the strategy with `params` applied.
+    pub fn backtester(
+        &self,
+        params: &[ParamValue],
+        request: BacktestRunRequest,
+    ) -> Result<Backtester, Error> {
+        let (compiled, indicators) = self.apply_params(params)?;
+        let mut backtester = Backtester::new(
+            request,
+            self.rhai_engine.clone(),
+            compiled,
+            indicators,
+            self.candle_store.clone(),
+        );
+        backtester.set_cancel_token(self.cancel.clone());
+        Ok(backtester)
+    }
+
+    /// Compile the strategy with `params` applied to its state defaults and indicators.
+    fn apply_params(
+        &self,
+        params: &[ParamValue],
+    ) -> Result<(CompiledStrategy, Vec<IndexId>), Error> {
+        let row = &self.strategy;
+        let mut state_decls: StateDeclarations = row
+            .state_declarations
+            .as_ref()
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        let original: Vec<IndexId> =
+            serde_json::from_value(row.indicators.clone()).unwrap_or_default();
+        let mut indicators = original.clone();
+
+        for param in params {
+            match &param.target {
+                ParamTarget::State { name } => {
+                    let slot = state_decls.get_mut(name).ok_or_else(|| {
+                        Error::Custom(format!("state variable {name} is not declared"))
+                    })?;
+                    *slot = state_value(slot, param.value);
+                }
+                ParamTarget::Indicator { index, field } => {
+                    let (_, kind, _) = indicators.get_mut(*index).ok_or_else(|| {
+                        Error::Custom(format!("strategy has no indicator at index {index}"))
+                    })?;
+                    *kind = set_indicator_param(*kind, field.as_deref(), param.value)?;
+                }
+            }
+        }
+
+        let mut scripts = [
+            row.on_idle.clone(),
+            row.on_open.clone(),
+            row.on_busy.clone(),
+        ];
+        let base_asset = self.request.base.config.asset.as_str();
+        for (before, after) in original.iter().zip(&indicators) {
+            if before.1 != after.1 {
+                for script in scripts.iter_mut() {
+                    *script = rename_indicator_key(script, base_asset, before, after.1);
+                }
+            }
+        }
+
+        let decls = (!state_decls.is_empty()).then_some(&state_decls);
+        let compiled = compile_strategy(
+            &self.rhai_engine,
+            &scripts[0],
+            &scripts[1],
+            &scripts[2],
+            decls,
+        )
+        .map_err(|e| Error::Custom(format!("strategy {} failed to compile: {e}", row.id)))?;
+        Ok((compiled, indicators))
+    }
+}
+
+fn expand_candidates(request: &OptimizeRequest) -> Result<Vec<Vec<ParamValue>>, String> {
+    if request.params.is_empty() {
+        return Err("optimization needs at least one parameter range".to_string());
+    }
+    for range in &request.params {
+        range.validate()?;
+    }
+
+    match request.search {
+        SearchMode::Grid => {
+            let total = request
+                .params
+                .iter()
+                .try_fold(1_usize, |acc, range| acc.checked_mul(range.value_count()))
+                .filter(|total| *total <= MAX_OPTIMIZE_RUNS)
+                .ok_or_else(|| {
+                    format!("grid expands beyond {MAX_OPTIMIZE_RUNS} runs; narrow the ranges")
+                })?;
+            let mut out: Vec<Vec<ParamValue>> = vec![Vec::with_capacity(request.params.len())];
+            for range in &request.params {
+                let values = range.values();
+                out = out
+                    .into_iter()
+                    .flat_map(|prefix| {
+                        values.iter().map(move |value| {
+                            let mut next = prefix.clone();
+                            next.push(ParamValue {
+                                target: range.target.clone(),
+                                value: *value,
+                            });
+                            next
+                        })
+                    })
+                    .collect();
+            }
+            debug_assert_eq!(out.len(), total);
+            Ok(out)
+        }
+        SearchMode::Random { samples, seed } => {
+            if samples == 0 || samples > MAX_OPTIMIZE_RUNS {
+                return Err(format!(
+                    "random search samples must be between 1 and {MAX_OPTIMIZE_RUNS}"
+                ));
+            }
+            let mut rng = match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            };
+            Ok((0..samples)
+                .map(|_| {
+                    request
+                        .params
+                        .iter()
+                        .map(|range| ParamValue {
+                            target: range.target.clone(),
+                            value: range.sample(&mut rng),
+                        })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+}
+
+/// Best score first; runs without a score (e.g. no trades for Sharpe) sink to the bottom.
+fn rank_candidates(candidates: &mut [OptimizeCandidate]) {
+    candidates.sort_by(|a, b| match (a.score, b.score) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+/// Keep integer state defaults integral so scripts comparing against ints keep working.
+fn state_value(current: &JsonValue, value: f64) -> JsonValue {
+    if current.is_i64() && value.fract().abs() < RANGE_EPSILON {
+        JsonValue::from(value.round() as i64)
+    } else {
+        JsonValue::from(value)
+    }
+}
+
+fn set_indicator_param(
+    kind: IndicatorKind,
+    field: Option<&str>,
+    value: f64,
+) -> Result<IndicatorKind, Error> {
+    let period = JsonValue::from(value.round().max(1.0) as u64);
+    let mut json = serde_json::to_value(kind)
+        .map_err(|e| Error::Custom(format!("serialize indicator: {e}")))?;
+    let inner = json
+        .as_object_mut()
+        .and_then(|map| map.values_mut().next())
+        .ok_or_else(|| Error::Custom("indicator has no tunable parameters".to_string()))?;
+
+    match (inner, field) {
+        (slot @ JsonValue::Number(_), None) => *slot = period,
+        (JsonValue::Object(fields), Some(field)) if fields.contains_key(field) => {
+            fields.insert(field.to_string(), period);
+        }
+        (JsonValue::Object(fields), _) => {
+            let names = fields.keys().cloned().collect::<Vec<_>>().join(", ");
+            return Err(Error::Custom(format!(
+                "indicator parameter field must be one of: {names}"
+            )));
+        }
+        _ => {
+            return Err(Error::Custom(
+                "indicator parameter field is not supported for this indicator".to_string(),
+            ));
+        }
+    }
+
+    serde_json::from_value(json)
+        .map_err(|e| Error::Custom(format!("invalid indicator parameter: {e}")))
+}
+
+/// Point `extract("<asset>_<key>_<tf>")` style references at the retuned indicator.
+fn rename_indicator_key(
+    script: &str,
+    base_asset: &str,
+    before: &IndexId,
+    new_kind: IndicatorKind,
+) -> String {
+    let (asset, kind, tf) = before;
+    let mut prefixes = vec![check_asset_fix(asset)];
+    if asset.as_ref() == "self" {
+        prefixes.push(check_asset_fix(base_asset));
+    } else if asset.as_ref() == base_asset {
+        prefixes.push("self".to_string());
+    }
+
+    let mut out = script.to_string();
+    for prefix in prefixes {
+        let old = format!("\"{prefix}_{}_{}\"", kind.key(), tf.as_str());
+        let new = format!("\"{prefix}_{}_{}\"", new_kind.key(), tf.as_str());
+        out = out.replace(&old, &new);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeFrame;
+    use crate::backtest::{BacktestConfig, DataSource};
+
+    fn request(params: Vec<ParamRange>, search: SearchMode) -> OptimizeRequest {
+        OptimizeRequest {
+            run_id: None,
+            base: BacktestRunRequest {
+                run_id: None,
+                config: BacktestConfig {
+                    asset: "BTC".to_string(),
+                    source: DataSource::default(),
+                    strategy_id: uuid::Uuid::nil(),
+                    resolution: TimeFrame::Hour1,
+                    margin: 1_000.0,
+                    lev: 1,
+                    taker_fee_bps: 0,
+                    maker_fee_bps: 0,
+                    funding_rate_bps_per_8h: 0.0,
+                    historical_funding: false,
+                    start_time: 0,
+                    end_time: 1,
+                    snapshot_interval_candles: 0,
+                    max_equity_points: 2000,
+                    max_snapshots: 500,
+                    max_script_logs: 1000,
+                    fill_model: Default::default(),
+                    intrabar_resolution: false,
+                },
+                warmup_candles: 0,
+                monte_carlo: None,
+            },
+            params,
+            search,
+            metric: OptimizeMetric::NetPnl,
+            top_n: 3,
+        }
+    }
+
+    fn range(target: ParamTarget, start: f64, end: f64, step: f64) -> ParamRange {
+        ParamRange {
+            target,
+            start,
+            end,
+            step,
+        }
+    }
+
+    #[test]
+    fn grid_expands_cartesian_product() {
+        let rsi = ParamTarget::Indicator {
+            index: 0,
+            field: None,
+        };
+        let threshold = ParamTarget::State {
+            name: "threshold".to_string(),
+        };
+        let req = request(
+            vec![
+                range(rsi.clone(), 10.0, 20.0, 5.0),
+                range(threshold, 25.0, 35.0, 10.0),
+            ],
+            SearchMode::Grid,
+        );
+        let candidates = expand_candidates(&req).unwrap();
+        assert_eq!(candidates.len(), 6);
+        assert_eq!(candidates[0][0].value, 10.0);
+        assert_eq!(candidates[0][1].value, 25.0);
+        assert_eq!(candidates[5][0].value, 20.0);
+        assert_eq!(candidates[5][1].value, 35.0);
+
+        let too_big = request(vec![range(rsi, 1.0, 10_000.0, 1.0)], SearchMode::Grid);
+        assert!(expand_candidates(&too_big).is_err());
+    }
+
+    #[test]
+    fn random_search_is_reproducible_with_seed() {
+        let target = ParamTarget::State {
+            name: "threshold".to_string(),
+        };
+        let search = SearchMode::Random {
+            samples: 8,
+            seed: Some(7),
+        };
+        let req = request(vec![range(target, 25.0, 35.0, 0.5)], search);
+        let first = expand_candidates(&req).unwrap();
+        assert_eq!(first, expand_candidates(&req).unwrap());
+        assert!(
+            first
+                .iter()
+                .all(|params| (25.0..=35.0).contains(&params[0].value))
+        );
+    }
+
+    #[test]
+    fn indicator_params_and_script_keys_are_retuned() {
+        let kind = set_indicator_param(IndicatorKind::Rsi(14), None, 10.0).unwrap();
+        assert_eq!(kind, IndicatorKind::Rsi(10));
+        assert!(set_indicator_param(IndicatorKind::Rsi(14), Some("fast"), 10.0).is_err());
+
+        let index: IndexId = (Arc::from("self"), IndicatorKind::Rsi(14), TimeFrame::Min15);
+        let script =
+            r#"let rsi = extract("BTC_rsi_14_15m"); let other = extract("ETH_rsi_14_15m");"#;
+        let renamed = rename_indicator_key(script, "BTC", &index, kind);
+        assert!(renamed.contains(r#"extract("BTC_rsi_10_15m")"#));
+        assert!(renamed.contains(r#"extract("ETH_rsi_14_15m")"#));
+    }
+
+    #[test]
+    fn ranking_puts_unscored_runs_last() {
+        let summary: BacktestSummary = serde_json::from_value(serde_json::json!({
+            "initialEquity": 0.0, "finalEquity": 0.0, "netPnl": 0.0, "returnPct": 0.0,
+            "maxDrawdownAbs": 0.0, "maxDrawdownPct": 0.0, "totalTrades": 0, "wins": 0,
+            "losses": 0, "winRatePct": 0.0, "grossProfit": 0.0, "grossLoss": 0.0,
+            "avgWin": 0.0, "avgLoss": 0.0, "profitFactor": null, "expectancy": 0.0
+        }))
+        .unwrap();
+        let mut candidates = [None, Some(1.0), Some(3.0)].map(|score| OptimizeCandidate {
+            run_id: String::new(),
+            params: Vec::new(),
+            score,
+            summary: summary.clone(),
+        });
+        rank_candidates(&mut candidates);
+        assert_eq!(
+            candidates.iter().map(|c| c.score).collect::<Vec<_>>(),
+            vec![Some(3.0), Some(1.0), None]
+        );
+    }
+}
@@ -0,0 +1,737 @@
+use std::sync::Arc;
+
+use log::info;
+use rhai::Engine;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::backtester::{Backtester, SimCursor, build_summary};
+use super::candle_store::CandleStore;
+use super::downsample::lttb_equity;
+use super::fetcher::DataSource;
+use super::types::{
+    BacktestConfig, BacktestProgress, BacktestRunRequest, BacktestSummary, EquityPoint, FillModel,
+};
+use crate::backend::LocalStore;
+use crate::backend::app_state::StrategyCache;
+use crate::{Error, MarginAllocation, Price, TimeFrame, TradeInfo, get_time_now};
+
+pub const MAX_PORTFOLIO_LEGS: usize = 16;
+
+/// Share of a position's initial margin the account must keep for it: half,
+/// like the exchange's maintenance margin, at the leg's leverage.
+const MAINTENANCE_MARGIN_RATIO: f64 = 0.5;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioLeg {
+    pub asset: String,
+    pub strategy_id: Uuid,
+    pub resolution: TimeFrame,
+    pub allocation: MarginAllocation,
+    pub lev: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioConfig {
+    pub source: DataSource,
+    /// Account balance the legs' margins are carved from.
+    pub margin: f64,
+    pub taker_fee_bps: u32,
+    pub maker_fee_bps: u32,
+    pub funding_rate_bps_per_8h: f64,
+    #[serde(default)]
+    pub historical_funding: bool,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub legs: Vec<PortfolioLeg>,
+    #[serde(default = "default_max_equity_points")]
+    pub max_equity_points: usize,
+    #[serde(default)]
+    pub fill_model: FillModel,
+    #[serde(default)]
+    pub intrabar_resolution: bool,
+}
+
+impl PortfolioConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        allocate_leg_margins(self.margin, &self.legs)?;
+        if self.end_time <= self.start_time {
+            return Err("endTime must be greater than startTime".to_string());
+        }
+        if let Some(leg) = self.legs.iter().find(|leg| leg.resolution.to_millis() == 0) {
+            return Err(format!(
+                "{}: resolution must be a supported timeframe",
+                leg.asset
+            ));
+        }
+        self.fill_model.validate()
+    }
+}
+
+fn default_max_equity_points() -> usize {
+    2000
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioRunRequest {
+    #[serde(default)]
+    pub run_id: Option<String>,
+    pub config: PortfolioConfig,
+    pub warmup_candles: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioLegResult {
+    pub asset: String,
+    pub strategy_id: Uuid,
+    pub allocation: MarginAllocation,
+    pub lev: usize,
+    /// Margin the leg's strategy sizes from, resolved from `allocation`.
+    pub margin: f64,
+    pub summary: BacktestSummary,
+    pub trades: Vec<TradeInfo>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioResult {
+    pub run_id: String,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub candles_loaded: u64,
+    pub candles_processed: u64,
+    pub config: PortfolioConfig,
+    pub summary: BacktestSummary,
+    pub equity_curve: Vec<EquityPoint>,
+    pub legs: Vec<PortfolioLegResult>,
+    pub liquidated: bool,
+}
+
+struct LegRun<'a> {
+    backtester: &'a mut Backtester,
+    cursor: SimCursor,
+    last_candle: Option<Price>,
+}
+
+impl LegRun<'_> {
+    /// Margin resolved from the leg's allocation.
+    fn allocation(&self) -> f64 {
+        self.backtester.request().config.margin
+    }
+}
+
+/// Runs several strategy legs against one cross-margin account. Every leg
+/// sizes from its allocation as far as the account's free margin covers it,
+/// books its fills, fees and funding against the shared balance, and the
+/// whole account is liquidated once its equity falls to the combined
+/// maintenance margin of the open positions.
+pub struct PortfolioBacktester {
+    request: PortfolioRunRequest,
+    legs: Vec<Backtester>,
+    cancel: CancellationToken,
+}
+
+impl PortfolioBacktester {
+    pub async fn from_request(
+        request: PortfolioRunRequest,
+        rhai_engine: Arc<Engine>,
+        strategy_cache: StrategyCache,
+        store: Arc<LocalStore>,
+        candle_store: Arc<CandleStore>,
+    ) -> Result<Self, Error> {
+        let cfg = &request.config;
+        let margins = allocate_leg_margins(cfg.margin, &cfg.legs).map_err(Error::Custom)?;
+
+        let mut legs = Vec::with_capacity(cfg.legs.len());
+        for (idx, (leg, margin)) in cfg.legs.iter().zip(margins).enumerate() {
+            let leg_request = BacktestRunRequest {
+                run_id: request
+                    .run_id
+                    .as_ref()
+                    .map(|run_id| format!("{run_id}-leg{idx}")),
+                config: BacktestConfig {
+                    asset: leg.asset.clone(),
+                    source: cfg.source.clone(),
+                    strategy_id: leg.strategy_id,
+                    resolution: leg.resolution,
+                    margin,
+                    lev: leg.lev,
+                    taker_fee_bps: cfg.taker_fee_bps,
+                    maker_fee_bps: cfg.maker_fee_bps,
+                    funding_rate_bps_per_8h: cfg.funding_rate_bps_per_8h,
+                    historical_funding: cfg.historical_funding,
+                    start_time: cfg.start_time,
+                    end_time: cfg.end_time,
+                    snapshot_interval_candles: 0,
+                    max_equity_points: cfg.max_equity_points,
+                    max_snapshots: 0,
+                    max_script_logs: 0,
+                    fill_model: cfg.fill_model,
+                    intrabar_resolution: cfg.intrabar_resolution,
+                },
+                warmup_candles: request.warmup_candles,
+                monte_carlo: None,
+            };
+            leg_request
+                .config
+                .validate()
+                .map_err(|e| Error::Custom(format!("{}: {e}", leg.asset)))?;
+            legs.push(
+                Backtester::from_request(
+                    leg_request,
+                    rhai_engine.clone(),
+                    strategy_cache.clone(),
+                    store.clone(),
+                    candle_store.clone(),
+                )
+                .await?,
+            );
+        }
+
+        Ok(Self {
+            request,
+            legs,
+            cancel: CancellationToken::new(),
+        })
+    }
+
+    /// Cancelling `token` stops candle loading or the shared candle loop and
+    /// makes the run return an error after reporting `BacktestProgress::Cancelled`.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        for leg in &mut self.legs {
+            leg.set_cancel_token(token.clone());
+        }
+        self.cancel = token;
+    }
+
+    pub async fn run_with_progress<F>(
+        &mut self,
+        mut on_progress: F,
+    ) -> Result<PortfolioResult, Error>
+    where
+        F: FnMut(BacktestProgress),
+    {
+        let started_at = get_time_now();
+        let cfg = self.request.config.clone();
+        let run_id = self
+            .request
+            .run_id
+            .clone()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| format!("pf-{started_at}"));
+        info!(
+            "portfolio[{run_id}] start legs={} margin={} sim={}..{}",
+            self.legs.len(),
+            cfg.margin,
+            cfg.start_time,
+            cfg.end_time
+        );
+
+        let cancel = self.cancel.clone();
+        let mut runs = Vec::with_capacity(self.legs.len());
+        for (idx, backtester) in self.legs.iter_mut().enumerate() {
+            let leg_id = format!("{run_id}-leg{idx}");
+            let cursor = backtester.prepare(&leg_id, &mut on_progress).await?;
+            runs.push(LegRun {
+                backtester,
+                cursor,
+                last_candle: None,
+            });
+        }
+
+        let (mut equity_curve, liquidated) =
+            simulate_account(&mut runs, cfg.margin, &cancel, &run_id, &mut on_progress)?;
+
+        for run in runs.iter_mut() {
+            if let Err(err) = run.backtester.finish(&run.cursor, liquidated) {
+                on_progress(BacktestProgress::Failed {
+                    message: err.to_string(),
+                });
+                return Err(err);
+            }
+        }
+        equity_curve.push(equity_point(cfg.margin, &runs));
+
+        on_progress(BacktestProgress::Finalizing);
+        let finished_at = get_time_now();
+        let mut legs = Vec::with_capacity(runs.len());
+        let mut trades = Vec::new();
+        let mut candles_loaded = 0_u64;
+        let mut candles_processed = 0_u64;
+        for (run, leg) in runs.iter().zip(&cfg.legs) {
+            let result = run
+                .backtester
+                .build_result(started_at, finished_at, &run.cursor);
+            candles_loaded += result.candles_loaded;
+            candles_processed += result.candles_processed;
+            trades.extend(
+                result
+                    .trades
+                    .iter()
+                    .cloned()
+                    .zip(result.summary.trade_excursions.iter().copied()),
+            );
+            legs.push(PortfolioLegResult {
+                asset: leg.asset.clone(),
+                strategy_id: leg.strategy_id,
+                allocation: leg.allocation,
+                lev: leg.lev,
+                margin: result.config.margin,
+                summary: result.summary,
+                trades: result.trades,
+                equity_curve: result.equity_curve,
+            });
+        }
+        trades.sort_by_key(|(trade, _)| trade.close.time);
+        let (trades, excursions): (Vec<_>, Vec<_>) = trades.into_iter().unzip();
+
+        let resolution = cfg
+            .legs
+            .iter()
+            .map(|leg| leg.resolution)
+            .min_by_key(|tf| tf.to_millis())
+            .unwrap_or(TimeFrame::Hour1);
+        let final_equity = equity_curve.last().map(|p| p.equity).unwrap_or(cfg.margin);
+        let mut summary =
+            build_summary(cfg.margin, final_equity, &equity_curve, &trades, resolution);
+        summary.set_trade_excursions(excursions);
+        let equity_curve = lttb_equity(&equity_curve, cfg.max_equity_points);
+
+        info!(
+            "portfolio[{run_id}] done legs={} trades={} net_pnl={:.6} liquidated={liquidated}",
+            legs.len(),
+            summary.total_trades,
+            summary.net_pnl
+        );
+        on_progress(BacktestProgress::Done);
+        Ok(PortfolioResult {
+            run_id,
+            started_at,
+            finished_at,
+            candles_loaded,
+            candles_processed,
+            config: cfg,
+            summary,
+            equity_curve,
+            legs,
+            liquidated,
+        })
+    }
+}
+
+/// Step the legs through their merged time axis against the shared account.
+/// Returns the account's equity curve and whether it was liquidated.
+fn simulate_account<F>(
+    runs: &mut [LegRun],
+    margin: f64,
+    cancel: &CancellationToken,
+    run_id: &str,
+    on_progress: &mut F,
+) -> Result<(Vec<EquityPoint>, bool), Error>
+where
+    F: FnMut(BacktestProgress),
+{
+    let sim_total = runs.iter().map(|run| run.cursor.sim_total).sum::<u64>();
+    let mut equity_curve = Vec::new();
+    let mut liquidated = false;
+    let mut last_reported = 0_u64;
+
+    while let Some(ts) = runs.iter().filter_map(|run| run.cursor.peek_ts()).min() {
+        if cancel.is_cancelled() {
+            let processed = runs.iter().map(|run| run.cursor.sim_processed).sum::<u64>();
+            info!("portfolio[{run_id}] cancelled processed={processed} total={sim_total}");
+            on_progress(BacktestProgress::Cancelled { processed });
+            return Err(Error::Custom("Portfolio backtest cancelled".to_string()));
+        }
+        let mut stepped: Vec<(usize, Price)> = Vec::new();
+        for idx in 0..runs.len() {
+            if runs[idx].cursor.peek_ts() != Some(ts) {
+                continue;
+            }
+            let sizing_margin = leg_sizing_margin(margin, runs, idx);
+            let run = &mut runs[idx];
+            run.backtester.set_account_margin(sizing_margin);
+            let batch = run.cursor.next_batch_at(ts);
+            if let Some(candle) = run.backtester.step(&mut run.cursor, batch) {
+                run.backtester
+                    .settle_bar(candle, run.cursor.intrabar_for(candle));
+                run.last_candle = Some(candle);
+                stepped.push((idx, candle));
+            }
+        }
+        if stepped.is_empty() {
+            continue;
+        }
+
+        // Legs stepped at `ts` are marked at this bar's adverse extreme; the
+        // others already were when their own last bar closed.
+        let mut adverse_equity = account_balance(margin, runs);
+        let mut maintenance = 0.0;
+        for (idx, run) in runs.iter().enumerate() {
+            let Some(candle) = run.last_candle else {
+                continue;
+            };
+            let mark_px = if stepped.iter().any(|(i, _)| *i == idx) {
+                run.backtester.adverse_px(candle)
+            } else {
+                candle.close
+            };
+            adverse_equity += run.backtester.close_out_upnl(mark_px);
+            maintenance += run.backtester.position_margin(mark_px) * MAINTENANCE_MARGIN_RATIO;
+        }
+        if adverse_equity <= maintenance {
+            for run in runs.iter_mut() {
+                if let Some(candle) = run.last_candle {
+                    run.backtester.force_liquidate(candle);
+                }
+            }
+            liquidated = true;
+            info!("portfolio[{run_id}] account liquidated at ts={ts}");
+            break;
+        }
+
+        for (idx, candle) in stepped {
+            let run = &mut runs[idx];
+            run.backtester.mark_candle(candle);
+            run.cursor.set_last_sim_candle(candle);
+        }
+        equity_curve.push(equity_point(margin, runs));
+
+        let processed = runs.iter().map(|run| run.cursor.sim_processed).sum::<u64>();
+        if processed.saturating_sub(last_reported) >= 200 {
+            last_reported = processed;
+            on_progress(BacktestProgress::Simulating {
+                processed,
+                total: sim_total,
+            });
+        }
+    }
+
+    Ok((equity_curve, liquidated))
+}
+
+/// Every leg books its fills, fees and funding against its allocation, so the
+/// shared balance is the account's margin plus what the legs have booked.
+fn account_balance(margin: f64, runs: &[LegRun]) -> f64 {
+    margin
+        + runs
+            .iter()
+            .map(|run| run.backtester.balance() - run.allocation())
+            .sum::<f64>()
+}
+
+/// Free margin leg `idx` sizes from: the shared balance less the margin held
+/// by the other legs' positions, up to the leg's allocation.
+fn leg_sizing_margin(margin: f64, runs: &[LegRun], idx: usize) -> f64 {
+    let held = runs
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != idx)
+        .filter_map(|(_, run)| {
+            run.last_candle
+                .map(|candle| run.backtester.position_margin(candle.close))
+        })
+        .sum::<f64>();
+    (account_balance(margin, runs) - held)
+        .min(runs[idx].allocation())
+        .max(0.0)
+}
+
+fn equity_point(margin: f64, runs: &[LegRun]) -> EquityPoint {
+    let balance = account_balance(margin, runs);
+    let mut upnl = 0.0;
+    let mut ts = 0;
+    for run in runs {
+        if let Some(candle) = run.last_candle {
+            upnl += run.backtester.unrealised_pnl(candle.close);
+            ts = ts.max(candle.close_time);
+        }
+    }
+    EquityPoint {
+        ts,
+        equity: balance + upnl,
+        balance,
+        upnl,
+    }
+}
+
+/// Resolve leg allocations in order against the shared balance, mirroring
+/// `MarginBook::allocate_from_current`.
+pub fn allocate_leg_margins(total: f64, legs: &[PortfolioLeg]) -> Result<Vec<f64>, String> {
+    if !total.is_finite() || total <= 0.0 {
+        return Err("margin must be a positive finite number".to_string());
+    }
+    if legs.is_empty() || legs.len() > MAX_PORTFOLIO_LEGS {
+        return Err(format!(
+            "portfolio needs between 1 and {MAX_PORTFOLIO_LEGS} legs"
+        ));
+    }
+
+    let mut free = total;
+    let mut out = Vec::with_capacity(legs.len());
+    for leg in legs {
+        if leg.asset.trim().is_empty() {
+            return Err("leg asset must not be empty".to_string());
+        }
+        if leg.lev == 0 {
+            return Err(format!("{}: lev must be greater than zero", leg.asset));
+        }
+        let margin = match leg.allocation {
+            MarginAllocation::Alloc(ptc) if ptc.is_finite() && ptc > 0.0 && ptc <= 1.0 => {
+                free * ptc
+            }
+            MarginAllocation::Amount(amount) if amount.is_finite() && amount > 0.0 => amount,
+            _ => return Err(format!("{}: invalid margin allocation", leg.asset)),
+        };
+        if margin > free + 1e-9 {
+            return Err(format!(
+                "{}: insufficient free margin ({free:.2} left)",
+                leg.asset
+            ));
+        }
+        free -= margin;
+        out.push(margin);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::scripting::{compile_strategy, create_engine};
+    use crate::backtest::{Exchange, MarketType};
+
+    /// Leg book on 1m candles with no fees, funded by `margin`.
+    fn leg_backtester(asset: &str, on_idle: &str, margin: f64, lev: usize) -> Backtester {
+        let rhai_engine = Arc::new(create_engine());
+        let compiled =
+            compile_strategy(&rhai_engine, on_idle, "()", "()", None).expect("strategy compiles");
+        let request = BacktestRunRequest {
+            run_id: None,
+            config: BacktestConfig {
+                asset: asset.to_string(),
+                source: DataSource::new(Exchange::Hyperliquid, MarketType::Futures),
+                strategy_id: Uuid::nil(),
+                resolution: TimeFrame::Min1,
+                margin,
+                lev,
+                taker_fee_bps: 0,
+                maker_fee_bps: 0,
+                funding_rate_bps_per_8h: 0.0,
+                historical_funding: false,
+                start_time: 0,
+                end_time: 3_600_000,
+                snapshot_interval_candles: 0,
+                max_equity_points: 100,
+                max_snapshots: 0,
+                max_script_logs: 0,
+                fill_model: FillModel::default(),
+                intrabar_resolution: false,
+            },
+            warmup_candles: 0,
+            monte_carlo: None,
+        };
+        let candle_store = CandleStore::open(std::env::temp_dir().join("kwant-portfolio-tests"))
+            .expect("candle store opens");
+        Backtester::new(
+            request,
+            rhai_engine,
+            compiled,
+            Vec::new(),
+            Arc::new(candle_store),
+        )
+    }
+
+    fn bar(minute: u64, low: f64, high: f64, close: f64) -> Price {
+        Price {
+            open_time: minute * 60_000,
+            close_time: (minute + 1) * 60_000,
+            open: close,
+            high,
+            low,
+            close,
+            vlm: 1.0,
+        }
+    }
+
+    fn leg_run<'a>(backtester: &'a mut Backtester, asset: &str, prices: Vec<Price>) -> LegRun<'a> {
+        LegRun {
+            backtester,
+            cursor: SimCursor::from_prices(asset, TimeFrame::Min1, prices),
+            last_candle: None,
+        }
+    }
+
+    fn leg(asset: &str, allocation: MarginAllocation) -> PortfolioLeg {
+        PortfolioLeg {
+            asset: asset.to_string(),
+            strategy_id: Uuid::nil(),
+            resolution: TimeFrame::Hour1,
+            allocation,
+            lev: 2,
+        }
+    }
+
+    #[test]
+    fn allocations_draw_from_remaining_free_margin() {
+        let legs = [
+            leg("BTC", MarginAllocation::Amount(400.0)),
+            leg("ETH", MarginAllocation::Alloc(0.5)),
+            leg("SOL", MarginAllocation::Alloc(1.0)),
+        ];
+        let margins = allocate_leg_margins(1_000.0, &legs).unwrap();
+        assert_eq!(margins, vec![400.0, 300.0, 300.0]);
+    }
+
+    #[test]
+    fn allocations_reject_overdraw_and_bad_values() {
+        let overdraw = [
+            leg("BTC", MarginAllocation::Alloc(0.8)),
+            leg("ETH", MarginAllocation::Amount(300.0)),
+        ];
+        assert!(allocate_leg_margins(1_000.0, &overdraw).is_err());
+        assert!(
+            allocate_leg_margins(1_000.0, &[leg("BTC", MarginAllocation::Alloc(1.5))]).is_err()
+        );
+        assert!(allocate_leg_margins(1_000.0, &[]).is_err());
+    }
+
+    #[test]
+    fn config_validation_rejects_bad_legs_margin_and_range() {
+        let config = |margin: f64, legs: Vec<PortfolioLeg>| PortfolioConfig {
+            source: DataSource::new(Exchange::Hyperliquid, MarketType::Futures),
+            margin,
+            taker_fee_bps: 0,
+            maker_fee_bps: 0,
+            funding_rate_bps_per_8h: 0.0,
+            historical_funding: false,
+            start_time: 0,
+            end_time: 3_600_000,
+            legs,
+            max_equity_points: 100,
+            fill_model: FillModel::default(),
+            intrabar_resolution: false,
+        };
+        let btc = || leg("BTC", MarginAllocation::Alloc(0.5));
+
+        assert!(config(1_000.0, vec![btc()]).validate().is_ok());
+        assert!(config(1_000.0, Vec::new()).validate().is_err());
+        assert!(config(0.0, vec![btc()]).validate().is_err());
+        assert!(config(f64::NAN, vec![btc()]).validate().is_err());
+        let no_lev = PortfolioLeg { lev: 0, ..btc() };
+        assert!(config(1_000.0, vec![no_lev]).validate().is_err());
+        let inverted = PortfolioConfig {
+            end_time: 0,
+            ..config(1_000.0, vec![btc()])
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn losing_leg_liquidates_the_account_while_the_other_is_in_profit() {
+        let open = "open_market(LONG, margin_amount(400.0))";
+        let mut btc = leg_backtester("BTC", open, 500.0, 5);
+        let mut eth = leg_backtester("ETH", open, 500.0, 5);
+        let mut runs = vec![
+            leg_run(
+                &mut btc,
+                "BTC",
+                vec![bar(0, 99.0, 101.0, 100.0), bar(1, 110.0, 120.0, 118.0)],
+            ),
+            leg_run(
+                &mut eth,
+                "ETH",
+                vec![bar(0, 99.0, 101.0, 100.0), bar(1, 50.0, 100.0, 55.0)],
+            ),
+        ];
+
+        let (curve, liquidated) = simulate_account(
+            &mut runs,
+            1_000.0,
+            &CancellationToken::new(),
+            "test",
+            &mut |_| {},
+        )
+        .unwrap();
+
+        // Both legs hold 20 at 100. At the second bar's lows the account is
+        // worth 1000 + 200 - 1000 = 200, below half of the 640 of margin the
+        // positions hold at those prices, though still above zero.
+        assert!(liquidated);
+        assert_eq!(curve.len(), 1);
+        assert!(
+            runs.iter()
+                .all(|run| run.backtester.open_position().is_none())
+        );
+        assert!(runs[0].backtester.trades()[0].pnl > 0.0);
+        assert!(runs[1].backtester.trades()[0].pnl < 0.0);
+        assert!((equity_point(1_000.0, &runs).equity - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cancelling_stops_the_candle_loop_and_reports_it() {
+        let mut btc = leg_backtester("BTC", "()", 500.0, 2);
+        let mut runs = vec![leg_run(
+            &mut btc,
+            "BTC",
+            (0..3).map(|m| bar(m, 99.0, 101.0, 100.0)).collect(),
+        )];
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let mut progress = Vec::new();
+        let outcome = simulate_account(&mut runs, 1_000.0, &cancel, "test", &mut |evt| {
+            progress.push(evt)
+        });
+
+        assert!(outcome.is_err());
+        assert!(matches!(
+            progress.as_slice(),
+            [BacktestProgress::Cancelled { processed: 0 }]
+        ));
+        assert_eq!(runs[0].cursor.sim_processed, 0);
+    }
+
+    #[test]
+    fn legs_step_on_a_merged_time_axis_into_one_equity_curve() {
+        let mut btc = leg_backtester("BTC", "()", 500.0, 2);
+        let mut eth = leg_backtester("ETH", "()", 300.0, 2);
+        let mut runs = vec![
+            leg_run(
+                &mut btc,
+                "BTC",
+                (0..3).map(|m| bar(m, 99.0, 101.0, 100.0)).collect(),
+            ),
+            leg_run(
+                &mut eth,
+                "ETH",
+                (1..4).map(|m| bar(m, 99.0, 101.0, 100.0)).collect(),
+            ),
+        ];
+
+        let (curve, liquidated) = simulate_account(
+            &mut runs,
+            1_000.0,
+            &CancellationToken::new(),
+            "test",
+            &mut |_| {},
+        )
+        .unwrap();
+
+        assert!(!liquidated);
+        let ts: Vec<u64> = curve.iter().map(|point| point.ts).collect();
+        assert_eq!(ts, vec![60_000, 120_000, 180_000, 240_000]);
+        // The unallocated 200 stays in the account alongside both legs.
+        assert!(
+            curve
+                .iter()
+                .all(|point| (point.equity - 1_000.0).abs() < 1e-9)
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use super::types::{BacktestResult, EquityPoint};
+use crate::{Side, TradeInfo};
+
+const CHART_WIDTH: f64 = 960.0;
+const CHART_HEIGHT: f64 = 220.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    #[default]
+    Html,
+    TradesCsv,
+    EquityCsv,
+    Json,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 4] = [
+        ReportFormat::Html,
+        ReportFormat::TradesCsv,
+        ReportFormat::EquityCsv,
+        ReportFormat::Json,
+    ];
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "text/html; charset=utf-8",
+            ReportFormat::TradesCsv | ReportFormat::EquityCsv => "text/csv; charset=utf-8",
+            ReportFormat::Json => "application/json",
+        }
+    }
+
+    /// File name suffix, e.g. `{run_id}-trades.csv`.
+    pub fn file_suffix(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "report.html",
+            ReportFormat::TradesCsv => "trades.csv",
+            ReportFormat::EquityCsv => "equity.csv",
+            ReportFormat::Json => "result.json",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "html" => Ok(ReportFormat::Html),
+            "trades" | "trades.csv" | "tradescsv" => Ok(ReportFormat::TradesCsv),
+            "equity" | "equity.csv" | "equitycsv" => Ok(ReportFormat::EquityCsv),
+            "json" => Ok(ReportFormat::Json),
+            other => Err(format!(
+                "unknown report format {other}; expected html, trades, equity or json"
+            )),
+        }
+    }
+}
+
+pub fn render(result: &BacktestResult, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Html => html_report(result),
+        ReportFormat::TradesCsv => trades_csv(&result.trades),
+        ReportFormat::EquityCsv => equity_csv(&result.equity_curve),
+        ReportFormat::Json => json_report(result),
+    }
+}
+
+/// The stored result, pretty-printed. Field order follows the struct definitions.
+pub fn json_report(result: &BacktestResult) -> String {
+    serde_json::to_string_pretty(result).expect("backtest result serializes")
+}
+
+pub fn trades_csv(trades: &[TradeInfo]) -> String {
+    let mut out = String::from(
+        "side,size,open_time,open_px,open_fill,close_time,close_px,close_fill,fees,funding,pnl\n",
+    );
+    for t in trades {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{:?},{},{},{:?},{},{},{}",
+            side_str(t.side),
+            t.size,
+            t.open.time,
+            t.open.price,
+            t.open.fill_type,
+            t.close.time,
+            t.close.price,
+            t.close.fill_type,
+            t.fees,
+            t.funding,
+            t.pnl
+        );
+    }
+    out
+}
+
+pub fn equity_csv(curve: &[EquityPoint]) -> String {
+    let mut out = String::from("ts,equity,balance,upnl,drawdown_pct\n");
+    for (point, dd) in curve.iter().zip(drawdown_pct(curve)) {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{dd:.4}",
+            point.ts, point.equity, point.balance, point.upnl
+        );
+    }
+    out
+}
+
+/// Self-contained page: inline CSS and SVG charts, no external assets.
+pub fn html_report(result: &BacktestResult) -> String {
+    let cfg = &result.config;
+    let s = &result.summary;
+    let title = format!(
+        "{} {} backtest {}",
+        cfg.asset,
+        cfg.resolution.as_str(),
+        result.run_id
+    );
+
+    let mut metrics = vec![
+        ("Initial equity", format!("{:.2}", s.initial_equity)),
+        ("Final equity", format!("{:.2}", s.final_equity)),
+        ("Net PnL", format!("{:.2}", s.net_pnl)),
+        ("Return", format!("{:.2}%", s.return_pct)),
+        ("Max drawdown", format!("{:.2}%", s.max_drawdown_pct)),
+        ("Trades", s.total_trades.to_string()),
+        ("Win rate", format!("{:.2}%", s.win_rate_pct)),
+        ("Profit factor", fmt_opt(s.profit_factor)),
+        ("Expectancy", format!("{:.4}", s.expectancy)),
+        ("Sharpe", fmt_opt(s.sharpe_ratio)),
+        ("Sortino", fmt_opt(s.sortino_ratio)),
+        ("Calmar", fmt_opt(s.calmar_ratio)),
+        ("Time in market", format!("{:.2}%", s.time_in_market_pct)),
+        ("Longest win streak", s.longest_win_streak.to_string()),
+        ("Longest loss streak", s.longest_loss_streak.to_string()),
+    ];
+    if let Some(benchmark) = &result.benchmark {
+        metrics.push(("Buy & hold", format!("{:.2}%", benchmark.return_pct)));
+        metrics.push((
+            "Excess return",
+            format!("{:.2}%", benchmark.excess_return_pct),
+        ));
+    }
+
+    let mut html = String::with_capacity(16 * 1024);
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\"><title>{}</title>\
+         <style>body{{font-family:system-ui,sans-serif;margin:24px;color:#1b1f24}}\
+         table{{border-collapse:collapse;font-size:13px}}td,th{{padding:4px 10px;border-bottom:1px solid #e3e6ea;text-align:right}}\
+         th{{background:#f5f6f8}}td:first-child,th:first-child{{text-align:left}}\
+         .grid{{display:grid;grid-template-columns:repeat(auto-fill,minmax(180px,1fr));gap:8px;margin:16px 0}}\
+         .card{{border:1px solid #e3e6ea;border-radius:6px;padding:8px}}.card p{{margin:2px 0}}.label{{color:#6b7280;font-size:12px}}\
+         svg{{background:#fafbfc;border:1px solid #e3e6ea;border-radius:6px}}</style></head><body>",
+        escape(&title)
+    );
+    let _ = write!(
+        html,
+        "<h1>{}</h1><p>{} to {} &middot; {} candles &middot; margin {} &middot; {}x</p>",
+        escape(&title),
+        fmt_ts(cfg.start_time),
+        fmt_ts(cfg.end_time),
+        result.candles_processed,
+        cfg.margin,
+        cfg.lev
+    );
+
+    html.push_str("<div class=\"grid\">");
+    for (label, value) in metrics {
+        let _ = write!(
+            html,
+            "<div class=\"card\"><p class=\"label\">{}</p><p>{}</p></div>",
+            escape(label),
+            escape(&value)
+        );
+    }
+    html.push_str("</div>");
+
+    html.push_str("<h2>Equity</h2>");
+    html.push_str(&line_chart(
+        &result
+            .equity_curve
+            .iter()
+            .map(|p| (p.ts, p.equity))
+            .collect::<Vec<_>>(),
+        "#2563eb",
+    ));
+    html.push_str("<h2>Drawdown %</h2>");
+    html.push_str(&line_chart(
+        &result
+            .equity_curve
+            .iter()
+            .zip(drawdown_pct(&result.equity_curve))
+            .map(|(p, dd)| (p.ts, -dd))
+            .collect::<Vec<_>>(),
+        "#dc2626",
+    ));
+
+    if !s.monthly_returns.is_empty() {
+        html.push_str(
+            "<h2>Monthly returns</h2><table><tr><th>Month</th><th>PnL</th><th>Return</th></tr>",
+        );
+        for month in &s.monthly_returns {
+            let _ = write!(
+                html,
+                "<tr><td>{}-{:02}</td><td>{:.2}</td><td>{:.2}%</td></tr>",
+                month.year, month.month, month.pnl, month.return_pct
+            );
+        }
+        html.push_str("</table>");
+    }
+
+    html.push_str(
+        "<h2>Trades</h2><table><tr><th>#</th><th>Side</th><th>Open</th><th>Open px</th>\
+         <th>Close</th><th>Close px</th><th>Size</th><th>Fees</th><th>Funding</th><th>PnL</th></tr>",
+    );
+    for (idx, t) in result.trades.iter().enumerate() {
+        let _ = write!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+             <td>{}</td><td>{:.4}</td><td>{:.4}</td><td>{:.4}</td></tr>",
+            idx + 1,
+            side_str(t.side),
+            fmt_ts(t.open.time),
+            t.open.price,
+            fmt_ts(t.close.time),
+            t.close.price,
+            t.size,
+            t.fees,
+            t.funding,
+            t.pnl
+        );
+    }
+    html.push_str("</table></body></html>\n");
+    html
+}
+
+fn line_chart(points: &[(u64, f64)], color: &str) -> String {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return "<p>No data.</p>".to_string();
+    };
+    let (min, max) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (_, v)| {
+            (lo.min(*v), hi.max(*v))
+        });
+    let span_x = last.0.saturating_sub(first.0).max(1) as f64;
+    let span_y = (max - min).max(f64::EPSILON);
+
+    let mut path = String::with_capacity(points.len() * 16);
+    for (idx, (ts, value)) in points.iter().enumerate() {
+        let x = (ts - first.0) as f64 / span_x * CHART_WIDTH;
+        let y = CHART_HEIGHT - (value - min) / span_y * CHART_HEIGHT;
+        let _ = write!(path, "{}{x:.1},{y:.1}", if idx == 0 { "M" } else { " L" });
+    }
+    format!(
+        "<svg viewBox=\"0 0 {CHART_WIDTH} {CHART_HEIGHT}\" width=\"100%\" preserveAspectRatio=\"none\">\
+         <path d=\"{path}\" fill=\"none\" stroke=\"{color}\" stroke-width=\"1.5\" vector-effect=\"non-scaling-stroke\"/></svg>\
+         <p class=\"label\">min {min:.2} &middot; max {max:.2}</p>"
+    )
+}
+
+fn drawdown_pct(curve: &[EquityPoint]) -> Vec<f64> {
+    let mut peak = f64::NEG_INFINITY;
+    curve
+        .iter()
+        .map(|point| {
+            peak = peak.max(point.equity);
+            if peak > 0.0 {
+                (peak - point.equity) / peak * 100.0
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+fn side_str(side: Side) -> &'static str {
+    match side {
+        Side::Long => "long",
+        Side::Short => "short",
+    }
+}
+
+fn fmt_opt(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{v:.3}"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn fmt_ts(ts: u64) -> String {
+    DateTime::from_timestamp_millis(ts as i64)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+fn escape(raw: &str) -> String {
+    raw.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(ts: u64, equity: f64) -> EquityPoint {
+        EquityPoint {
+            ts,
+            equity,
+            balance: equity,
+            upnl: 0.0,
+        }
+    }
+
+    #[test]
+    fn equity_csv_includes_drawdown_from_running_peak() {
+        let csv = equity_csv(&[point(1, 100.0), point(2, 80.0), point(3, 120.0)]);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "ts,equity,balance,upnl,drawdown_pct");
+        assert_eq!(lines[2], "2,80,80,0,20.0000");
+        assert_eq!(lines[3], "3,120,120,0,0.0000");
+    }
+
+    #[test]
+    fn formats_parse_and_html_is_escaped() {
+        assert_eq!(
+            "trades.csv".parse::<ReportFormat>(),
+            Ok(ReportFormat::TradesCsv)
+        );
+        assert!("pdf".parse::<ReportFormat>().is_err());
+        assert_eq!(escape("<a & \"b\">"), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
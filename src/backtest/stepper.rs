@@ -0,0 +1,421 @@
+//! Bar-by-bar replay of a backtest for debugging strategy logic.
+//!
+//! A session owns a prepared [`Backtester`] and advances it one execution
+//! candle at a time, reporting what the engine and script saw on the way.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use rhai::Dynamic;
+use serde::{Deserialize, Serialize};
+
+use super::backtester::{Backtester, SimCursor};
+use super::types::{
+    BacktestProgress, BacktestResult, BacktestRunRequest, CandlePoint, ScriptLogEntry,
+};
+use crate::backend::scripting::dynamic_to_json;
+use crate::{
+    EngineView, Error, IndicatorData, Intent, OpenPositionLocal, Price, TimeFrame, get_time_now,
+};
+
+/// Intents kept in a frame; a long `continue` keeps only the latest ones.
+const MAX_FRAME_INTENTS: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntentKind {
+    Open,
+    Add,
+    Reduce,
+    Flatten,
+    Arm,
+    Disarm,
+    Abort,
+    SetSl,
+    SetTp,
+    ClearTriggers,
+}
+
+impl From<&Intent> for IntentKind {
+    fn from(intent: &Intent) -> Self {
+        match intent {
+            Intent::Open(_) => IntentKind::Open,
+            Intent::Add(_) => IntentKind::Add,
+            Intent::Reduce(_) => IntentKind::Reduce,
+            Intent::Flatten(_) => IntentKind::Flatten,
+            Intent::Arm(_) => IntentKind::Arm,
+            Intent::Disarm => IntentKind::Disarm,
+            Intent::Abort => IntentKind::Abort,
+            Intent::SetSl(_) => IntentKind::SetSl,
+            Intent::SetTp(_) => IntentKind::SetTp,
+            Intent::ClearTriggers => IntentKind::ClearTriggers,
+        }
+    }
+}
+
+/// An intent returned by the script, including ones the engine ignored
+/// because it was busy or in the wrong state.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepIntent {
+    /// Open time of the execution candle being simulated.
+    pub ts: u64,
+    /// Series whose tick produced the intent.
+    pub asset: Arc<str>,
+    pub tf: TimeFrame,
+    pub kind: IntentKind,
+    pub detail: String,
+}
+
+impl StepIntent {
+    pub fn new(ts: u64, asset: &Arc<str>, tf: TimeFrame, intent: Intent) -> Self {
+        Self {
+            ts,
+            asset: Arc::clone(asset),
+            tf,
+            kind: IntentKind::from(&intent),
+            detail: format!("{intent:?}"),
+        }
+    }
+}
+
+/// Condition that stops `step` and `continue` after the bar it matches.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Breakpoint {
+    Intent { intent: IntentKind },
+    AnyIntent,
+    TradeClosed,
+    EngineState { state: EngineView },
+}
+
+/// What happened during one bar, as seen by breakpoints.
+struct BarOutcome<'a> {
+    intents: &'a [StepIntent],
+    trade_closed: bool,
+    prev_state: EngineView,
+    state: EngineView,
+}
+
+impl Breakpoint {
+    fn matches(&self, bar: &BarOutcome) -> bool {
+        match self {
+            Breakpoint::Intent { intent } => bar.intents.iter().any(|i| i.kind == *intent),
+            Breakpoint::AnyIntent => !bar.intents.is_empty(),
+            Breakpoint::TradeClosed => bar.trade_closed,
+            Breakpoint::EngineState { state } => bar.prev_state != *state && bar.state == *state,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum StepCommand {
+    Start {
+        request: BacktestRunRequest,
+        #[serde(default)]
+        breakpoints: Vec<Breakpoint>,
+    },
+    Step {
+        bars: u64,
+    },
+    Continue,
+    Pause,
+    SetBreakpoints {
+        breakpoints: Vec<Breakpoint>,
+    },
+    Close,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum StopReason {
+    Bars,
+    Breakpoint { index: usize },
+    Paused,
+    End,
+    Liquidated,
+}
+
+/// Everything visible after the last simulated bar.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepFrame {
+    /// 1-based index of the execution candle.
+    pub bar: u64,
+    pub candle: CandlePoint,
+    pub engine_state: EngineView,
+    pub indicators: Vec<IndicatorData>,
+    /// The script's `state` map.
+    pub state: serde_json::Value,
+    /// Intents emitted since the previous frame.
+    pub intents: Vec<StepIntent>,
+    /// Script prints and errors since the previous frame.
+    pub logs: Vec<ScriptLogEntry>,
+    pub position: Option<OpenPositionLocal>,
+    pub balance: f64,
+    pub equity: f64,
+    pub trades: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum StepEvent {
+    Started {
+        session_id: String,
+        total: u64,
+    },
+    Stopped {
+        session_id: String,
+        reason: StopReason,
+        frame: Option<StepFrame>,
+    },
+    Finished {
+        session_id: String,
+        result: Box<BacktestResult>,
+    },
+    Error {
+        session_id: Option<String>,
+        message: String,
+    },
+}
+
+pub struct StepSession {
+    id: String,
+    backtester: Backtester,
+    cursor: SimCursor,
+    breakpoints: Vec<Breakpoint>,
+    started_at: u64,
+    bar: u64,
+    last_candle: Option<Price>,
+    intents: VecDeque<StepIntent>,
+    logs_seen: usize,
+    stop: Option<StopReason>,
+}
+
+impl StepSession {
+    /// Load the run's candles and warm the engine; the session then sits
+    /// before the first simulation candle.
+    pub async fn start<F>(
+        id: String,
+        mut backtester: Backtester,
+        breakpoints: Vec<Breakpoint>,
+        mut on_progress: F,
+    ) -> Result<Self, Error>
+    where
+        F: FnMut(BacktestProgress),
+    {
+        let started_at = get_time_now();
+        backtester.trace_intents();
+        let cursor = backtester.prepare(&id, &mut on_progress).await?;
+        Ok(Self {
+            id,
+            backtester,
+            cursor,
+            breakpoints,
+            started_at,
+            bar: 0,
+            last_candle: None,
+            intents: VecDeque::new(),
+            logs_seen: 0,
+            stop: None,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn total(&self) -> u64 {
+        self.cursor.sim_total
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Breakpoint>) {
+        self.breakpoints = breakpoints;
+    }
+
+    /// No more bars left; call [`StepSession::finish`] for the result.
+    pub fn is_done(&self) -> bool {
+        matches!(self.stop, Some(StopReason::End | StopReason::Liquidated))
+    }
+
+    /// Simulate up to `max_bars` execution candles, stopping early on the
+    /// first bar that matches a breakpoint.
+    pub fn advance(&mut self, max_bars: u64) -> StopReason {
+        if let Some(stop) = self.stop.filter(|_| self.is_done()) {
+            return stop;
+        }
+        for _ in 0..max_bars {
+            let prev_state = self.backtester.engine().view();
+            let trades_before = self.backtester.trades().len();
+            let Some((candle, liquidated)) = self.next_bar() else {
+                return self.stopped(StopReason::End);
+            };
+            self.bar += 1;
+            self.last_candle = Some(candle);
+            let bar_intents = self.backtester.take_traced_intents();
+            if liquidated {
+                self.keep_intents(bar_intents);
+                return self.stopped(StopReason::Liquidated);
+            }
+
+            let outcome = BarOutcome {
+                intents: &bar_intents,
+                trade_closed: self.backtester.trades().len() > trades_before,
+                prev_state,
+                state: self.backtester.engine().view(),
+            };
+            let hit = self.breakpoints.iter().position(|bp| bp.matches(&outcome));
+            self.keep_intents(bar_intents);
+            if let Some(index) = hit {
+                return self.stopped(StopReason::Breakpoint { index });
+            }
+        }
+        self.stopped(StopReason::Bars)
+    }
+
+    fn next_bar(&mut self) -> Option<(Price, bool)> {
+        while let Some(ts) = self.cursor.peek_ts() {
+            let batch = self.cursor.next_batch_at(ts);
+            if let Some(candle) = self.backtester.step(&mut self.cursor, batch) {
+                let liquidated = self
+                    .backtester
+                    .process_candle(candle, self.cursor.intrabar_for(candle));
+                self.cursor.set_last_sim_candle(candle);
+                return Some((candle, liquidated));
+            }
+        }
+        None
+    }
+
+    fn keep_intents(&mut self, intents: Vec<StepIntent>) {
+        self.intents.extend(intents);
+        let excess = self.intents.len().saturating_sub(MAX_FRAME_INTENTS);
+        self.intents.drain(..excess);
+    }
+
+    fn stopped(&mut self, reason: StopReason) -> StopReason {
+        self.stop = Some(reason);
+        reason
+    }
+
+    /// Snapshot of the last simulated bar; drains the intents and logs
+    /// collected since the previous frame.
+    pub fn frame(&mut self) -> Option<StepFrame> {
+        let candle = self.last_candle?;
+        let logs = self.backtester.script_logs();
+        let new_logs = logs[self.logs_seen.min(logs.len())..].to_vec();
+        self.logs_seen = logs.len();
+
+        let engine = self.backtester.engine();
+        let state = dynamic_to_json(&Dynamic::from_map(engine.strategy_state()));
+        let balance = self.backtester.balance();
+        Some(StepFrame {
+            bar: self.bar,
+            candle: CandlePoint::from(candle),
+            engine_state: engine.view(),
+            indicators: engine.get_indicators_data(),
+            state,
+            intents: self.intents.drain(..).collect(),
+            logs: new_logs,
+            position: self.backtester.open_position(),
+            balance,
+            equity: balance + self.backtester.unrealised_pnl(candle.close),
+            trades: self.backtester.trades().len(),
+        })
+    }
+
+    /// Close out the run the same way a full backtest would.
+    pub fn finish(mut self) -> Result<BacktestResult, Error> {
+        let liquidated = self.stop == Some(StopReason::Liquidated);
+        self.backtester.finish(&self.cursor, liquidated)?;
+        let mut result =
+            self.backtester
+                .build_result(self.started_at, get_time_now(), &self.cursor);
+        result.run_id = self.id;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(kind: IntentKind) -> StepIntent {
+        StepIntent {
+            ts: 0,
+            asset: Arc::from("BTC"),
+            tf: TimeFrame::Min1,
+            kind,
+            detail: String::new(),
+        }
+    }
+
+    #[test]
+    fn breakpoints_match_their_bar_conditions() {
+        let intents = [intent(IntentKind::Arm), intent(IntentKind::Open)];
+        let bar = BarOutcome {
+            intents: &intents,
+            trade_closed: false,
+            prev_state: EngineView::Armed,
+            state: EngineView::Opening,
+        };
+
+        assert!(
+            Breakpoint::Intent {
+                intent: IntentKind::Open
+            }
+            .matches(&bar)
+        );
+        assert!(
+            !Breakpoint::Intent {
+                intent: IntentKind::Flatten
+            }
+            .matches(&bar)
+        );
+        assert!(Breakpoint::AnyIntent.matches(&bar));
+        assert!(!Breakpoint::TradeClosed.matches(&bar));
+        assert!(
+            Breakpoint::EngineState {
+                state: EngineView::Opening
+            }
+            .matches(&bar)
+        );
+
+        let unchanged = BarOutcome {
+            prev_state: EngineView::Opening,
+            ..bar
+        };
+        assert!(
+            !Breakpoint::EngineState {
+                state: EngineView::Opening
+            }
+            .matches(&unchanged)
+        );
+    }
+
+    #[test]
+    fn step_commands_parse_from_client_json() {
+        let cmd: StepCommand = serde_json::from_str(r#"{"kind":"step","bars":5}"#).unwrap();
+        assert!(matches!(cmd, StepCommand::Step { bars: 5 }));
+
+        let cmd: StepCommand = serde_json::from_str(
+            r#"{"kind":"setBreakpoints","breakpoints":[{"kind":"intent","intent":"open"},{"kind":"engineState","state":"Open"}]}"#,
+        )
+        .unwrap();
+        let StepCommand::SetBreakpoints { breakpoints } = cmd else {
+            panic!("expected setBreakpoints");
+        };
+        assert_eq!(
+            breakpoints,
+            vec![
+                Breakpoint::Intent {
+                    intent: IntentKind::Open
+                },
+                Breakpoint::EngineState {
+                    state: EngineView::Open
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,582 @@
+//! Synthetic candles for stress-testing strategies on markets that never
+//! happened. Series are written under `Exchange::Synthetic` and backtested
+//! like any other offline source.
+
+use log::info;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::candle_store::CandleStore;
+use super::fetcher::{DataSource, Exchange};
+use super::importer::{resample, target_timeframes};
+use crate::{Error, Price, TimeFrame};
+
+const YEAR_MS: f64 = 365.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+/// Roughly nine years of 1m candles.
+const MAX_SYNTHETIC_CANDLES: u64 = 5_000_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntheticConfig {
+    pub asset: String,
+    pub start_time: u64,
+    pub end_time: u64,
+    /// Interval of the generated candles; coarser timeframes are resampled from it.
+    #[serde(default = "default_interval")]
+    pub interval: TimeFrame,
+    pub start_price: f64,
+    /// Typical volume of a quiet candle; bootstrap uses the historical volume.
+    #[serde(default = "default_base_volume")]
+    pub base_volume: f64,
+    /// The same seed and config always produce the same candles.
+    #[serde(default)]
+    pub seed: u64,
+    pub model: PriceModel,
+    #[serde(default)]
+    pub scenarios: Vec<Scenario>,
+    /// Timeframes written; empty writes every multiple of `interval`.
+    #[serde(default)]
+    pub timeframes: Vec<TimeFrame>,
+}
+
+fn default_interval() -> TimeFrame {
+    TimeFrame::Min1
+}
+
+fn default_base_volume() -> f64 {
+    1_000.0
+}
+
+/// Drift and volatility are annualised fractions, e.g. `0.8` for 80%.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum PriceModel {
+    /// Geometric Brownian motion, optionally with Merton jumps.
+    Gbm {
+        drift: f64,
+        volatility: f64,
+        #[serde(default)]
+        jumps: Option<Jumps>,
+    },
+    /// GBM whose parameters switch between regimes, starting in the first.
+    RegimeSwitching { regimes: Vec<Regime> },
+    /// Blocks of real candles drawn with replacement and chained end to end.
+    /// The history must already be in the candle store at `interval`.
+    Bootstrap {
+        source: DataSource,
+        asset: String,
+        start_time: u64,
+        end_time: u64,
+        block_len: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Jumps {
+    /// Expected jumps per year.
+    pub intensity: f64,
+    /// Mean and standard deviation of the log jump size.
+    pub mean: f64,
+    pub std: f64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Regime {
+    pub drift: f64,
+    pub volatility: f64,
+    /// Average candles spent in the regime before switching to another.
+    pub mean_bars: f64,
+    #[serde(default)]
+    pub jumps: Option<Jumps>,
+}
+
+/// Event forced onto the generated path at a given time.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum Scenario {
+    /// Price loses `dropPct` percent over `bars` candles starting at `at`.
+    Crash { at: u64, drop_pct: f64, bars: usize },
+    /// The candle containing `at` opens `pct` percent away from the previous close.
+    Gap { at: u64, pct: f64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntheticSummary {
+    pub candles: usize,
+    pub first_open_time: u64,
+    pub last_open_time: u64,
+    pub last_close: f64,
+    /// Candles written per resampled timeframe.
+    pub written: Vec<(TimeFrame, usize)>,
+}
+
+impl SyntheticConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.asset.trim().is_empty() {
+            return Err("asset must not be empty".to_string());
+        }
+        if self.end_time <= self.start_time {
+            return Err("endTime must be greater than startTime".to_string());
+        }
+        if !self.start_price.is_finite() || self.start_price <= 0.0 {
+            return Err("startPrice must be a positive finite number".to_string());
+        }
+        if !self.base_volume.is_finite() || self.base_volume < 0.0 {
+            return Err("baseVolume must be finite and non-negative".to_string());
+        }
+        if self.candle_count() > MAX_SYNTHETIC_CANDLES {
+            return Err(format!(
+                "at most {MAX_SYNTHETIC_CANDLES} candles can be generated at once"
+            ));
+        }
+        match &self.model {
+            PriceModel::Gbm {
+                drift,
+                volatility,
+                jumps,
+            } => validate_gbm(*drift, *volatility, jumps.as_ref())?,
+            PriceModel::RegimeSwitching { regimes } => {
+                if regimes.is_empty() {
+                    return Err("regimeSwitching needs at least one regime".to_string());
+                }
+                for regime in regimes {
+                    validate_gbm(regime.drift, regime.volatility, regime.jumps.as_ref())?;
+                    if !regime.mean_bars.is_finite() || regime.mean_bars < 1.0 {
+                        return Err("regime meanBars must be at least 1".to_string());
+                    }
+                }
+            }
+            PriceModel::Bootstrap {
+                start_time,
+                end_time,
+                block_len,
+                ..
+            } => {
+                if end_time <= start_time {
+                    return Err("bootstrap endTime must be greater than startTime".to_string());
+                }
+                if *block_len == 0 {
+                    return Err("bootstrap blockLen must be greater than zero".to_string());
+                }
+            }
+        }
+        for scenario in &self.scenarios {
+            match *scenario {
+                Scenario::Crash { drop_pct, bars, .. } => {
+                    if !(drop_pct > 0.0 && drop_pct < 100.0) || bars == 0 {
+                        return Err(
+                            "crash dropPct must be in (0, 100) over at least one bar".to_string()
+                        );
+                    }
+                }
+                Scenario::Gap { pct, .. } => {
+                    if !pct.is_finite() || pct <= -100.0 {
+                        return Err("gap pct must be finite and above -100".to_string());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn first_open_time(&self) -> u64 {
+        let interval_ms = self.interval.to_millis();
+        self.start_time.div_ceil(interval_ms) * interval_ms
+    }
+
+    fn candle_count(&self) -> u64 {
+        let interval_ms = self.interval.to_millis();
+        if interval_ms == 0 {
+            return 0;
+        }
+        self.end_time.saturating_sub(self.first_open_time()) / interval_ms
+    }
+}
+
+fn validate_gbm(drift: f64, volatility: f64, jumps: Option<&Jumps>) -> Result<(), String> {
+    if !drift.is_finite() || !volatility.is_finite() || volatility < 0.0 {
+        return Err("drift must be finite and volatility non-negative".to_string());
+    }
+    if let Some(jumps) = jumps
+        && !(jumps.intensity.is_finite()
+            && jumps.intensity >= 0.0
+            && jumps.mean.is_finite()
+            && jumps.std.is_finite()
+            && jumps.std >= 0.0)
+    {
+        return Err("jump intensity and std must be finite and non-negative".to_string());
+    }
+    Ok(())
+}
+
+/// Generate the series and replace whatever `source` held for the asset.
+pub async fn write_synthetic(
+    store: &CandleStore,
+    source: &DataSource,
+    config: &SyntheticConfig,
+) -> Result<SyntheticSummary, Error> {
+    if source.exchange != Exchange::Synthetic {
+        return Err(Error::Custom(
+            "Synthetic candles can only be written under the synthetic exchange".to_string(),
+        ));
+    }
+    config.validate().map_err(Error::Custom)?;
+    let interval_ms = config.interval.to_millis();
+    let targets = target_timeframes(interval_ms, &config.timeframes)?;
+
+    let history = match &config.model {
+        PriceModel::Bootstrap {
+            source: history_source,
+            asset,
+            start_time,
+            end_time,
+            ..
+        } => {
+            let key =
+                history_source.candle_key(&history_source.normalize_asset(asset), config.interval);
+            let _guard = store.acquire_key(&key, |_, _| {}).await;
+            store.range_to_vec(&key, *start_time, *end_time)
+        }
+        _ => Vec::new(),
+    };
+    let prices = generate(config, &history)?;
+
+    let asset = source.normalize_asset(&config.asset);
+    let mut written = Vec::with_capacity(targets.len());
+    for tf in targets {
+        let resampled = resample(&prices, interval_ms, tf);
+        let key = source.candle_key(&asset, tf);
+        let _guard = store.acquire_key(&key, |_, _| {}).await;
+        store.replace_all(&key, &resampled);
+        written.push((tf, resampled.len()));
+    }
+
+    let summary = SyntheticSummary {
+        candles: prices.len(),
+        first_open_time: prices.first().map(|p| p.open_time).unwrap_or_default(),
+        last_open_time: prices.last().map(|p| p.open_time).unwrap_or_default(),
+        last_close: prices.last().map(|p| p.close).unwrap_or_default(),
+        written,
+    };
+    info!(
+        "generated {} synthetic candles for {} seed={} ({:?})",
+        summary.candles, asset, config.seed, summary.written
+    );
+    Ok(summary)
+}
+
+/// One candle relative to the previous close.
+#[derive(Clone, Copy, Debug)]
+struct Shape {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    vlm: f64,
+}
+
+/// Generate `config`'s candles; `history` feeds the bootstrap model.
+pub fn generate(config: &SyntheticConfig, history: &[Price]) -> Result<Vec<Price>, Error> {
+    config.validate().map_err(Error::Custom)?;
+    let interval_ms = config.interval.to_millis();
+    let count = config.candle_count() as usize;
+    let dt = interval_ms as f64 / YEAR_MS;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let mut shapes = match &config.model {
+        PriceModel::Gbm {
+            drift,
+            volatility,
+            jumps,
+        } => (0..count)
+            .map(|_| gbm_shape(&mut rng, *drift, *volatility, jumps.as_ref(), dt, config))
+            .collect(),
+        PriceModel::RegimeSwitching { regimes } => {
+            let mut current = 0;
+            let mut shapes = Vec::with_capacity(count);
+            for _ in 0..count {
+                let regime = regimes[current];
+                shapes.push(gbm_shape(
+                    &mut rng,
+                    regime.drift,
+                    regime.volatility,
+                    regime.jumps.as_ref(),
+                    dt,
+                    config,
+                ));
+                if regimes.len() > 1 && rng.gen_bool((1.0 / regime.mean_bars).min(1.0)) {
+                    // Uniformly among the other regimes.
+                    let next = rng.gen_range(0..regimes.len() - 1);
+                    current = if next >= current { next + 1 } else { next };
+                }
+            }
+            shapes
+        }
+        PriceModel::Bootstrap { block_len, .. } => {
+            bootstrap_shapes(&mut rng, history, *block_len, count)?
+        }
+    };
+
+    let first_open = config.first_open_time();
+    apply_scenarios(&mut shapes, &config.scenarios, first_open, interval_ms);
+
+    let mut prev_close = config.start_price;
+    Ok(shapes
+        .iter()
+        .enumerate()
+        .map(|(idx, shape)| {
+            let open_time = first_open + idx as u64 * interval_ms;
+            let price = Price {
+                open: prev_close * shape.open,
+                high: prev_close * shape.high,
+                low: prev_close * shape.low,
+                close: prev_close * shape.close,
+                open_time,
+                close_time: open_time + interval_ms,
+                vlm: shape.vlm,
+            };
+            prev_close = price.close;
+            price
+        })
+        .collect())
+}
+
+fn gbm_shape(
+    rng: &mut StdRng,
+    drift: f64,
+    volatility: f64,
+    jumps: Option<&Jumps>,
+    dt: f64,
+    config: &SyntheticConfig,
+) -> Shape {
+    let sigma = volatility * dt.sqrt();
+    let mut ret = (drift - 0.5 * volatility * volatility) * dt + sigma * standard_normal(rng);
+    if let Some(jumps) = jumps
+        && rng.gen_bool((jumps.intensity * dt).min(1.0))
+    {
+        ret += jumps.mean + jumps.std * standard_normal(rng);
+    }
+    let close = ret.exp();
+    // Wicks scale with the bar's volatility; volume with the size of the move.
+    let high = 1.0_f64.max(close) * (0.5 * sigma * standard_normal(rng).abs()).exp();
+    let low = 1.0_f64.min(close) * (-0.5 * sigma * standard_normal(rng).abs()).exp();
+    let activity = if sigma > 0.0 {
+        1.0 + ret.abs() / sigma
+    } else {
+        1.0
+    };
+    let vlm = config.base_volume * activity * (0.25 * standard_normal(rng)).exp();
+    Shape {
+        open: 1.0,
+        high,
+        low,
+        close,
+        vlm,
+    }
+}
+
+fn bootstrap_shapes(
+    rng: &mut StdRng,
+    history: &[Price],
+    block_len: usize,
+    count: usize,
+) -> Result<Vec<Shape>, Error> {
+    let source: Vec<Shape> = history
+        .windows(2)
+        .filter(|pair| pair[0].close > 0.0)
+        .map(|pair| {
+            let (prev, candle) = (pair[0].close, pair[1]);
+            Shape {
+                open: candle.open / prev,
+                high: candle.high / prev,
+                low: candle.low / prev,
+                close: candle.close / prev,
+                vlm: candle.vlm,
+            }
+        })
+        .collect();
+    if source.len() < block_len {
+        return Err(Error::Custom(format!(
+            "Bootstrap needs at least {} stored candles, found {}",
+            block_len + 1,
+            history.len()
+        )));
+    }
+
+    let mut shapes = Vec::with_capacity(count);
+    while shapes.len() < count {
+        let start = rng.gen_range(0..=source.len() - block_len);
+        let take = block_len.min(count - shapes.len());
+        shapes.extend_from_slice(&source[start..start + take]);
+    }
+    Ok(shapes)
+}
+
+fn apply_scenarios(
+    shapes: &mut [Shape],
+    scenarios: &[Scenario],
+    first_open: u64,
+    interval_ms: u64,
+) {
+    let bar_at = |ts: u64| {
+        (ts >= first_open)
+            .then(|| ((ts - first_open) / interval_ms) as usize)
+            .filter(|idx| *idx < shapes.len())
+    };
+    for scenario in scenarios {
+        match *scenario {
+            Scenario::Crash { at, drop_pct, bars } => {
+                let Some(start) = bar_at(at) else {
+                    continue;
+                };
+                let per_bar = (1.0 - drop_pct / 100.0).powf(1.0 / bars as f64);
+                for shape in shapes.iter_mut().skip(start).take(bars) {
+                    shape.close *= per_bar;
+                    shape.low = shape.low.min(shape.close);
+                }
+            }
+            Scenario::Gap { at, pct } => {
+                let Some(idx) = bar_at(at) else {
+                    continue;
+                };
+                let factor = 1.0 + pct / 100.0;
+                let shape = &mut shapes[idx];
+                shape.open *= factor;
+                shape.high *= factor;
+                shape.low *= factor;
+                shape.close *= factor;
+            }
+        }
+    }
+}
+
+/// Box-Muller draw, so the stream only depends on `rng`.
+fn standard_normal(rng: &mut StdRng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen_range(0.0..1.0);
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    fn config(model: PriceModel) -> SyntheticConfig {
+        SyntheticConfig {
+            asset: "SYN".to_string(),
+            start_time: 0,
+            end_time: 500 * MINUTE,
+            interval: TimeFrame::Min1,
+            start_price: 100.0,
+            base_volume: 1_000.0,
+            seed: 42,
+            model,
+            scenarios: Vec::new(),
+            timeframes: Vec::new(),
+        }
+    }
+
+    fn jump_diffusion() -> PriceModel {
+        PriceModel::Gbm {
+            drift: 0.1,
+            volatility: 0.8,
+            jumps: Some(Jumps {
+                intensity: 500.0,
+                mean: -0.02,
+                std: 0.01,
+            }),
+        }
+    }
+
+    #[test]
+    fn fixed_seed_reproduces_valid_candles() {
+        let cfg = config(jump_diffusion());
+        let ohlc = |prices: &[Price]| -> Vec<(f64, f64, f64, f64, f64)> {
+            prices
+                .iter()
+                .map(|p| (p.open, p.high, p.low, p.close, p.vlm))
+                .collect()
+        };
+        let first = generate(&cfg, &[]).unwrap();
+
+        assert_eq!(first.len(), 500);
+        assert_eq!(ohlc(&first), ohlc(&generate(&cfg, &[]).unwrap()));
+        assert_ne!(
+            ohlc(&first),
+            ohlc(&generate(&SyntheticConfig { seed: 43, ..cfg }, &[]).unwrap())
+        );
+        for pair in first.windows(2) {
+            assert_eq!(pair[1].open, pair[0].close);
+            assert_eq!(pair[1].open_time, pair[0].close_time);
+        }
+        assert!(first.iter().all(|p| {
+            p.low > 0.0 && p.low <= p.open.min(p.close) && p.high >= p.open.max(p.close)
+        }));
+    }
+
+    #[test]
+    fn scenarios_force_crash_and_gap() {
+        let mut cfg = config(PriceModel::Gbm {
+            drift: 0.0,
+            volatility: 0.0,
+            jumps: None,
+        });
+        cfg.end_time = 20 * MINUTE;
+        cfg.scenarios = vec![
+            Scenario::Crash {
+                at: 2 * MINUTE,
+                drop_pct: 50.0,
+                bars: 4,
+            },
+            Scenario::Gap {
+                at: 10 * MINUTE + 30_000,
+                pct: 10.0,
+            },
+        ];
+        let prices = generate(&cfg, &[]).unwrap();
+
+        assert!((prices[1].close - 100.0).abs() < 1e-9);
+        assert!((prices[5].close - 50.0).abs() < 1e-9);
+        assert!((prices[9].close - 50.0).abs() < 1e-9);
+        assert!((prices[10].open - 55.0).abs() < 1e-9);
+        assert!((prices[19].close - 55.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn bootstrap_chains_historical_candle_shapes() {
+        let history: Vec<Price> = (0..50)
+            .map(|i| {
+                let close = 10.0 * 1.01_f64.powi(i);
+                Price {
+                    open: close / 1.01,
+                    high: close * 1.02,
+                    low: close / 1.02,
+                    close,
+                    open_time: i as u64 * MINUTE,
+                    close_time: (i as u64 + 1) * MINUTE,
+                    vlm: 7.0,
+                }
+            })
+            .collect();
+        let mut cfg = config(PriceModel::Bootstrap {
+            source: DataSource::default(),
+            asset: "BTC".to_string(),
+            start_time: 0,
+            end_time: 50 * MINUTE,
+            block_len: 8,
+        });
+        cfg.end_time = 30 * MINUTE;
+        let prices = generate(&cfg, &history).unwrap();
+
+        assert_eq!(prices.len(), 30);
+        assert!((prices[29].close - 100.0 * 1.01_f64.powi(30)).abs() < 1e-6);
+        assert!(prices.iter().all(|p| p.vlm == 7.0));
+
+        assert!(generate(&cfg, &history[..5]).is_err());
+    }
+}
@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 
 use uuid::Uuid;
 
+use super::benchmark::BenchmarkSummary;
 use super::fetcher::DataSource;
+use super::monte_carlo::{MonteCarloConfig, MonteCarloSummary};
+use crate::backend::ScriptLogKind;
 use crate::{EngineView, IndicatorData, OpenPositionLocal, Price, TimeFrame, TradeInfo};
 
 pub type PnlTracker = BTreeMap<u64, f64>;
@@ -21,6 +24,9 @@ pub struct BacktestConfig {
     pub taker_fee_bps: u32,
     pub maker_fee_bps: u32,
     pub funding_rate_bps_per_8h: f64,
+    /// Charge the venue's recorded funding rates instead of `funding_rate_bps_per_8h`.
+    #[serde(default)]
+    pub historical_funding: bool,
     pub start_time: u64,
     pub end_time: u64,
     pub snapshot_interval_candles: u64,
@@ -28,6 +34,35 @@ pub struct BacktestConfig {
     pub max_equity_points: usize,
     #[serde(default = "default_max_snapshots")]
     pub max_snapshots: usize,
+    /// Script prints and runtime errors kept in the result; later ones are counted only.
+    #[serde(default = "default_max_script_logs")]
+    pub max_script_logs: usize,
+    #[serde(default)]
+    pub fill_model: FillModel,
+    /// Load 1m candles to settle bars where several fills could have happened first.
+    #[serde(default)]
+    pub intrabar_resolution: bool,
+}
+
+impl BacktestConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.asset.trim().is_empty() {
+            return Err("asset must not be empty".to_string());
+        }
+        if !self.margin.is_finite() || self.margin <= 0.0 {
+            return Err("margin must be a positive finite number".to_string());
+        }
+        if self.lev == 0 {
+            return Err("lev must be greater than zero".to_string());
+        }
+        if self.end_time <= self.start_time {
+            return Err("endTime must be greater than startTime".to_string());
+        }
+        if self.resolution.to_millis() == 0 {
+            return Err("resolution must be a supported timeframe".to_string());
+        }
+        self.fill_model.validate()
+    }
 }
 
 fn default_max_equity_points() -> usize {
@@ -38,6 +73,123 @@ fn default_max_snapshots() -> usize {
     500
 }
 
+fn default_max_script_logs() -> usize {
+    1000
+}
+
+/// How orders are priced and filled. The default fills market orders at the
+/// signal candle's close and limit orders in full on touch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FillModel {
+    #[serde(default)]
+    pub slippage: SlippageModel,
+    #[serde(default)]
+    pub latency: LatencyModel,
+    #[serde(default)]
+    pub limit: LimitFillModel,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum SlippageModel {
+    #[default]
+    None,
+    FixedBps {
+        bps: f64,
+    },
+    /// Slip by `multiplier` times the execution timeframe's ATR.
+    Atr {
+        period: usize,
+        multiplier: f64,
+    },
+    /// Square-root impact: `impactBps * sqrt(size / candle volume)`.
+    VolumeImpact {
+        impact_bps: f64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LatencyModel {
+    #[default]
+    Immediate,
+    /// Market orders fill at the next execution candle's open.
+    NextOpen,
+}
+
+/// Fills of resting limit orders. TP/SL triggers are not affected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitFillModel {
+    #[serde(default)]
+    pub touch: TouchFill,
+    /// Largest share of a candle's volume one order can take; the rest stays
+    /// resting. Candles without volume are not capped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_volume_fraction: Option<f64>,
+}
+
+/// What happens when a candle reaches a limit price without trading past it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum TouchFill {
+    #[default]
+    Fill,
+    /// Only fill once price trades `bps` beyond the level.
+    TradeThrough { bps: f64 },
+    /// Fill a touch with `probability`, standing in for queue position.
+    /// Trading through always fills.
+    Probability {
+        probability: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+}
+
+impl LimitFillModel {
+    pub fn validate(&self) -> Result<(), String> {
+        let touch_valid = match self.touch {
+            TouchFill::Fill => true,
+            TouchFill::TradeThrough { bps } => bps.is_finite() && bps >= 0.0,
+            TouchFill::Probability { probability, .. } => (0.0..=1.0).contains(&probability),
+        };
+        if !touch_valid {
+            return Err(
+                "fillModel.limit touch parameters must be finite, with probability in [0, 1]"
+                    .to_string(),
+            );
+        }
+        if let Some(fraction) = self.max_volume_fraction
+            && !(fraction > 0.0 && fraction <= 1.0)
+        {
+            return Err("fillModel.limit maxVolumeFraction must be in (0, 1]".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl FillModel {
+    pub fn validate(&self) -> Result<(), String> {
+        let valid = match self.slippage {
+            SlippageModel::None => true,
+            SlippageModel::FixedBps { bps } => bps.is_finite() && bps >= 0.0,
+            SlippageModel::Atr { period, multiplier } => {
+                period > 0 && multiplier.is_finite() && multiplier >= 0.0
+            }
+            SlippageModel::VolumeImpact { impact_bps } => {
+                impact_bps.is_finite() && impact_bps >= 0.0
+            }
+        };
+        if !valid {
+            return Err(
+                "fillModel slippage parameters must be finite and non-negative".to_string(),
+            );
+        }
+        self.limit.validate()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BacktestRunRequest {
@@ -45,6 +197,35 @@ pub struct BacktestRunRequest {
     pub run_id: Option<String>,
     pub config: BacktestConfig,
     pub warmup_candles: u64,
+    /// Resample the finished run's trades; results land in `summary.monteCarlo`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monte_carlo: Option<MonteCarloConfig>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckpointStatus {
+    Running,
+    Cancelled,
+    /// Still running when the process stopped; set as the store opens.
+    Interrupted,
+}
+
+/// How far a single run got, kept until it completes so a cancelled or
+/// interrupted run can be retried with the same request. Engine and script
+/// state are not saved, so a retry runs the request again from the first
+/// candle, reusing the candles the first attempt stored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacktestCheckpoint {
+    pub run_id: String,
+    pub request: BacktestRunRequest,
+    pub status: CheckpointStatus,
+    /// Simulated execution-series candles.
+    pub processed: u64,
+    pub total: u64,
+    pub started_at: u64,
+    pub updated_at: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -54,9 +235,12 @@ pub enum BacktestProgress {
     LoadingCandles { loaded: u64, total: u64 },
     WarmingEngine { loaded: u64, total: u64 },
     Simulating { processed: u64, total: u64 },
+    Optimizing { completed: u64, total: u64 },
+    WalkForward { window: u64, total: u64 },
     Finalizing,
     Done,
     Failed { message: String },
+    Cancelled { processed: u64 },
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -98,11 +282,13 @@ pub struct EquityPoint {
 #[serde(rename_all = "camelCase")]
 pub enum SnapshotReason {
     Open,
+    Add,
     Reduce,
     Flatten,
     Close,
     ForceClose,
     CancelResting,
+    TriggerUpdate,
     Fill,
     Interval,
 }
@@ -143,6 +329,69 @@ pub struct BacktestSummary {
     pub expectancy: f64,
     #[serde(default)]
     pub sharpe_ratio: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monte_carlo: Option<MonteCarloSummary>,
+    /// Price impact paid on market fills under `config.fillModel`.
+    #[serde(default)]
+    pub slippage_cost: f64,
+    /// Bars whose fill order was decided by replaying 1m candles.
+    #[serde(default)]
+    pub intrabar_resolved_bars: usize,
+    #[serde(default)]
+    pub sortino_ratio: Option<f64>,
+    /// Annualised return over max drawdown.
+    #[serde(default)]
+    pub calmar_ratio: Option<f64>,
+    /// Share of the simulated span with an open position.
+    #[serde(default)]
+    pub time_in_market_pct: f64,
+    #[serde(default)]
+    pub avg_trade_duration_ms: u64,
+    #[serde(default)]
+    pub median_trade_duration_ms: u64,
+    #[serde(default)]
+    pub longest_win_streak: usize,
+    #[serde(default)]
+    pub longest_loss_streak: usize,
+    #[serde(default)]
+    pub avg_mae_pct: f64,
+    #[serde(default)]
+    pub avg_mfe_pct: f64,
+    /// One entry per trade, in the same order as `trades`.
+    #[serde(default)]
+    pub trade_excursions: Vec<TradeExcursion>,
+    #[serde(default)]
+    pub monthly_returns: Vec<MonthlyReturn>,
+}
+
+impl BacktestSummary {
+    pub fn set_trade_excursions(&mut self, excursions: Vec<TradeExcursion>) {
+        let count = excursions.len().max(1) as f64;
+        self.avg_mae_pct = excursions.iter().map(|e| e.mae_pct).sum::<f64>() / count;
+        self.avg_mfe_pct = excursions.iter().map(|e| e.mfe_pct).sum::<f64>() / count;
+        self.trade_excursions = excursions;
+    }
+}
+
+/// Largest adverse and favourable price move while a trade was open,
+/// in percent of its entry price.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeExcursion {
+    pub mae_pct: f64,
+    pub mfe_pct: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyReturn {
+    pub year: i32,
+    /// 1-12, UTC.
+    pub month: u32,
+    pub start_equity: f64,
+    pub end_equity: f64,
+    pub pnl: f64,
+    pub return_pct: f64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -158,6 +407,24 @@ pub struct BacktestResult {
     pub trades: Vec<TradeInfo>,
     pub equity_curve: Vec<EquityPoint>,
     pub snapshots: Vec<PositionSnapshot>,
+    /// Buy-and-hold over the execution series, for comparison with `summary`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<BenchmarkSummary>,
+    #[serde(default)]
+    pub script_logs: Vec<ScriptLogEntry>,
+    /// Entries past `config.maxScriptLogs` that were not kept.
+    #[serde(default)]
+    pub script_logs_dropped: usize,
+}
+
+/// A strategy `print` or runtime error, stamped with the execution candle's
+/// open time like snapshots.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptLogEntry {
+    pub ts: u64,
+    pub kind: ScriptLogKind,
+    pub msg: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
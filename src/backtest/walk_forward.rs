@@ -0,0 +1,340 @@
+use std::sync::Arc;
+
+use log::{info, warn};
+use rhai::Engine;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use super::backtester::build_summary;
+use super::candle_store::CandleStore;
+use super::optimizer::{OptimizeMetric, OptimizeRequest, Optimizer, ParamValue};
+use super::types::{BacktestProgress, BacktestSummary, EquityPoint};
+use crate::backend::{LocalStore, StrategyRow};
+use crate::{Error, TradeInfo, get_time_now};
+
+/// Upper bound on the number of in-sample/out-of-sample windows per run.
+pub const MAX_WALK_FORWARD_WINDOWS: usize = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkForwardRequest {
+    #[serde(default)]
+    pub run_id: Option<String>,
+    /// Parameter search applied to every in-sample window. The base config's
+    /// `startTime..endTime` is the full span that gets split into windows.
+    pub optimize: OptimizeRequest,
+    pub in_sample_ms: u64,
+    pub out_of_sample_ms: u64,
+    /// Keep every in-sample window starting at `startTime` instead of rolling it.
+    #[serde(default)]
+    pub anchored: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkForwardSplit {
+    pub in_sample_start: u64,
+    pub in_sample_end: u64,
+    pub out_of_sample_start: u64,
+    pub out_of_sample_end: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkForwardWindow {
+    pub index: usize,
+    #[serde(flatten)]
+    pub split: WalkForwardSplit,
+    pub params: Vec<ParamValue>,
+    pub in_sample_score: Option<f64>,
+    pub in_sample_summary: BacktestSummary,
+    pub out_of_sample_summary: BacktestSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkForwardResult {
+    pub run_id: String,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub metric: OptimizeMetric,
+    pub windows: Vec<WalkForwardWindow>,
+    /// Out-of-sample summary over the chained windows only.
+    pub summary: BacktestSummary,
+    pub trades: Vec<TradeInfo>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+pub struct WalkForward {
+    run_id: String,
+    request: WalkForwardRequest,
+    strategy: StrategyRow,
+    rhai_engine: Arc<Engine>,
+    candle_store: Arc<CandleStore>,
+    splits: Vec<WalkForwardSplit>,
+    cancel: CancellationToken,
+}
+
+impl WalkForward {
+    pub async fn from_request(
+        request: WalkForwardRequest,
+        rhai_engine: Arc<Engine>,
+        store: Arc<LocalStore>,
+        candle_store: Arc<CandleStore>,
+    ) -> Result<Self, Error> {
+        let cfg = &request.optimize.base.config;
+        let splits = split_windows(
+            cfg.start_time,
+            cfg.end_time,
+            request.in_sample_ms,
+            request.out_of_sample_ms,
+            request.anchored,
+        )
+        .map_err(Error::Custom)?;
+
+        let sid = cfg.strategy_id;
+        let strategy = store
+            .strategy(sid)
+            .await
+            .map_err(|e| Error::Custom(format!("local storage error: {e}")))?
+            .ok_or_else(|| Error::Custom(format!("strategy {sid} not found")))?;
+
+        let run_id = request
+            .run_id
+            .clone()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| format!("wf-{}-{}", cfg.asset, get_time_now()));
+
+        Ok(Self {
+            run_id,
+            request,
+            strategy,
+            rhai_engine,
+            candle_store,
+            splits,
+            cancel: CancellationToken::new(),
+        })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Cancelling `token` stops the window in flight and makes the run return
+    /// an error after reporting `BacktestProgress::Cancelled`.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    pub async fn run_with_progress<F>(
+        &mut self,
+        mut on_progress: F,
+    ) -> Result<WalkForwardResult, Error>
+    where
+        F: FnMut(BacktestProgress),
+    {
+        match self.run_windows(&mut on_progress).await {
+            Ok(result) => {
+                on_progress(BacktestProgress::Done);
+                Ok(result)
+            }
+            Err(_) if self.cancel.is_cancelled() => {
+                info!("walk_forward[{}] cancelled", self.run_id);
+                on_progress(BacktestProgress::Cancelled { processed: 0 });
+                Err(Error::Custom("Walk-forward cancelled".to_string()))
+            }
+            Err(err) => {
+                on_progress(BacktestProgress::Failed {
+                    message: err.to_string(),
+                });
+                Err(err)
+            }
+        }
+    }
+
+    async fn run_windows<F>(&mut self, on_progress: &mut F) -> Result<WalkForwardResult, Error>
+    where
+        F: FnMut(BacktestProgress),
+    {
+        let started_at = get_time_now();
+        let total = self.splits.len() as u64;
+        let base = &self.request.optimize.base;
+        let initial_equity = base.config.margin;
+        let resolution = base.config.resolution;
+        info!(
+            "walk_forward[{}] start strategy={} windows={} anchored={}",
+            self.run_id, self.strategy.id, total, self.request.anchored
+        );
+
+        let mut equity = initial_equity;
+        let mut windows = Vec::with_capacity(self.splits.len());
+        let mut trades = Vec::new();
+        let mut excursions = Vec::new();
+        let mut equity_curve = Vec::new();
+
+        for (index, split) in self.splits.iter().copied().enumerate() {
+            on_progress(BacktestProgress::WalkForward {
+                window: index as u64 + 1,
+                total,
+            });
+
+            let mut in_sample = self.request.optimize.clone();
+            in_sample.run_id = Some(format!("{}-is{index}", self.run_id));
+            in_sample.base.config.start_time = split.in_sample_start;
+            in_sample.base.config.end_time = split.in_sample_end;
+            in_sample.top_n = 1;
+            let mut optimizer = Optimizer::new(
+                in_sample,
+                self.strategy.clone(),
+                self.rhai_engine.clone(),
+                self.candle_store.clone(),
+            )?;
+            optimizer.set_cancel_token(self.cancel.clone());
+            let best = optimizer
+                .run_with_progress(|_| {})
+                .await?
+                .top
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    Error::Custom(format!("window {index} produced no in-sample result"))
+                })?;
+
+            // Out-of-sample runs compound: each window starts from the previous equity.
+            let mut out_of_sample = self.request.optimize.base.clone();
+            out_of_sample.run_id = Some(format!("{}-oos{index}", self.run_id));
+            out_of_sample.config.start_time = split.out_of_sample_start;
+            out_of_sample.config.end_time = split.out_of_sample_end;
+            out_of_sample.config.margin = equity;
+            let result = optimizer
+                .backtester(&best.params, out_of_sample)?
+                .run()
+                .await?;
+
+            equity = result.summary.final_equity;
+            trades.extend(result.trades);
+            excursions.extend_from_slice(&result.summary.trade_excursions);
+            equity_curve.extend(result.equity_curve);
+            windows.push(WalkForwardWindow {
+                index,
+                split,
+                params: best.params,
+                in_sample_score: best.score,
+                in_sample_summary: best.summary,
+                out_of_sample_summary: result.summary,
+            });
+
+            if equity <= 0.0 {
+                warn!(
+                    "walk_forward[{}] equity depleted after window {index}; stopping",
+                    self.run_id
+                );
+                break;
+            }
+        }
+
+        on_progress(BacktestProgress::Finalizing);
+        let mut summary = build_summary(initial_equity, equity, &equity_curve, &trades, resolution);
+        summary.set_trade_excursions(excursions);
+        info!(
+            "walk_forward[{}] done windows={} oos_net_pnl={:.4}",
+            self.run_id,
+            windows.len(),
+            summary.net_pnl
+        );
+
+        Ok(WalkForwardResult {
+            run_id: self.run_id.clone(),
+            started_at,
+            finished_at: get_time_now(),
+            metric: self.request.optimize.metric,
+            windows,
+            summary,
+            trades,
+            equity_curve,
+        })
+    }
+}
+
+/// Split `start..end` into consecutive out-of-sample windows, each preceded by
+/// its in-sample window. The final out-of-sample window is clipped to `end`.
+pub fn split_windows(
+    start: u64,
+    end: u64,
+    in_sample_ms: u64,
+    out_of_sample_ms: u64,
+    anchored: bool,
+) -> Result<Vec<WalkForwardSplit>, String> {
+    if in_sample_ms == 0 || out_of_sample_ms == 0 {
+        return Err("inSampleMs and outOfSampleMs must be greater than zero".to_string());
+    }
+    let first_oos = start
+        .checked_add(in_sample_ms)
+        .filter(|oos_start| *oos_start < end)
+        .ok_or_else(|| "time range is shorter than the in-sample window".to_string())?;
+
+    let mut splits = Vec::new();
+    let mut oos_start = first_oos;
+    while oos_start < end {
+        if splits.len() == MAX_WALK_FORWARD_WINDOWS {
+            return Err(format!(
+                "walk-forward expands beyond {MAX_WALK_FORWARD_WINDOWS} windows; widen outOfSampleMs"
+            ));
+        }
+        let oos_end = oos_start.saturating_add(out_of_sample_ms).min(end);
+        splits.push(WalkForwardSplit {
+            in_sample_start: if anchored {
+                start
+            } else {
+                oos_start - in_sample_ms
+            },
+            in_sample_end: oos_start,
+            out_of_sample_start: oos_start,
+            out_of_sample_end: oos_end,
+        });
+        oos_start = oos_end;
+    }
+    Ok(splits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_windows_chain_out_of_sample_ranges() {
+        let splits = split_windows(0, 1_000, 400, 250, false).unwrap();
+        assert_eq!(splits.len(), 3);
+        assert_eq!(
+            splits[0],
+            WalkForwardSplit {
+                in_sample_start: 0,
+                in_sample_end: 400,
+                out_of_sample_start: 400,
+                out_of_sample_end: 650,
+            }
+        );
+        assert_eq!(splits[1].in_sample_start, 250);
+        assert_eq!(splits[1].out_of_sample_start, 650);
+        // Last window is clipped to the end of the range.
+        assert_eq!(splits[2].out_of_sample_start, 900);
+        assert_eq!(splits[2].out_of_sample_end, 1_000);
+        assert!(
+            splits
+                .windows(2)
+                .all(|w| w[0].out_of_sample_end == w[1].out_of_sample_start)
+        );
+    }
+
+    #[test]
+    fn anchored_windows_keep_start_and_reject_bad_ranges() {
+        let splits = split_windows(100, 1_100, 400, 500, true).unwrap();
+        assert_eq!(splits.len(), 2);
+        assert!(splits.iter().all(|split| split.in_sample_start == 100));
+        assert_eq!(splits[1].in_sample_end, 1_000);
+
+        assert!(split_windows(0, 300, 400, 100, false).is_err());
+        assert!(split_windows(0, 1_000, 0, 100, false).is_err());
+        assert!(split_windows(0, 10_000, 10, 1, false).is_err());
+    }
+}
@@ -0,0 +1,432 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use hyperliquid_rust_bot::IndexId;
+use hyperliquid_rust_bot::backend::{StateDeclarations, compile_strategy, create_engine};
+use hyperliquid_rust_bot::backtest::report;
+use hyperliquid_rust_bot::backtest::{
+    BacktestConfig, BacktestResult, BacktestRunRequest, BacktestSummary, Backtester, CandleStore,
+    ReportFormat,
+};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+const DEFAULT_CANDLE_DIR: &str = "./data/candles";
+const DEFAULT_OUT: &str = "./backtest-result.json";
+const DEFAULT_WARMUP_CANDLES: u64 = 5_000;
+
+/// Strategy definition in the same shape the strategy editor saves.
+#[derive(Deserialize)]
+struct StrategyFile {
+    #[serde(default)]
+    name: Option<String>,
+    on_idle: String,
+    on_open: String,
+    on_busy: String,
+    #[serde(default)]
+    indicators: Vec<IndexId>,
+    #[serde(default)]
+    state_declarations: Option<StateDeclarations>,
+}
+
+/// Exit thresholds; any breach makes the process exit with status 2.
+#[derive(Default)]
+struct Thresholds {
+    max_drawdown_pct: Option<f64>,
+    min_return_pct: Option<f64>,
+    min_sharpe: Option<f64>,
+    min_profit_factor: Option<f64>,
+    min_win_rate_pct: Option<f64>,
+    min_trades: Option<usize>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::init();
+    let args = env::args().collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_help();
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args).await {
+        Ok(breaches) if breaches.is_empty() => ExitCode::SUCCESS,
+        Ok(breaches) => {
+            for breach in breaches {
+                eprintln!("threshold breached: {breach}");
+            }
+            ExitCode::from(2)
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(args: &[String]) -> Result<Vec<String>, String> {
+    let strategy_path =
+        arg_value(args, "strategy").ok_or_else(|| "--strategy is required".to_string())?;
+    let config_path =
+        arg_value(args, "config").ok_or_else(|| "--config is required".to_string())?;
+    let strategy: StrategyFile = read_file(strategy_path)?;
+    let config = read_config(config_path)?;
+    config.validate()?;
+
+    let warmup_candles = arg_parse(args, "warmup")?.unwrap_or(DEFAULT_WARMUP_CANDLES);
+    let thresholds = Thresholds {
+        max_drawdown_pct: arg_parse(args, "max-drawdown-pct")?,
+        min_return_pct: arg_parse(args, "min-return-pct")?,
+        min_sharpe: arg_parse(args, "min-sharpe")?,
+        min_profit_factor: arg_parse(args, "min-profit-factor")?,
+        min_win_rate_pct: arg_parse(args, "min-win-rate-pct")?,
+        min_trades: arg_parse(args, "min-trades")?,
+    };
+
+    let rhai_engine = Arc::new(create_engine());
+    let compiled = compile_strategy(
+        &rhai_engine,
+        &strategy.on_idle,
+        &strategy.on_open,
+        &strategy.on_busy,
+        strategy.state_declarations.as_ref(),
+    )
+    .map_err(|err| format!("strategy failed to compile: {err}"))?;
+
+    let candle_store = Arc::new(CandleStore::open(
+        arg_value(args, "dir").unwrap_or(DEFAULT_CANDLE_DIR),
+    )?);
+    let request = BacktestRunRequest {
+        run_id: arg_value(args, "run-id").map(str::to_string),
+        config,
+        warmup_candles,
+        monte_carlo: None,
+    };
+    let mut backtester = Backtester::new(
+        request,
+        rhai_engine,
+        compiled,
+        strategy.indicators,
+        candle_store,
+    );
+    let result = backtester.run().await.map_err(|err| err.to_string())?;
+
+    let out = arg_value(args, "out").unwrap_or(DEFAULT_OUT);
+    let json = serde_json::to_vec_pretty(&result)
+        .map_err(|err| format!("failed to encode result: {err}"))?;
+    fs::write(out, json).map_err(|err| format!("failed to write {out}: {err}"))?;
+    if let Some(dir) = arg_value(args, "report") {
+        write_reports(Path::new(dir), &result)?;
+    }
+
+    print_summary(strategy.name.as_deref().unwrap_or(strategy_path), &result);
+    println!("\nresult written to {out}");
+    Ok(thresholds.breaches(&result.summary))
+}
+
+impl Thresholds {
+    fn breaches(&self, summary: &BacktestSummary) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Some(max) = self.max_drawdown_pct
+            && summary.max_drawdown_pct > max
+        {
+            out.push(format!(
+                "max drawdown {:.2}% > {max}%",
+                summary.max_drawdown_pct
+            ));
+        }
+        if let Some(min) = self.min_return_pct
+            && summary.return_pct < min
+        {
+            out.push(format!("return {:.2}% < {min}%", summary.return_pct));
+        }
+        if let Some(min) = self.min_sharpe
+            && summary.sharpe_ratio.is_none_or(|sharpe| sharpe < min)
+        {
+            out.push(format!("sharpe {} < {min}", fmt_opt(summary.sharpe_ratio)));
+        }
+        if let Some(min) = self.min_profit_factor
+            && summary.profit_factor.is_none_or(|pf| pf < min)
+        {
+            out.push(format!(
+                "profit factor {} < {min}",
+                fmt_opt(summary.profit_factor)
+            ));
+        }
+        if let Some(min) = self.min_win_rate_pct
+            && summary.win_rate_pct < min
+        {
+            out.push(format!("win rate {:.2}% < {min}%", summary.win_rate_pct));
+        }
+        if let Some(min) = self.min_trades
+            && summary.total_trades < min
+        {
+            out.push(format!("trades {} < {min}", summary.total_trades));
+        }
+        out
+    }
+}
+
+fn write_reports(dir: &Path, result: &BacktestResult) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|err| format!("failed to create {}: {err}", dir.display()))?;
+    for format in ReportFormat::ALL {
+        let path = dir.join(format!("{}-{}", result.run_id, format.file_suffix()));
+        fs::write(&path, report::render(result, format))
+            .map_err(|err| format!("failed to write {}: {err}", path.display()))?;
+        println!("report written to {}", path.display());
+    }
+    Ok(())
+}
+
+fn print_summary(name: &str, result: &BacktestResult) {
+    let cfg = &result.config;
+    let s = &result.summary;
+    println!(
+        "{name}: {} {} {}..{} ({} candles)",
+        cfg.asset,
+        cfg.resolution.as_str(),
+        cfg.start_time,
+        cfg.end_time,
+        result.candles_processed
+    );
+    let rows = [
+        ("initial equity", format!("{:.2}", s.initial_equity)),
+        ("final equity", format!("{:.2}", s.final_equity)),
+        ("net pnl", format!("{:.2}", s.net_pnl)),
+        ("return", format!("{:.2}%", s.return_pct)),
+        ("max drawdown", format!("{:.2}%", s.max_drawdown_pct)),
+        ("trades", s.total_trades.to_string()),
+        ("win rate", format!("{:.2}%", s.win_rate_pct)),
+        ("profit factor", fmt_opt(s.profit_factor)),
+        ("expectancy", format!("{:.4}", s.expectancy)),
+        ("sharpe", fmt_opt(s.sharpe_ratio)),
+        ("sortino", fmt_opt(s.sortino_ratio)),
+        ("calmar", fmt_opt(s.calmar_ratio)),
+        ("time in market", format!("{:.2}%", s.time_in_market_pct)),
+        (
+            "buy & hold",
+            result
+                .benchmark
+                .as_ref()
+                .map(|b| format!("{:.2}%", b.return_pct))
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+    ];
+    for (label, value) in rows {
+        println!("  {label:<16}{value:>14}");
+    }
+}
+
+fn fmt_opt(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{v:.3}"))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn read_file<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let raw = fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    parse_file(path, &raw)
+}
+
+/// Parse `raw` as TOML or JSON depending on `path`'s extension.
+fn parse_file<T: DeserializeOwned>(path: &str, raw: &str) -> Result<T, String> {
+    let is_toml = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    if is_toml {
+        toml::from_str(raw).map_err(|err| format!("invalid TOML in {path}: {err}"))
+    } else {
+        serde_json::from_str(raw).map_err(|err| format!("invalid JSON in {path}: {err}"))
+    }
+}
+
+fn read_config(path: &str) -> Result<BacktestConfig, String> {
+    parse_config(read_file(path)?)
+}
+
+/// `strategyId` only keys the server's strategy cache, so files may omit it.
+fn parse_config(mut value: serde_json::Value) -> Result<BacktestConfig, String> {
+    if let Some(obj) = value.as_object_mut() {
+        obj.entry("strategyId")
+            .or_insert_with(|| serde_json::Value::String(uuid::Uuid::nil().to_string()));
+    }
+    serde_json::from_value(value).map_err(|err| format!("invalid backtest config: {err}"))
+}
+
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let eq_prefix = format!("--{name}=");
+    let flag = format!("--{name}");
+    args.iter().enumerate().find_map(|(index, arg)| {
+        arg.strip_prefix(&eq_prefix).or_else(|| {
+            (arg == &flag)
+                .then(|| args.get(index + 1))
+                .flatten()
+                .map(String::as_str)
+        })
+    })
+}
+
+fn arg_parse<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    arg_value(args, name)
+        .map(|raw| {
+            raw.parse::<T>()
+                .map_err(|_| format!("invalid --{name}: {raw}"))
+        })
+        .transpose()
+}
+
+fn print_help() {
+    println!(
+        "Run a backtest without the server\n\
+         \n\
+         Options:\n\
+         --strategy=<path>          .json or .toml with on_idle/on_open/on_busy, indicators, state_declarations\n\
+         --config=<path>            .json or .toml BacktestConfig (camelCase, strategyId optional)\n\
+         --warmup=<n>               warmup candles per series, default 5000\n\
+         --dir=<path>               candle store directory, default ./data/candles\n\
+         --out=<path>               result JSON path, default ./backtest-result.json\n\
+         --run-id=<id>              run id recorded in the result\n\
+         --report=<dir>             also write HTML, trades/equity CSV and JSON reports to <dir>\n\
+         \n\
+         Thresholds (exit status 2 when breached):\n\
+         --max-drawdown-pct=<x>     --min-return-pct=<x>     --min-sharpe=<x>\n\
+         --min-profit-factor=<x>    --min-win-rate-pct=<x>   --min-trades=<n>"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn summary(return_pct: f64, sharpe_ratio: Option<f64>, total_trades: usize) -> BacktestSummary {
+        BacktestSummary {
+            initial_equity: 1_000.0,
+            final_equity: 1_000.0 * (1.0 + return_pct / 100.0),
+            net_pnl: 10.0 * return_pct,
+            return_pct,
+            max_drawdown_abs: 100.0,
+            max_drawdown_pct: 10.0,
+            total_trades,
+            wins: total_trades,
+            losses: 0,
+            win_rate_pct: 100.0,
+            gross_profit: 10.0 * return_pct,
+            gross_loss: 0.0,
+            avg_win: 0.0,
+            avg_loss: 0.0,
+            profit_factor: None,
+            expectancy: 0.0,
+            sharpe_ratio,
+            monte_carlo: None,
+            slippage_cost: 0.0,
+            intrabar_resolved_bars: 0,
+            sortino_ratio: None,
+            calmar_ratio: None,
+            time_in_market_pct: 0.0,
+            avg_trade_duration_ms: 0,
+            median_trade_duration_ms: 0,
+            longest_win_streak: 0,
+            longest_loss_streak: 0,
+            avg_mae_pct: 0.0,
+            avg_mfe_pct: 0.0,
+            trade_excursions: Vec::new(),
+            monthly_returns: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn thresholds_report_each_breach() {
+        let thresholds = Thresholds {
+            max_drawdown_pct: Some(5.0),
+            min_return_pct: Some(2.0),
+            min_sharpe: Some(1.0),
+            min_profit_factor: Some(1.5),
+            min_trades: Some(3),
+            ..Thresholds::default()
+        };
+        // The 10% drawdown and the missing sharpe and profit factor breach;
+        // return and trade count pass.
+        let breaches = thresholds.breaches(&summary(5.0, None, 3));
+        assert_eq!(breaches.len(), 3);
+        assert!(breaches[0].starts_with("max drawdown"));
+        assert!(breaches[1].starts_with("sharpe -"));
+        assert!(breaches[2].starts_with("profit factor -"));
+
+        assert!(
+            Thresholds::default()
+                .breaches(&summary(-50.0, None, 0))
+                .is_empty()
+        );
+        let strict = Thresholds {
+            min_return_pct: Some(2.0),
+            min_sharpe: Some(1.0),
+            min_trades: Some(3),
+            ..Thresholds::default()
+        };
+        assert_eq!(strict.breaches(&summary(1.0, Some(1.2), 2)).len(), 2);
+    }
+
+    #[test]
+    fn args_accept_both_flag_forms() {
+        let args = args(&[
+            "backtest",
+            "--config=cfg.toml",
+            "--out",
+            "res.json",
+            "--warmup",
+        ]);
+        assert_eq!(arg_value(&args, "config"), Some("cfg.toml"));
+        assert_eq!(arg_value(&args, "out"), Some("res.json"));
+        assert_eq!(arg_value(&args, "warmup"), None);
+        assert_eq!(arg_value(&args, "strategy"), None);
+    }
+
+    #[test]
+    fn arg_parse_rejects_malformed_values() {
+        let args = args(&["--min-trades=12", "--min-sharpe", "high"]);
+        assert_eq!(arg_parse::<usize>(&args, "min-trades"), Ok(Some(12)));
+        assert_eq!(arg_parse::<f64>(&args, "max-drawdown-pct"), Ok(None));
+        assert_eq!(
+            arg_parse::<f64>(&args, "min-sharpe"),
+            Err("invalid --min-sharpe: high".to_string())
+        );
+    }
+
+    #[test]
+    fn toml_config_gets_a_nil_strategy_id() {
+        let raw = r#"
+            asset = "BTC"
+            resolution = "hour1"
+            margin = 1000.0
+            lev = 2
+            takerFeeBps = 5
+            makerFeeBps = 2
+            fundingRateBpsPer8h = 0.0
+            startTime = 0
+            endTime = 3600000
+            snapshotIntervalCandles = 0
+
+            [source]
+            exchange = "hyperliquid"
+            market = "futures"
+            quoteAsset = "USDC"
+        "#;
+        let config = parse_config(parse_file("config.toml", raw).unwrap()).unwrap();
+        assert_eq!(config.strategy_id, uuid::Uuid::nil());
+        assert_eq!(config.lev, 2);
+        assert!(config.validate().is_ok());
+
+        let mut explicit = serde_json::to_value(&config).unwrap();
+        let id = uuid::Uuid::from_u128(1);
+        explicit["strategyId"] = serde_json::json!(id);
+        assert_eq!(parse_config(explicit).unwrap().strategy_id, id);
+        assert!(parse_file::<serde_json::Value>("config.toml", "asset = ").is_err());
+    }
+}
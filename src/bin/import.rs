@@ -0,0 +1,86 @@
+use std::env;
+use std::path::PathBuf;
+
+use hyperliquid_rust_bot::TimeFrame;
+use hyperliquid_rust_bot::backtest::{
+    CandleStore, DataSource, Exchange, MarketType, import_candles,
+};
+
+const DEFAULT_CANDLE_DIR: &str = "./data/candles";
+
+#[tokio::main]
+async fn main() -> Result<(), String> {
+    env_logger::init();
+    let args = env::args().collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_help();
+        return Ok(());
+    }
+
+    let file = arg_value(&args, "file")
+        .map(PathBuf::from)
+        .ok_or_else(|| "--file is required".to_string())?;
+    let asset = arg_value(&args, "asset").ok_or_else(|| "--asset is required".to_string())?;
+    let market = match arg_value(&args, "market").unwrap_or("futures") {
+        "spot" => MarketType::Spot,
+        "futures" => MarketType::Futures,
+        other => return Err(format!("invalid --market: {other}")),
+    };
+    let quote = arg_value(&args, "quote").unwrap_or(DataSource::DEFAULT_QUOTE);
+    let timeframes = arg_value(&args, "tf")
+        .map(|raw| {
+            raw.split(',')
+                .map(|tf| tf.trim().parse::<TimeFrame>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?
+        .unwrap_or_default();
+    let candle_dir = arg_value(&args, "dir").unwrap_or(DEFAULT_CANDLE_DIR);
+
+    let store = CandleStore::open(candle_dir)?;
+    let source = DataSource::with_quote(Exchange::Local, market, quote);
+    let summary = import_candles(&store, &source, asset, &file, &timeframes)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    println!(
+        "imported {} rows ({}ms candles, {}..{}) into {candle_dir}",
+        summary.rows_read,
+        summary.source_interval_ms,
+        summary.first_open_time,
+        summary.last_open_time
+    );
+    for (tf, count) in summary.written {
+        println!("  {:>4}  {count} candles", tf.as_str());
+    }
+    Ok(())
+}
+
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let eq_prefix = format!("--{name}=");
+    let flag = format!("--{name}");
+    args.iter().enumerate().find_map(|(index, arg)| {
+        arg.strip_prefix(&eq_prefix).or_else(|| {
+            (arg == &flag)
+                .then(|| args.get(index + 1))
+                .flatten()
+                .map(String::as_str)
+        })
+    })
+}
+
+fn print_help() {
+    println!(
+        "Import local candle files for offline backtests\n\
+         \n\
+         Options:\n\
+         --file=<path>        .csv or .parquet with open_time/open/high/low/close[/volume] columns\n\
+         --asset=<name>       asset the candles belong to, e.g. BTC or xyz:XYZ100\n\
+         --market=<type>      spot or futures, default futures\n\
+         --quote=<asset>      quote asset, default USDT\n\
+         --tf=<list>          comma separated timeframes, default every multiple of the file interval\n\
+         --dir=<path>         candle store directory, default ./data/candles\n\
+         \n\
+         Run backtests against the imported data with source {{\"exchange\": \"local\"}}."
+    );
+}
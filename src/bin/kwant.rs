@@ -97,7 +97,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         rhai_engine,
         strategy_cache: Arc::new(RwLock::new(HashMap::new())),
         candle_store,
-        active_backtests: Arc::new(RwLock::new(HashSet::new())),
+        active_backtests: Arc::new(RwLock::new(HashMap::new())),
+        step_sessions: Arc::default(),
         bot_startups: Arc::new(RwLock::new(HashSet::new())),
         jwt_secret,
         encryption_key,
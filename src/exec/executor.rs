@@ -188,16 +188,99 @@ impl Executor {
     }
 
     async fn cancel_force_target_resting(&mut self, order: &EngineOrder) -> Result<(), Error> {
+        self.cancel_resting_with_action(order.action).await
+    }
+
+    async fn cancel_resting_with_action(&mut self, action: PositionOp) -> Result<(), Error> {
         let oids = self
             .resting_orders
             .iter()
             .filter_map(|(&oid, resting)| {
-                (resting.tpsl.is_none() && resting.intent == order.action).then_some(oid)
+                (resting.tpsl.is_none() && resting.intent == action).then_some(oid)
             })
             .collect();
         self.cancel_resting_oids(oids).await
     }
 
+    /// Re-place resting TP/SL that no longer cover a position grown by an add.
+    async fn resize_resting_triggers(&mut self) {
+        let Some(size) = self.with_position(|pos| pos.map(|p| p.size)).await else {
+            return;
+        };
+        let sz_decimals = self.asset.sz_decimals;
+        let stale: Vec<(u64, TriggerKind, f64)> = self
+            .resting_orders
+            .iter()
+            .filter_map(|(&oid, resting)| {
+                let kind = resting.tpsl?;
+                let px = resting.limit_px?;
+                (roundf!(size - resting.sz, sz_decimals) > 0.0).then_some((oid, kind, px))
+            })
+            .collect();
+
+        for (oid, kind, px) in stale {
+            if !self
+                .submit_order(EngineOrder::new_trigger_close(kind, size, px))
+                .await
+            {
+                warn!("failed to place resized {kind} trigger; keeping the old one");
+                continue;
+            }
+            if let Err(e) = self.cancel_resting_oids(vec![oid]).await {
+                warn!("failed to cancel {kind} trigger after resize: {}", e);
+            }
+        }
+    }
+
+    /// Swap the resting TP/SL for `replace`. The new trigger is placed before
+    /// the old ones are canceled so the position is never left uncovered, and
+    /// a replace with the trigger that is already resting is skipped.
+    async fn replace_triggers(&mut self, replace: TriggerReplace) {
+        let stale: Vec<u64> = self
+            .resting_orders
+            .iter()
+            .filter_map(|(&oid, resting)| replace.cancels(resting.tpsl).then_some(oid))
+            .collect();
+
+        if let Some(order) = replace.order {
+            if self.trigger_already_resting(&stale, order).await {
+                return;
+            }
+            if !self.submit_order(order).await {
+                warn!("trigger replace could not place the new trigger; keeping the resting ones");
+                return;
+            }
+        }
+
+        if let Err(e) = self.cancel_resting_oids(stale).await {
+            warn!(
+                "trigger replace failed while canceling replaced triggers: {}",
+                e
+            );
+        }
+    }
+
+    /// Whether `stale` is a single resting trigger that already is `order`.
+    async fn trigger_already_resting(&self, stale: &[u64], order: EngineOrder) -> bool {
+        let ([oid], Some(limit)) = (stale, order.limit) else {
+            return false;
+        };
+        let Some(resting) = self.resting_orders.get(oid).copied() else {
+            return false;
+        };
+        let Some(size) = self
+            .open_position
+            .lock()
+            .await
+            .map(|p| order.size.min(p.size))
+        else {
+            return false;
+        };
+        resting.tpsl == order.is_tpsl()
+            && resting.limit_px == Some(roundf!(limit.limit_px, self.decimals.px))
+            && roundf!(size - resting.sz, self.decimals.sz) <= 0.0
+    }
+
     fn into_hl_order(
         asset: &str,
         sz: f64,
@@ -411,7 +494,8 @@ impl Executor {
         }
     }
 
-    async fn submit_order(&mut self, order: EngineOrder) {
+    /// Send `order` to the exchange. Returns whether it was accepted.
+    async fn submit_order(&mut self, order: EngineOrder) -> bool {
         let order_params: Option<(Side, f64)> = match order.action {
             PositionOp::OpenLong => Some((Side::Long, order.size)),
             PositionOp::OpenShort => Some((Side::Short, order.size)),
@@ -438,6 +522,7 @@ impl Executor {
                 Ok(order_response) => {
                     self.resting_orders
                         .insert(order_response.oid, order_response);
+                    return true;
                 }
                 Err(Error::AuthError(msg)) => {
                     warn!("[executor] auth error: {msg}");
@@ -459,6 +544,7 @@ impl Executor {
                 Err(e) => warn!("{}", e),
             }
         }
+        false
     }
 
     async fn force_taker(&mut self, order: EngineOrder) {
@@ -500,6 +586,17 @@ impl Executor {
                     }
                     self.force_taker(order).await;
                 }
+                ReplaceTriggers(replace) => {
+                    if self.is_paused {
+                        continue;
+                    }
+                    self.replace_triggers(replace).await;
+                }
+                CancelResting(action) => {
+                    if let Err(e) = self.cancel_resting_with_action(action).await {
+                        warn!("failed to cancel resting {:?} orders: {}", action, e);
+                    }
+                }
                 Control(control) => match control {
                     ExecControl::Kill => {
                         self.kill(false).await;
@@ -547,6 +644,9 @@ impl Executor {
                             }
 
                             let (trade_info, is_manual) = self.apply_fill(fill).await;
+                            if is_open && is_known {
+                                self.resize_resting_triggers().await;
+                            }
 
                             if let Some(trade_info) = trade_info {
                                 if !is_open {
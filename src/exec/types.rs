@@ -16,6 +16,41 @@ pub enum ExecCommand {
     Control(ExecControl),
     Event(ExecEvent),
     ReloadWallet(Arc<ExchangeClient>),
+    ReplaceTriggers(TriggerReplace),
+    /// Cancel resting non-trigger orders placed with this action.
+    CancelResting(PositionOp),
+}
+
+/// Cancel-and-replace of the TP/SL resting against the open position.
+#[derive(Copy, Clone, Debug)]
+pub struct TriggerReplace {
+    /// Trigger kind to cancel; `None` cancels both TP and SL.
+    pub cancel: Option<TriggerKind>,
+    pub order: Option<EngineOrder>,
+}
+
+impl TriggerReplace {
+    pub fn new(kind: TriggerKind, size: f64, trigger_px: f64) -> Self {
+        TriggerReplace {
+            cancel: Some(kind),
+            order: Some(EngineOrder::new_trigger_close(kind, size, trigger_px)),
+        }
+    }
+
+    pub fn clear() -> Self {
+        TriggerReplace {
+            cancel: None,
+            order: None,
+        }
+    }
+
+    pub fn cancels(&self, trigger: Option<TriggerKind>) -> bool {
+        match (self.cancel, trigger) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some(kind), Some(trigger)) => kind == trigger,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -573,4 +608,18 @@ mod tests {
         assert!(parse_finite_fill_value("fill", "inf").is_err());
         assert!(parse_finite_fill_value("fill", "-inf").is_err());
     }
+
+    #[test]
+    fn trigger_replace_cancels_only_matching_triggers() {
+        let set_sl = TriggerReplace::new(TriggerKind::Sl, 1.0, 95.0);
+        assert!(set_sl.cancels(Some(TriggerKind::Sl)));
+        assert!(!set_sl.cancels(Some(TriggerKind::Tp)));
+        assert!(!set_sl.cancels(None));
+
+        let clear = TriggerReplace::clear();
+        assert!(clear.cancels(Some(TriggerKind::Tp)));
+        assert!(clear.cancels(Some(TriggerKind::Sl)));
+        assert!(!clear.cancels(None));
+        assert!(clear.order.is_none());
+    }
 }
@@ -1,4 +1,7 @@
-use crate::backtest::{BacktestProgress, BacktestResult, BacktestRunRequest};
+use crate::backtest::{
+    BacktestProgress, BacktestResult, BacktestRunRequest, OptimizeResult, PortfolioResult,
+    WalkForwardResult,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Deserialize)]
@@ -19,6 +22,30 @@ pub struct BacktestRunResponse {
     pub progress: Vec<BacktestProgress>,
 }
 
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OptimizeRunResponse {
+    pub run_id: String,
+    pub result: OptimizeResult,
+    pub progress: Vec<BacktestProgress>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalkForwardRunResponse {
+    pub run_id: String,
+    pub result: WalkForwardResult,
+    pub progress: Vec<BacktestProgress>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioRunResponse {
+    pub run_id: String,
+    pub result: PortfolioResult,
+    pub progress: Vec<BacktestProgress>,
+}
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BacktestRunError {
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::backtest::{BacktestProgress, BacktestResult};
+use crate::backtest::{BacktestProgress, BacktestResult, StepCommand, StepEvent};
 use crate::{
     AssetMargin, EngineView, IndexId, MarginAllocation, MarketState, OpenPositionLocal, Price,
     TradeInfo, Value,
@@ -130,6 +130,7 @@ pub enum UpdateFrontend {
     UserError(String),
     BacktestProgress(BacktestProgressUpdate),
     BacktestResult(Box<BacktestResultUpdate>),
+    BacktestStep(Box<StepEvent>),
     LoadSession(UserSession),
     Status(BackendStatus),
     StrategyLog(ScriptLog),
@@ -137,6 +138,13 @@ pub enum UpdateFrontend {
     NeedsBuilderApproval(bool),
 }
 
+/// Messages the frontend sends over the websocket.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClientMessage {
+    BacktestStep(StepCommand),
+}
+
 #[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ScriptLog {
@@ -8,15 +8,15 @@ use serde::{Deserialize, Serialize};
 
 use kwant::indicators::Price;
 
-use crate::backend::scripting::CompiledStrategy;
+use crate::backend::scripting::{CompiledStrategy, ScriptCandles, with_script_candles};
 use crate::broadcast::{PriceAsset, PriceData};
 use crate::metrics;
 use crate::strategy::{Strat, StratContext, Strategy, replace_self_with_asset};
 use crate::trade_setup::TimeFrame;
 use crate::{
     BusyType, EngineOrder, ExecCommand, ExecControl, IndicatorData, Intent, LiqSide,
-    LiveTimeoutInfo, MIN_ORDER_VALUE, MarketCommand, OnTimeout, PositionOp, Side, TimeoutInfo,
-    TriggerKind, Triggers,
+    LiveTimeoutInfo, MAX_POSITION_ADDS, MIN_ORDER_VALUE, MarketCommand, OnTimeout, PositionOp,
+    Side, TimeoutInfo, Trail, TriggerKind, TriggerReplace, Triggers, Value,
 };
 
 use flume::{Sender, TrySendError as FlumeTrySendError, bounded};
@@ -33,13 +33,17 @@ type TrackersMap = HashMap<TrackerKey, Box<Tracker>, BuildHasherDefault<FxHasher
 const MARKET_COMMAND_SEND_TIMEOUT_SECS: u64 = 5;
 const LIVE_STRATEGY_INTERVAL_MS: u64 = 60_000;
 
-fn insert_indicators(trackers: &mut TrackersMap, indicators: impl IntoIterator<Item = IndexId>) {
+fn insert_indicators(
+    trackers: &mut TrackersMap,
+    indicators: impl IntoIterator<Item = IndexId>,
+    history_len: usize,
+) {
     for (asset, kind, tf) in indicators {
         let key = (Arc::clone(&asset), tf);
         if let Some(tracker) = trackers.get_mut(&key) {
             tracker.add_indicator(kind);
         } else {
-            let mut new_tracker = Tracker::new(asset, tf);
+            let mut new_tracker = Tracker::new(asset, tf, history_len);
             new_tracker.add_indicator(kind);
             trackers.insert(key, Box::new(new_tracker));
         }
@@ -60,6 +64,14 @@ pub struct SignalEngine {
     pending_strategy_candle: Option<Price>,
     log_tx: Option<tokioSender<String>>,
     paused: bool,
+    /// Intent returned by the strategy on the latest backtest tick.
+    last_intent: Option<Intent>,
+    /// Indicator closes kept for the strategy's `prev`/`history` lookups.
+    history_len: usize,
+    trailing: Option<TrailingStop>,
+    pending_add: Option<PendingAdd>,
+    /// Open time of the position added to, and how many adds it has had.
+    position_adds: (u64, usize),
 }
 
 impl SignalEngine {
@@ -78,6 +90,7 @@ impl SignalEngine {
     ) -> Self {
         replace_self_with_asset(asset_name.as_ref(), &mut strat_indicators);
 
+        let history_len = compiled.history_len;
         let strategy = Strategy::new(
             rhai_engine.clone(),
             compiled,
@@ -94,7 +107,7 @@ impl SignalEngine {
         all_indicators.extend(strat_indicators);
 
         let mut trackers: TrackersMap = HashMap::default();
-        insert_indicators(&mut trackers, all_indicators);
+        insert_indicators(&mut trackers, all_indicators, history_len);
 
         SignalEngine {
             asset: asset_name,
@@ -110,6 +123,11 @@ impl SignalEngine {
             pending_orders: None,
             pending_strategy_candle: None,
             paused: false,
+            last_intent: None,
+            history_len,
+            trailing: None,
+            pending_add: None,
+            position_adds: (0, 0),
         }
     }
 
@@ -124,9 +142,24 @@ impl SignalEngine {
         self.reset();
         self.state = EngineState::Idle;
         self.pending_orders = None;
+        self.last_intent = None;
+        self.trailing = None;
+        self.pending_add = None;
+        self.position_adds = (0, 0);
         self.strategy.reset_scope();
     }
 
+    /// Intent the strategy emitted on the latest `tick_backtest`, whether or
+    /// not the engine acted on it.
+    pub fn last_intent(&self) -> Option<Intent> {
+        self.last_intent
+    }
+
+    /// Copy of the script's `state` map.
+    pub fn strategy_state(&self) -> rhai::Map {
+        self.strategy.state()
+    }
+
     pub fn set_trading_enabled(&mut self, enabled: bool) {
         self.paused = !enabled;
         if !enabled {
@@ -140,7 +173,7 @@ impl SignalEngine {
         if let Some(tracker) = self.trackers.get_mut(&key) {
             tracker.add_indicator(id.1);
         } else {
-            let mut new_tracker = Tracker::new(Arc::clone(&id.0), id.2);
+            let mut new_tracker = Tracker::new(Arc::clone(&id.0), id.2, self.history_len);
             new_tracker.add_indicator(id.1);
             self.trackers.insert(key, Box::new(new_tracker));
         }
@@ -180,6 +213,24 @@ impl SignalEngine {
         values
     }
 
+    pub fn get_active_history(&self) -> HistoryMap {
+        let mut history: HistoryMap = HashMap::default();
+        if self.history_len == 0 {
+            return history;
+        }
+        for tracker in self.trackers.values() {
+            history.extend(tracker.get_active_history());
+        }
+        history
+    }
+
+    fn set_history_len(&mut self, history_len: usize) {
+        self.history_len = history_len;
+        for tracker in self.trackers.values_mut() {
+            tracker.set_history_len(history_len);
+        }
+    }
+
     pub fn get_indicators_data(&self) -> Vec<IndicatorData> {
         let mut values = Vec::new();
         for tracker in self.trackers.values() {
@@ -224,6 +275,10 @@ impl SignalEngine {
                 if matches!(self.state, EngineState::Closing(_)) && previous_pos == Some(open_pos) {
                     return;
                 }
+                if matches!(self.state, EngineState::Opening(_)) && !self.add_filled(open_pos) {
+                    return;
+                }
+                self.cancel_add_remainder();
                 self.state = EngineState::Open(open_pos);
                 self.queue_pending_tpsl(open_pos);
             }
@@ -250,19 +305,44 @@ impl SignalEngine {
 
     fn strat_tick(&mut self, price: Price, values: ValuesMap) -> Option<Intent> {
         use EngineState as E;
+        let history = self.get_active_history();
         let ctx = StratContext {
             free_margin: self.exec_params.free_margin(),
             lev: self.exec_params.lev,
             last_price: price,
             indicators: &values,
+            history: &history,
         };
 
-        match self.state {
+        let candles = self.take_script_candles();
+        let (intent, candles) = with_script_candles(candles, || match self.state {
             E::Idle => self.strategy.on_idle(ctx, None),
             E::Armed(expiry) => self.strategy.on_idle(ctx, Some(expiry)),
             E::Opening(timeout) => self.strategy.on_busy(ctx, BusyType::Opening(timeout)),
             E::Closing(timeout) => self.strategy.on_busy(ctx, BusyType::Closing(timeout)),
             E::Open(open_pos) => self.strategy.on_open(ctx, &open_pos),
+        });
+        self.restore_script_candles(candles);
+        intent
+    }
+
+    /// Move every tracker's candles out for `candles()` during a strategy tick.
+    fn take_script_candles(&mut self) -> ScriptCandles {
+        ScriptCandles {
+            asset: Arc::clone(&self.asset),
+            series: self
+                .trackers
+                .iter_mut()
+                .map(|(key, tracker)| (key.clone(), tracker.take_candles()))
+                .collect(),
+        }
+    }
+
+    fn restore_script_candles(&mut self, candles: ScriptCandles) {
+        for (key, history) in candles.series {
+            if let Some(tracker) = self.trackers.get_mut(&key) {
+                tracker.restore_candles(history);
+            }
         }
     }
 
@@ -361,10 +441,11 @@ impl SignalEngine {
                     }
                 };
 
-                let tpsl = if order.tp.is_some() || order.sl.is_some() {
+                let tpsl = if order.tp.is_some() || order.sl.is_some() || order.trail.is_some() {
                     Some(Triggers {
                         tp: order.tp,
                         sl: order.sl,
+                        trail: order.trail,
                     })
                 } else {
                     None
@@ -373,6 +454,29 @@ impl SignalEngine {
                 Some(PendingOrder::Open(PendingOpen { open, tpsl }))
             }
 
+            I::Add(add) => {
+                let side = self.exec_params.open_pos?.side;
+                let open = match &add.liq_side {
+                    LiqSide::Taker => {
+                        let size = add.size.get_size(
+                            self.exec_params.lev as f64,
+                            self.exec_params.free_margin(),
+                            last_price.close,
+                        );
+                        EngineOrder::new_market_open(side, size)
+                    }
+                    LiqSide::Maker(limit) => {
+                        let size = add.size.get_size(
+                            self.exec_params.lev as f64,
+                            self.exec_params.free_margin(),
+                            limit.limit_px,
+                        );
+                        EngineOrder::new_limit_open(side, size, limit.limit_px, None)
+                    }
+                };
+                Some(PendingOrder::Open(PendingOpen { open, tpsl: None }))
+            }
+
             I::Reduce(reduce) => {
                 let close = match &reduce.liq_side {
                     LiqSide::Taker => {
@@ -445,7 +549,7 @@ impl SignalEngine {
                 if let Some(tpsl) = order
                     .tpsl
                     .as_ref()
-                    .filter(|tpsl| tpsl.tp.is_some() || tpsl.sl.is_some())
+                    .filter(|tpsl| tpsl.tp.is_some() || tpsl.sl.is_some() || tpsl.trail.is_some())
                 {
                     validate_tpsl(tpsl)?;
                 }
@@ -509,6 +613,89 @@ impl SignalEngine {
         Ok(())
     }
 
+    fn validate_add(&self) -> Result<(), String> {
+        let EngineState::Open(open_pos) = self.state else {
+            return Err("INVALID STATE: Add with no open position".into());
+        };
+        if self.adds_made(&open_pos) >= MAX_POSITION_ADDS {
+            return Err(format!(
+                "EXCEEDED MAX_ADDS: position was already added to {MAX_POSITION_ADDS} times"
+            ));
+        }
+        Ok(())
+    }
+
+    fn adds_made(&self, open_pos: &OpenPosInfo) -> usize {
+        let (open_time, adds) = self.position_adds;
+        if open_time == open_pos.open_time {
+            adds
+        } else {
+            0
+        }
+    }
+
+    fn record_add(&mut self, action: PositionOp, resting: bool) {
+        let Some(open_pos) = self.exec_params.open_pos else {
+            return;
+        };
+        self.position_adds = (open_pos.open_time, self.adds_made(&open_pos) + 1);
+        self.pending_add = Some(PendingAdd {
+            action,
+            resting,
+            size_before: open_pos.size,
+        });
+    }
+
+    /// Clear the add once the position grew, returning the side of a limit
+    /// add whose unfilled remainder may still be resting.
+    fn take_filled_add(&mut self) -> Option<PositionOp> {
+        self.pending_add
+            .take()
+            .filter(|add| add.resting)
+            .map(|add| add.action)
+    }
+
+    fn cancel_add_remainder(&mut self) {
+        if let Some(action) = self.take_filled_add() {
+            let _ = self.queue_exec_command(
+                "filled add remainder cancel",
+                ExecCommand::CancelResting(action),
+            );
+        }
+    }
+
+    /// Whether the position has grown since the pending add was sent, if any.
+    fn add_filled(&self, open_pos: OpenPosInfo) -> bool {
+        self.pending_add
+            .is_none_or(|add| open_pos.size > add.size_before)
+    }
+
+    fn translate_trigger_update(
+        &mut self,
+        intent: &Intent,
+        last_price: f64,
+    ) -> Result<TriggerReplace, String> {
+        let EngineState::Open(open_pos) = self.state else {
+            return Err("INVALID STATE: trigger update with no open position".into());
+        };
+        let (trigger, trigger_px) = match *intent {
+            Intent::SetSl(px) => (TriggerKind::Sl, px),
+            Intent::SetTp(px) => (TriggerKind::Tp, px),
+            Intent::ClearTriggers => {
+                self.trailing = None;
+                return Ok(TriggerReplace::clear());
+            }
+            _ => return Err(format!("{intent:?} is not a trigger update")),
+        };
+        validate_trigger_price(trigger, trigger_px)?;
+        validate_trigger_side(open_pos.side, trigger, trigger_px, last_price)?;
+        if trigger == TriggerKind::Sl {
+            self.trailing = None;
+        }
+
+        Ok(TriggerReplace::new(trigger, open_pos.size, trigger_px))
+    }
+
     pub fn force_as_taker_order(&self, intent: &Intent, last_price: &Price) -> Option<EngineOrder> {
         match intent {
             Intent::Open(order) => {
@@ -603,8 +790,15 @@ impl SignalEngine {
 
     fn queue_pending_tpsl(&mut self, open_pos: OpenPosInfo) {
         if let Some(pending) = self.pending_orders.take()
-            && let Some(Triggers { tp, sl }) = pending.tpsl
+            && let Some(Triggers { tp, sl, trail }) = pending.tpsl
         {
+            if let Some(replace) = self.start_trailing(open_pos, trail) {
+                let _ = self.queue_exec_command(
+                    "trailing stop order",
+                    ExecCommand::ReplaceTriggers(replace),
+                );
+            }
+
             let size = pending.open.size;
             let side = open_pos.side;
             let ref_px = open_pos.entry_px;
@@ -639,6 +833,72 @@ impl SignalEngine {
         }
     }
 
+    /// Replace any trailing stop of a previous position with the one the
+    /// fresh entry asked for, and place its first stop from the entry price.
+    fn start_trailing(
+        &mut self,
+        open_pos: OpenPosInfo,
+        trail: Option<Trail>,
+    ) -> Option<TriggerReplace> {
+        self.trailing = trail.map(|t| TrailingStop::new(t, &open_pos));
+        let replace = self.ratchet_trailing(open_pos.entry_px, open_pos.entry_px);
+        if self.trailing.is_some() && replace.is_none() {
+            log::warn!(
+                "[engine:{}] trailing stop has no distance yet; waiting for ATR",
+                self.asset
+            );
+        }
+        replace
+    }
+
+    /// Start the trailing stop of a backtest entry as it fills and return its
+    /// first stop, which the backtester places next to the fixed TP/SL.
+    pub fn start_backtest_trailing(&mut self, trail: Trail) -> Option<TriggerReplace> {
+        let open_pos = self.exec_params.open_pos?;
+        self.start_trailing(open_pos, Some(trail))
+    }
+
+    fn trail_atr(&self, trail: Trail) -> Option<f64> {
+        let Trail::Atr { atr, tf, .. } = trail else {
+            return None;
+        };
+        let tracker = self.trackers.get(&(Arc::clone(&self.asset), tf))?;
+        match tracker.indicators.get(&atr)?.get_value()? {
+            Value::AtrValue(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Move the trailing stop with the traded market's latest candle.
+    fn ratchet_trailing(&mut self, high: f64, low: f64) -> Option<TriggerReplace> {
+        let Some(open_pos) = self.exec_params.open_pos else {
+            self.trailing = None;
+            return None;
+        };
+        if self
+            .trailing
+            .is_some_and(|t| t.open_time != open_pos.open_time)
+        {
+            self.trailing = None;
+        }
+        let atr = self.trail_atr(self.trailing?.trail);
+        let trailing = self.trailing.as_mut()?;
+        let stop_px = trailing.ratchet(high, low, self.exec_params.lev, atr)?;
+
+        Some(TriggerReplace::new(TriggerKind::Sl, open_pos.size, stop_px))
+    }
+
+    /// Follow the traded asset's price updates with the trailing stop; the
+    /// stop only moves once it gains `MIN_TRAIL_STEP`.
+    fn ratchet_live_trailing(&mut self, price: Price) {
+        if let Some(replace) = self.ratchet_trailing(price.high, price.low) {
+            let _ = self.queue_exec_command(
+                "trailing stop update",
+                ExecCommand::ReplaceTriggers(replace),
+            );
+        }
+    }
+
     fn process_strategy_tick(&mut self, price: Price) {
         let values = self.get_active_values();
 
@@ -661,6 +921,8 @@ impl SignalEngine {
         if intent == Intent::Abort {
             self.force_close_exec();
             let _ = self.pending_orders.take();
+            self.trailing = None;
+            self.pending_add = None;
             self.state = EngineState::Idle;
         } else if let Intent::Arm(duration) = intent {
             if self.state == EngineState::Idle {
@@ -680,8 +942,22 @@ impl SignalEngine {
                     self.state
                 );
             }
+        } else if intent.is_trigger_update() {
+            match self.translate_trigger_update(&intent, price.close) {
+                Ok(replace) => {
+                    let _ = self.queue_exec_command(
+                        "trigger update",
+                        ExecCommand::ReplaceTriggers(replace),
+                    );
+                }
+                Err(e) => log::warn!("Trigger update rejected: {}", e),
+            }
         } else if let Some(pending) = self.translate_intent(&intent, &price) {
-            if let Err(e) = self.validate_trade(pending, price.close) {
+            let is_add = matches!(intent, Intent::Add(_));
+            if let Err(e) = self
+                .validate_trade(pending, price.close)
+                .and_then(|()| if is_add { self.validate_add() } else { Ok(()) })
+            {
                 log::warn!("Trade rejected: {}", e);
                 return;
             }
@@ -696,6 +972,9 @@ impl SignalEngine {
             }
 
             self.pending_orders = pending_open;
+            if is_add {
+                self.record_add(main_order.action, main_order.limit.is_some());
+            }
 
             if let Some(ttl) = intent.get_ttl() {
                 let timeout = LiveTimeoutInfo {
@@ -707,7 +986,9 @@ impl SignalEngine {
                     Intent::Reduce(_) | Intent::Flatten(_) => {
                         self.state = EngineState::Closing(Some(timeout))
                     }
-                    Intent::Open(_) => self.state = EngineState::Opening(Some(timeout)),
+                    Intent::Open(_) | Intent::Add(_) => {
+                        self.state = EngineState::Opening(Some(timeout))
+                    }
                     _ => {}
                 }
             } else if intent.is_market_order() {
@@ -721,7 +1002,9 @@ impl SignalEngine {
                     Intent::Reduce(_) | Intent::Flatten(_) => {
                         self.state = EngineState::Closing(Some(timeout))
                     }
-                    Intent::Open(_) => self.state = EngineState::Opening(Some(timeout)),
+                    Intent::Open(_) | Intent::Add(_) => {
+                        self.state = EngineState::Opening(Some(timeout))
+                    }
                     _ => {}
                 }
             } else {
@@ -729,7 +1012,7 @@ impl SignalEngine {
                     Intent::Reduce(_) | Intent::Flatten(_) => {
                         self.state = EngineState::Closing(None)
                     }
-                    Intent::Open(_) => self.state = EngineState::Opening(None),
+                    Intent::Open(_) | Intent::Add(_) => self.state = EngineState::Opening(None),
                     _ => {}
                 }
             }
@@ -739,11 +1022,31 @@ impl SignalEngine {
     pub fn refresh_state(&mut self, price: &Price) {
         match self.state {
             EngineState::Opening(ttl_option) => {
-                if let Some(open_pos) = self.exec_params.open_pos {
+                if let Some(open_pos) = self.exec_params.open_pos
+                    && self.add_filled(open_pos)
+                {
+                    self.cancel_add_remainder();
                     self.state = EngineState::Open(open_pos);
                     self.queue_pending_tpsl(open_pos);
                     return;
                 }
+                // An add only ever cancels its own order, whether it expired
+                // or the position it was adding to is gone.
+                if let Some(add) = self.pending_add
+                    && (self.exec_params.open_pos.is_none()
+                        || ttl_option.is_some_and(|t| t.expire_at <= price.open_time))
+                {
+                    let _ = self.queue_exec_command(
+                        "expired add cancel",
+                        ExecCommand::CancelResting(add.action),
+                    );
+                    self.pending_add = None;
+                    self.state = self
+                        .exec_params
+                        .open_pos
+                        .map_or(EngineState::Idle, EngineState::Open);
+                    return;
+                }
                 if let Some(timeout) = ttl_option
                     && timeout.expire_at <= price.open_time
                 {
@@ -841,6 +1144,9 @@ impl SignalEngine {
                             {
                                 self.process_strategy_tick(strategy_price);
                             }
+                            if !self.paused && self.is_traded_asset(&asset) {
+                                self.ratchet_live_trailing(price);
+                            }
 
                             self.digest_single(&asset, price);
                             let ind = self.get_indicators_data();
@@ -879,6 +1185,7 @@ impl SignalEngine {
 
                 EngineCommand::UpdateStrategy(compiled, mut indicators) => {
                     replace_self_with_asset(self.asset.as_ref(), &mut indicators);
+                    self.set_history_len(compiled.history_len);
                     self.strategy = Strategy::new(
                         self.rhai_engine.clone(),
                         compiled,
@@ -980,6 +1287,7 @@ impl SignalEngine {
     ) -> Self {
         replace_self_with_asset(asset.as_ref(), &mut strat_indicators);
 
+        let history_len = compiled.history_len;
         let strategy = Strategy::new(
             rhai_engine.clone(),
             compiled,
@@ -989,7 +1297,7 @@ impl SignalEngine {
         );
 
         let mut trackers: TrackersMap = HashMap::default();
-        insert_indicators(&mut trackers, strat_indicators);
+        insert_indicators(&mut trackers, strat_indicators, history_len);
 
         let (_tx, dummy_rv) = channel::<EngineCommand>(1);
         let (trade_tx, _rx) = bounded::<ExecCommand>(0);
@@ -1009,6 +1317,11 @@ impl SignalEngine {
             pending_orders: None,
             pending_strategy_candle: None,
             paused: false,
+            last_intent: None,
+            history_len,
+            trailing: None,
+            pending_add: None,
+            position_adds: (0, 0),
             asset,
         }
     }
@@ -1044,8 +1357,28 @@ impl SignalEngine {
     fn refresh_state_backtest(&mut self, price: &Price, actions: &mut Vec<BtAction>) {
         match self.state {
             EngineState::Opening(ttl_option) => {
-                if let Some(open_pos) = self.exec_params.open_pos {
+                if let Some(open_pos) = self.exec_params.open_pos
+                    && self.add_filled(open_pos)
+                {
+                    if let Some(action) = self.take_filled_add() {
+                        actions.push(BtAction::CancelResting(action));
+                    }
                     self.state = EngineState::Open(open_pos);
+                    // TP/SL and the trailing stop were attached by the
+                    // backtester at fill time.
+                    let _ = self.pending_orders.take();
+                    return;
+                }
+                if let Some(add) = self.pending_add
+                    && (self.exec_params.open_pos.is_none()
+                        || ttl_option.is_some_and(|t| t.expire_at <= price.open_time))
+                {
+                    actions.push(BtAction::CancelResting(add.action));
+                    self.pending_add = None;
+                    self.state = self
+                        .exec_params
+                        .open_pos
+                        .map_or(EngineState::Idle, EngineState::Open);
                     return;
                 }
                 if let Some(timeout) = ttl_option
@@ -1140,6 +1473,7 @@ impl SignalEngine {
         execution_price: Price,
     ) -> Vec<BtAction> {
         let mut actions = Vec::new();
+        self.last_intent = None;
         if let Some(tracker) = self.trackers.get_mut(&(Arc::clone(asset), tf)) {
             tracker.digest(price);
         }
@@ -1147,9 +1481,13 @@ impl SignalEngine {
             return actions;
         }
         self.refresh_state_backtest(&price, &mut actions);
+        if let Some(replace) = self.ratchet_trailing(execution_price.high, execution_price.low) {
+            actions.push(BtAction::ReplaceTriggers(replace));
+        }
 
         let values = self.get_active_values();
-        if let Some(intent) = self.strat_tick(price, values) {
+        self.last_intent = self.strat_tick(price, values);
+        if let Some(intent) = self.last_intent {
             let busy = matches!(
                 self.state,
                 EngineState::Opening(_) | EngineState::Closing(_)
@@ -1163,6 +1501,8 @@ impl SignalEngine {
             if intent == Intent::Abort {
                 actions.push(BtAction::ForceCloseMarket);
                 let _ = self.pending_orders.take();
+                self.trailing = None;
+                self.pending_add = None;
                 self.state = EngineState::Idle;
                 return actions;
             }
@@ -1191,8 +1531,20 @@ impl SignalEngine {
                 return actions;
             }
 
+            if intent.is_trigger_update() {
+                match self.translate_trigger_update(&intent, execution_price.close) {
+                    Ok(replace) => actions.push(BtAction::ReplaceTriggers(replace)),
+                    Err(e) => log::warn!("Trigger update rejected: {}", e),
+                }
+                return actions;
+            }
+
             if let Some(pending) = self.translate_intent(&intent, &execution_price) {
-                if let Err(e) = self.validate_trade(pending, execution_price.close) {
+                let is_add = matches!(intent, Intent::Add(_));
+                if let Err(e) = self
+                    .validate_trade(pending, execution_price.close)
+                    .and_then(|()| if is_add { self.validate_add() } else { Ok(()) })
+                {
                     log::warn!("Trade rejected: {}", e);
                 } else {
                     if let Some(bt_order) = self.bt_order_from_pending(pending)
@@ -1206,6 +1558,13 @@ impl SignalEngine {
                                 tpsl: open.triggers,
                             });
                         }
+                        if is_add {
+                            let order = match bt_order {
+                                BtOrder::Open(open) => open.order,
+                                BtOrder::Close(close) => close.order,
+                            };
+                            self.record_add(order.action, order.limit.is_some());
+                        }
                         actions.push(BtAction::Submit {
                             order: bt_order,
                             intent: bt_intent,
@@ -1222,7 +1581,9 @@ impl SignalEngine {
                             Intent::Reduce(_) | Intent::Flatten(_) => {
                                 self.state = EngineState::Closing(Some(timeout))
                             }
-                            Intent::Open(_) => self.state = EngineState::Opening(Some(timeout)),
+                            Intent::Open(_) | Intent::Add(_) => {
+                                self.state = EngineState::Opening(Some(timeout))
+                            }
                             _ => {}
                         }
                     } else if intent.is_market_order() {
@@ -1236,7 +1597,9 @@ impl SignalEngine {
                             Intent::Reduce(_) | Intent::Flatten(_) => {
                                 self.state = EngineState::Closing(Some(timeout))
                             }
-                            Intent::Open(_) => self.state = EngineState::Opening(Some(timeout)),
+                            Intent::Open(_) | Intent::Add(_) => {
+                                self.state = EngineState::Opening(Some(timeout))
+                            }
                             _ => {}
                         }
                     } else {
@@ -1244,7 +1607,9 @@ impl SignalEngine {
                             Intent::Reduce(_) | Intent::Flatten(_) => {
                                 self.state = EngineState::Closing(None)
                             }
-                            Intent::Open(_) => self.state = EngineState::Opening(None),
+                            Intent::Open(_) | Intent::Add(_) => {
+                                self.state = EngineState::Opening(None)
+                            }
                             _ => {}
                         }
                     }
@@ -1301,6 +1666,7 @@ pub enum EngineView {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BtIntent {
     Open,
+    Add,
     Reduce,
     Flatten,
 }
@@ -1309,6 +1675,7 @@ impl BtIntent {
     fn from_intent(intent: &Intent) -> Option<Self> {
         match intent {
             Intent::Open(_) => Some(Self::Open),
+            Intent::Add(_) => Some(Self::Add),
             Intent::Reduce(_) => Some(Self::Reduce),
             Intent::Flatten(_) => Some(Self::Flatten),
             _ => None,
@@ -1359,6 +1726,8 @@ pub enum BtAction {
     ForceTaker { order: BtOrder, intent: BtIntent },
     CancelAllResting,
     ForceCloseMarket,
+    ReplaceTriggers(TriggerReplace),
+    CancelResting(PositionOp),
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1373,11 +1742,20 @@ struct PendingOpen {
     tpsl: Option<Triggers>,
 }
 
+#[derive(Copy, Clone, Debug)]
+struct PendingAdd {
+    action: PositionOp,
+    /// Limit adds may leave a remainder resting after a partial fill.
+    resting: bool,
+    /// Position size when the add was sent; growth past it means a fill.
+    size_before: f64,
+}
+
 impl PendingOpen {
     fn has_trigger(&self) -> bool {
         self.tpsl
             .as_ref()
-            .map(|t| t.tp.is_some() || t.sl.is_some())
+            .map(|t| t.tp.is_some() || t.sl.is_some() || t.trail.is_some())
             .unwrap_or(false)
     }
 }
@@ -1390,10 +1768,51 @@ mod tests {
     use crate::backend::scripting::{CompiledStrategy, compile_strategy, create_engine};
     use crate::broadcast::PriceData;
     use crate::{
-        BtAction, BtOrder, EngineOrder, EngineView, ExecCommand, ExecParams, IndicatorKind,
-        MarketCommand, OpenPosInfo, PositionOp, Side, TimeFrame,
+        BtAction, BtIntent, BtOrder, EngineOrder, EngineView, ExecCommand, ExecParams,
+        IndicatorKind, MAX_POSITION_ADDS, MarketCommand, OpenPosInfo, PositionOp, Side, TimeFrame,
+        Trail, TriggerKind,
     };
 
+    /// One-minute bar `minute` minutes in, a point either side of `close`.
+    fn bar(minute: u64, close: f64) -> crate::Price {
+        crate::Price {
+            open_time: minute * 60_000,
+            close_time: (minute + 1) * 60_000,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            vlm: 10.0,
+        }
+    }
+
+    /// Long position opened on the first minute at 100.
+    fn open_long(size: f64) -> OpenPosInfo {
+        OpenPosInfo {
+            side: Side::Long,
+            size,
+            entry_px: 100.0,
+            open_time: 60_000,
+        }
+    }
+
+    /// Backtest engine on BTC with 100 of margin running the given scripts.
+    fn strategy_engine(on_idle: &str, on_open: &str, lev: usize) -> (SignalEngine, Arc<str>) {
+        let rhai_engine = Arc::new(create_engine());
+        let compiled = compile_strategy(rhai_engine.as_ref(), on_idle, on_open, "()", None)
+            .expect("strategy compiles");
+        let asset = Arc::<str>::from("BTC");
+        let engine = SignalEngine::new_backtest(
+            100.0,
+            lev,
+            rhai_engine,
+            compiled,
+            Vec::new(),
+            Arc::clone(&asset),
+        );
+        (engine, asset)
+    }
+
     #[test]
     fn new_backtest_replaces_self_indicators_with_market_asset() {
         let rhai_engine = Arc::new(create_engine());
@@ -1481,6 +1900,203 @@ mod tests {
         assert!((open.order.size - 2.0).abs() < 1e-9);
     }
 
+    #[test]
+    fn backtest_strategy_reads_recent_candles_across_ticks() {
+        let rhai_engine = Arc::new(create_engine());
+        let compiled = compile_strategy(
+            rhai_engine.as_ref(),
+            r#"let bars = candles("self", MIN1, 3);
+if bars.len() < 3 || bars[0].close <= bars[1].high { return; }
+open_market(LONG, margin_amount(100.0))"#,
+            "()",
+            "()",
+            None,
+        )
+        .expect("strategy compiles");
+        let asset = Arc::<str>::from("BTC");
+        let mut engine = SignalEngine::new_backtest(
+            100.0,
+            2,
+            Arc::clone(&rhai_engine),
+            compiled,
+            vec![(Arc::from("self"), IndicatorKind::Sma(2), TimeFrame::Min1)],
+            Arc::clone(&asset),
+        );
+        for (minute, close) in [(1, 100.0), (2, 100.0)] {
+            let price = bar(minute, close);
+            let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+            assert!(actions.is_empty());
+        }
+        let breakout = bar(3, 110.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, breakout, breakout);
+        assert!(matches!(actions.first(), Some(BtAction::Submit { .. })));
+    }
+
+    #[test]
+    fn backtest_set_sl_replaces_stop_for_open_position() {
+        let (mut engine, asset) = strategy_engine(
+            "()",
+            "if last_price.close > 104.0 { set_sl(101.0) } else { set_sl(106.0) }",
+            2,
+        );
+        engine.set_backtest_open_position(Some(OpenPosInfo {
+            size: 1.5,
+            open_time: 0,
+            ..open_long(1.0)
+        }));
+
+        let price = bar(1, 105.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        let Some(BtAction::ReplaceTriggers(replace)) = actions.first().copied() else {
+            panic!("expected trigger replace action");
+        };
+        assert_eq!(replace.cancel, Some(TriggerKind::Sl));
+        let order = replace.order.expect("replacement stop");
+        assert_eq!(order.is_tpsl(), Some(TriggerKind::Sl));
+        assert!((order.size - 1.5).abs() < 1e-9);
+        assert_eq!(order.limit.map(|l| l.limit_px), Some(101.0));
+
+        // A long stop above the market would fire immediately.
+        let price = bar(2, 103.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn backtest_trailing_stop_starts_at_fill_and_ratchets() {
+        let (mut engine, asset) = strategy_engine(
+            "open_market(LONG, margin_amount(100.0), trailing(trail_pct(10.0)))",
+            "()",
+            5,
+        );
+        let stops = |actions: Vec<BtAction>| -> Vec<f64> {
+            actions
+                .into_iter()
+                .filter_map(|action| match action {
+                    BtAction::ReplaceTriggers(replace) => {
+                        assert_eq!(replace.cancel, Some(TriggerKind::Sl));
+                        replace.order.and_then(|o| o.limit).map(|l| l.limit_px)
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let price = bar(1, 100.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        assert!(matches!(actions.first(), Some(BtAction::Submit { .. })));
+
+        engine.set_backtest_open_position(Some(open_long(5.0)));
+        // 10% ROE at 5x trails 2% behind the best price: placed from the
+        // entry as it fills, then moved up with the candle's high.
+        let placed = stops(
+            engine
+                .start_backtest_trailing(Trail::Pct(10.0))
+                .map(BtAction::ReplaceTriggers)
+                .into_iter()
+                .collect(),
+        );
+        assert_eq!(placed.len(), 1);
+        assert!((placed[0] - 98.0).abs() < 1e-9);
+        let price = crate::Price {
+            high: 110.0,
+            ..bar(2, 100.0)
+        };
+        let placed = stops(engine.tick_backtest(&asset, TimeFrame::Min1, price, price));
+        assert_eq!(placed.len(), 1);
+        assert!((placed[0] - 107.8).abs() < 1e-9);
+
+        let price = crate::Price {
+            high: 105.0,
+            ..bar(3, 100.0)
+        };
+        assert!(stops(engine.tick_backtest(&asset, TimeFrame::Min1, price, price)).is_empty());
+    }
+
+    #[test]
+    fn backtest_adds_wait_for_fill_and_stop_at_cap() {
+        let (mut engine, asset) = strategy_engine(
+            "open_market(LONG, margin_amount(5.0))",
+            "add_market(margin_amount(5.0))",
+            5,
+        );
+        let adds = |actions: &[BtAction]| {
+            actions
+                .iter()
+                .filter(|action| {
+                    matches!(
+                        action,
+                        BtAction::Submit {
+                            intent: BtIntent::Add,
+                            order: BtOrder::Open(_),
+                        }
+                    )
+                })
+                .count()
+        };
+
+        let price = bar(1, 100.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        assert!(matches!(
+            actions.first(),
+            Some(BtAction::Submit {
+                intent: BtIntent::Open,
+                ..
+            })
+        ));
+
+        let mut size = 0.25;
+        let mut submitted = 0;
+        for minute in 2..(MAX_POSITION_ADDS as u64 + 4) {
+            engine.set_backtest_open_position(Some(open_long(size)));
+            let price = bar(minute, 100.0);
+            let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+            submitted += adds(&actions);
+            // The unfilled add keeps the engine busy until the size grows.
+            assert_eq!(
+                adds(&engine.tick_backtest(&asset, TimeFrame::Min1, price, price)),
+                0
+            );
+            size += 0.25;
+        }
+        assert_eq!(submitted, MAX_POSITION_ADDS);
+        assert!(matches!(engine.state, EngineState::Open(_)));
+    }
+
+    #[test]
+    fn backtest_partial_limit_add_cancels_its_remainder() {
+        let (mut engine, asset) = strategy_engine(
+            "open_market(LONG, margin_amount(5.0), trailing(trail_pct(10.0)))",
+            "add_limit(margin_amount(5.0), last_price.close * 0.99, timedelta(MIN1, 5))",
+            5,
+        );
+
+        let price = bar(1, 100.0);
+        engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        engine.set_backtest_open_position(Some(open_long(0.25)));
+        assert!(engine.start_backtest_trailing(Trail::Pct(10.0)).is_some());
+
+        let price = bar(2, 100.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        assert!(actions.iter().any(|action| matches!(
+            action,
+            BtAction::Submit {
+                intent: BtIntent::Add,
+                ..
+            }
+        )));
+
+        // Part of the add fills: the rest is canceled and the trail survives.
+        engine.set_backtest_open_position(Some(open_long(0.3)));
+        let price = bar(3, 100.0);
+        let actions = engine.tick_backtest(&asset, TimeFrame::Min1, price, price);
+        assert!(matches!(
+            actions.first(),
+            Some(BtAction::CancelResting(PositionOp::OpenLong))
+        ));
+        assert!(engine.trailing.is_some());
+    }
+
     #[test]
     fn validate_engine_order_rejects_non_finite_size_and_price() {
         let rhai_engine = Arc::new(create_engine());
@@ -1606,6 +2222,83 @@ mod tests {
         handle.await.expect("engine task should finish");
     }
 
+    #[tokio::test]
+    async fn live_trailing_stop_follows_price_updates_within_a_candle() {
+        let rhai_engine = Arc::new(create_engine());
+        let compiled = CompiledStrategy::noop(rhai_engine.as_ref());
+        let asset = Arc::<str>::from("BTC");
+        let (engine_tx, engine_rx) = tokio::sync::mpsc::channel(4);
+        let (log_tx, _log_rx) = tokio::sync::mpsc::channel(4);
+        let (trade_tx, trade_rx) = flume::bounded(4);
+
+        let mut engine = SignalEngine::new(
+            Arc::clone(&asset),
+            None,
+            rhai_engine,
+            compiled,
+            Vec::new(),
+            engine_rx,
+            None,
+            log_tx,
+            trade_tx,
+            ExecParams::new(100.0, 5),
+        )
+        .await;
+        let open_pos = open_long(5.0);
+        engine.exec_params.open_pos = Some(open_pos);
+        engine.state = EngineState::Open(open_pos);
+        assert!(
+            engine
+                .start_trailing(open_pos, Some(Trail::Pct(10.0)))
+                .is_some()
+        );
+
+        let handle = tokio::spawn(async move { engine.start().await });
+        let tick = async |high: f64| {
+            engine_tx
+                .send(EngineCommand::UpdatePrice((
+                    Arc::clone(&asset),
+                    PriceData::Single(crate::Price {
+                        high,
+                        ..bar(1, 100.0)
+                    }),
+                )))
+                .await
+                .expect("engine command accepted");
+        };
+        let wait =
+            |ms| tokio::time::timeout(std::time::Duration::from_millis(ms), trade_rx.recv_async());
+        let stop_px = |cmd: ExecCommand| {
+            let ExecCommand::ReplaceTriggers(replace) = cmd else {
+                panic!("expected trailing stop update");
+            };
+            replace.order.and_then(|o| o.limit).map(|l| l.limit_px)
+        };
+
+        // Updates of the forming candle move the stop, but only by at least
+        // the minimum step.
+        tick(110.0).await;
+        let cmd = wait(1_000)
+            .await
+            .expect("stop moved")
+            .expect("trade receiver open");
+        assert!(stop_px(cmd).is_some_and(|px| (px - 107.8).abs() < 1e-9));
+        tick(110.02).await;
+        assert!(wait(100).await.is_err());
+        tick(111.0).await;
+        let cmd = wait(1_000)
+            .await
+            .expect("stop moved")
+            .expect("trade receiver open");
+        assert!(stop_px(cmd).is_some_and(|px| (px - 108.78).abs() < 1e-9));
+
+        engine_tx
+            .send(EngineCommand::Stop)
+            .await
+            .expect("stop command accepted");
+        handle.await.expect("engine task should finish");
+    }
+
     #[tokio::test]
     async fn open_position_update_moves_live_engine_state_without_price_tick() {
         let rhai_engine = Arc::new(create_engine());
@@ -1,4 +1,4 @@
-use crate::{Limit, Side, TriggerKind, Triggers};
+use crate::{Limit, Side, Trail, TriggerKind, Triggers};
 
 const MIN_LIMIT_MULT: f64 = 0.05;
 const MAX_LIMIT_MULT: f64 = 15.0;
@@ -25,6 +25,23 @@ pub(super) fn validate_tpsl(tpsl: &Triggers) -> Result<(), String> {
         }
     }
 
+    if let Some(trail) = tpsl.trail {
+        if tpsl.sl.is_some() {
+            return Err("Invalid Trigger: a trailing stop replaces SL, set only one".into());
+        }
+        match trail {
+            Trail::Pct(pct) => {
+                validate_finite_positive("Trailing stop", pct)?;
+                if pct >= 100.0 {
+                    return Err("Invalid Trigger: trailing stop must be < 100 (cannot exceed full margin loss)".into());
+                }
+            }
+            Trail::Atr { mult, .. } => {
+                validate_finite_positive("Trailing stop ATR multiple", mult)?
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -49,6 +66,28 @@ pub(super) fn validate_trigger_price(trigger: TriggerKind, price: f64) -> Result
     Ok(())
 }
 
+/// A trigger already on the wrong side of the market would fire immediately.
+pub(super) fn validate_trigger_side(
+    side: Side,
+    trigger: TriggerKind,
+    trigger_px: f64,
+    ref_px: f64,
+) -> Result<(), String> {
+    let above = trigger_px > ref_px;
+    let ok = match (side, trigger) {
+        (Side::Long, TriggerKind::Tp) | (Side::Short, TriggerKind::Sl) => above,
+        (Side::Long, TriggerKind::Sl) | (Side::Short, TriggerKind::Tp) => trigger_px < ref_px,
+    };
+    if !ok {
+        let expected = if above { "below" } else { "above" };
+        return Err(format!(
+            "Invalid {trigger:?} trigger price: {trigger_px} must be {expected} the market price {ref_px} for a {side:?} position"
+        ));
+    }
+
+    Ok(())
+}
+
 pub(super) fn calc_trigger_px(
     side: Side,
     trigger: TriggerKind,
@@ -88,7 +127,8 @@ mod tests {
         assert!(
             validate_tpsl(&Triggers {
                 tp: Some(f64::NAN),
-                sl: None
+                sl: None,
+                trail: None,
             })
             .is_err()
         );
@@ -96,6 +136,31 @@ mod tests {
             validate_tpsl(&Triggers {
                 tp: None,
                 sl: Some(f64::INFINITY),
+                trail: None,
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_tpsl_rejects_trailing_stop_with_fixed_sl() {
+        let trailing = Triggers {
+            tp: Some(5.0),
+            sl: None,
+            trail: Some(Trail::Pct(2.0)),
+        };
+        assert!(validate_tpsl(&trailing).is_ok());
+        assert!(
+            validate_tpsl(&Triggers {
+                sl: Some(2.0),
+                ..trailing
+            })
+            .is_err()
+        );
+        assert!(
+            validate_tpsl(&Triggers {
+                trail: Some(Trail::Pct(f64::NAN)),
+                ..trailing
             })
             .is_err()
         );
@@ -114,4 +179,13 @@ mod tests {
         assert!(validate_trigger_price(TriggerKind::Sl, -1.0).is_err());
         assert!(validate_trigger_price(TriggerKind::Tp, 101.0).is_ok());
     }
+
+    #[test]
+    fn validate_trigger_side_rejects_triggers_through_the_market() {
+        assert!(validate_trigger_side(Side::Long, TriggerKind::Sl, 99.0, 100.0).is_ok());
+        assert!(validate_trigger_side(Side::Long, TriggerKind::Sl, 101.0, 100.0).is_err());
+        assert!(validate_trigger_side(Side::Long, TriggerKind::Tp, 101.0, 100.0).is_ok());
+        assert!(validate_trigger_side(Side::Short, TriggerKind::Sl, 101.0, 100.0).is_ok());
+        assert!(validate_trigger_side(Side::Short, TriggerKind::Tp, 100.0, 100.0).is_err());
+    }
 }
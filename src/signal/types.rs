@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Debug;
 use std::sync::Arc;
 
@@ -7,7 +7,8 @@ use std::hash::BuildHasherDefault;
 
 use kwant::indicators::*;
 
-use crate::{IndicatorData, IndicatorKind, Side, TimeFrame};
+use super::helpers::calc_trigger_px;
+use crate::{IndicatorData, IndicatorKind, Side, TimeFrame, Trail, TriggerKind};
 use log::warn;
 
 use serde::Deserialize;
@@ -57,6 +58,68 @@ impl ExecParams {
     }
 }
 
+/// Smallest relative move worth a cancel-and-replace of a trailing stop.
+const MIN_TRAIL_STEP: f64 = 0.0005;
+
+/// Trailing stop the engine ratchets; each move is sent out as a replaced SL.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) struct TrailingStop {
+    pub trail: Trail,
+    side: Side,
+    /// Open time of the position being trailed.
+    pub open_time: u64,
+    /// Best price seen since entry.
+    extreme: f64,
+    stop_px: Option<f64>,
+}
+
+impl TrailingStop {
+    pub fn new(trail: Trail, open_pos: &OpenPosInfo) -> Self {
+        Self {
+            trail,
+            side: open_pos.side,
+            open_time: open_pos.open_time,
+            extreme: open_pos.entry_px,
+            stop_px: None,
+        }
+    }
+
+    /// Fold a candle's range into the best price and return the new stop
+    /// when it moved far enough in the position's favour.
+    pub fn ratchet(&mut self, high: f64, low: f64, lev: usize, atr: Option<f64>) -> Option<f64> {
+        self.extreme = match self.side {
+            Side::Long => self.extreme.max(high),
+            Side::Short => self.extreme.min(low),
+        };
+        let candidate = match self.trail {
+            Trail::Pct(pct) => {
+                if lev == 0 {
+                    return None;
+                }
+                calc_trigger_px(self.side, TriggerKind::Sl, pct, self.extreme, lev)
+            }
+            Trail::Atr { mult, .. } => match self.side {
+                Side::Long => self.extreme - atr? * mult,
+                Side::Short => self.extreme + atr? * mult,
+            },
+        };
+        if !candidate.is_finite() || candidate <= 0.0 {
+            return None;
+        }
+
+        let moved = match (self.side, self.stop_px) {
+            (_, None) => true,
+            (Side::Long, Some(stop)) => candidate > stop * (1.0 + MIN_TRAIL_STEP),
+            (Side::Short, Some(stop)) => candidate < stop * (1.0 - MIN_TRAIL_STEP),
+        };
+        if !moved {
+            return None;
+        }
+        self.stop_px = Some(candidate);
+        Some(candidate)
+    }
+}
+
 pub enum ExecParam {
     Margin(f64),
     Lev(usize),
@@ -68,6 +131,8 @@ pub struct Handler {
     pub indicator: Box<dyn Indicator>,
     pub is_active: bool,
     pub closed: bool,
+    /// Values at past candle closes, newest first.
+    history: VecDeque<TimedValue>,
 }
 
 impl Handler {
@@ -76,6 +141,7 @@ impl Handler {
             indicator: match_kind(indicator),
             is_active: true,
             closed: false,
+            history: VecDeque::new(),
         }
     }
 
@@ -110,14 +176,118 @@ impl Handler {
         self.indicator.load(price_data);
     }
 
+    /// Remember the current value as the close at `ts`, keeping at most `max_len`.
+    fn record_close(&mut self, ts: u64, max_len: usize) {
+        if max_len == 0 {
+            return;
+        }
+        let Some(value) = self.get_value() else {
+            return;
+        };
+        self.history.truncate(max_len - 1);
+        self.history.push_front(TimedValue {
+            value,
+            on_close: true,
+            ts,
+        });
+    }
+
+    /// Closed values before the current one, newest first. Right after a close
+    /// the current value is itself the newest entry, so it is skipped.
+    pub fn history(&self) -> impl Iterator<Item = &TimedValue> {
+        self.history.iter().skip(usize::from(self.closed))
+    }
+
     pub fn reset(&mut self) {
         self.indicator.reset();
+        self.history.clear();
     }
 }
 
 pub type IndexId = (Arc<str>, IndicatorKind, TimeFrame);
 pub type AssetTimeFrame = (Arc<str>, TimeFrame);
 
+/// Closed candles kept per tracker; matches the script engine's array size limit.
+pub const MAX_TRACKER_CANDLES: usize = 1024;
+
+/// Recent candles at a tracker's timeframe, built from whatever candles it
+/// digests: live 1m updates are merged, backtest candles pass through as is.
+#[derive(Debug, Default)]
+pub struct TrackerCandles {
+    closed: VecDeque<Price>,
+    /// Finished sub-candles of the forming candle, merged.
+    forming: Option<Price>,
+    /// Latest version of the sub-candle still updating.
+    last: Option<Price>,
+}
+
+impl TrackerCandles {
+    fn push(&mut self, price: Price, tf: TimeFrame) {
+        if let Some(last) = self.last {
+            if price.open_time < last.open_time {
+                return;
+            }
+            if tf.candle_open(last.open_time) != tf.candle_open(price.open_time) {
+                if let Some(done) = self.current(tf) {
+                    if self.closed.len() == MAX_TRACKER_CANDLES {
+                        self.closed.pop_front();
+                    }
+                    self.closed.push_back(done);
+                }
+                self.forming = None;
+            } else if last.open_time != price.open_time {
+                self.forming = Some(match self.forming {
+                    Some(forming) => merge_candles(forming, last),
+                    None => last,
+                });
+            }
+        }
+        self.last = Some(price);
+    }
+
+    /// The forming candle: finished sub-candles merged with the latest one.
+    fn current(&self, tf: TimeFrame) -> Option<Price> {
+        let last = self.last?;
+        let mut candle = match self.forming {
+            Some(forming) => merge_candles(forming, last),
+            None => last,
+        };
+        candle.open_time = tf.candle_open(candle.open_time);
+        Some(candle)
+    }
+
+    /// Up to `n` closed candles, newest first. The forming candle counts once
+    /// its latest sub-candle reaches the end of the timeframe, so live and
+    /// backtest strategies see the same candles.
+    pub fn recent(&self, tf: TimeFrame, n: usize) -> Vec<Price> {
+        // Exchange candles close 1ms before the next one opens.
+        let finished = self
+            .current(tf)
+            .filter(|candle| candle.close_time + 1 >= tf.next_candle_open(candle.open_time));
+        finished
+            .into_iter()
+            .chain(self.closed.iter().rev().copied())
+            .take(n)
+            .collect()
+    }
+
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn merge_candles(first: Price, next: Price) -> Price {
+    Price {
+        open: first.open,
+        high: first.high.max(next.high),
+        low: first.low.min(next.low),
+        close: next.close,
+        open_time: first.open_time,
+        close_time: next.close_time,
+        vlm: first.vlm + next.vlm,
+    }
+}
+
 fn match_kind(kind: IndicatorKind) -> Box<dyn Indicator> {
     match kind {
         IndicatorKind::Rsi(periods) => Box::new(Rsi::new(periods, periods, None, None, None)),
@@ -166,22 +336,46 @@ pub struct Tracker {
     tf: TimeFrame,
     prev_close: Option<u64>,
     next_close: Option<u64>,
+    /// Past closes the strategy can look back on per indicator.
+    history_len: usize,
+    candles: TrackerCandles,
 }
 
 impl Tracker {
-    pub fn new(asset: Arc<str>, tf: TimeFrame) -> Self {
+    pub fn new(asset: Arc<str>, tf: TimeFrame, history_len: usize) -> Self {
         Tracker {
             indicators: HashMap::default(),
             asset,
             tf,
             prev_close: None,
             next_close: None,
+            history_len,
+            candles: TrackerCandles::default(),
+        }
+    }
+
+    /// One extra close is kept so a full lookback survives the bar the
+    /// current value closed on.
+    fn history_capacity(&self) -> usize {
+        if self.history_len == 0 {
+            0
+        } else {
+            self.history_len + 1
+        }
+    }
+
+    pub fn set_history_len(&mut self, history_len: usize) {
+        self.history_len = history_len;
+        let capacity = self.history_capacity();
+        for handler in self.indicators.values_mut() {
+            handler.history.truncate(capacity);
         }
     }
 
     pub fn digest(&mut self, price: Price) {
         let ts = price.close_time;
         let tf_ms = self.tf.to_millis();
+        self.candles.push(price, self.tf);
 
         let mut next = match self.next_close {
             Some(n) => n,
@@ -210,8 +404,11 @@ impl Tracker {
     }
 
     fn update_indicators_after_close(&mut self, price: Price) {
+        let ts = self.prev_close.unwrap_or(0);
+        let capacity = self.history_capacity();
         for handler in &mut self.indicators.values_mut() {
             handler.update_after_close(price);
+            handler.record_close(ts, capacity);
         }
     }
 
@@ -233,13 +430,22 @@ impl Tracker {
             return;
         };
         let slice = buffer.as_slice();
+        let tf_ms = self.tf.to_millis();
+        let prev_close = (last.close_time / tf_ms) * tf_ms;
+        let capacity = self.history_capacity();
+
+        self.candles.clear();
+        for &price in slice {
+            self.candles.push(price, self.tf);
+        }
 
         for handler in self.indicators.values_mut() {
             handler.load_slice(slice);
+            // Loaded candles are closed, so their last value seeds the history.
+            handler.history.clear();
+            handler.record_close(prev_close, capacity);
         }
 
-        let tf_ms = self.tf.to_millis();
-        let prev_close = (last.close_time / tf_ms) * tf_ms;
         self.prev_close = Some(prev_close);
         self.next_close = Some(prev_close + tf_ms);
     }
@@ -279,6 +485,23 @@ impl Tracker {
         values
     }
 
+    pub fn get_active_history(&self) -> HistoryMap {
+        let mut history: HistoryMap = HashMap::with_capacity_and_hasher(
+            self.indicators.len(),
+            BuildHasherDefault::<FxHasher>::default(),
+        );
+        if self.history_len == 0 {
+            return history;
+        }
+        for (kind, handler) in self.indicators.iter() {
+            let past: Vec<TimedValue> = handler.history().take(self.history_len).copied().collect();
+            if !past.is_empty() {
+                history.insert((Arc::clone(&self.asset), *kind, self.tf), past);
+            }
+        }
+        history
+    }
+
     pub fn get_indicators_data(&self) -> Vec<IndicatorData> {
         let mut values = Vec::with_capacity(self.indicators.len());
         for (kind, handler) in self.indicators.iter() {
@@ -296,6 +519,16 @@ impl Tracker {
         }
         self.prev_close = None;
         self.next_close = None;
+        self.candles.clear();
+    }
+
+    /// Lend the candle history out for a strategy tick; see `restore_candles`.
+    pub(super) fn take_candles(&mut self) -> TrackerCandles {
+        std::mem::take(&mut self.candles)
+    }
+
+    pub(super) fn restore_candles(&mut self, candles: TrackerCandles) {
+        self.candles = candles;
     }
 }
 
@@ -317,6 +550,8 @@ pub enum EditType {
 }
 
 pub type ValuesMap = HashMap<IndexId, TimedValue, BuildHasherDefault<FxHasher>>;
+/// Closed values before the current one per indicator, newest first.
+pub type HistoryMap = HashMap<IndexId, Vec<TimedValue>, BuildHasherDefault<FxHasher>>;
 
 #[derive(Debug, Copy, Clone)]
 pub struct TimedValue {
@@ -329,6 +564,33 @@ pub struct TimedValue {
 mod tests {
     use super::*;
 
+    #[test]
+    fn trailing_stop_only_ratchets_in_the_positions_favour() {
+        let pos = |side| OpenPosInfo {
+            side,
+            size: 1.0,
+            entry_px: 100.0,
+            open_time: 1,
+        };
+        let mut long = TrailingStop::new(Trail::Pct(10.0), &pos(Side::Long));
+        // 10% ROE at 5x leverage trails 2% behind the best price.
+        assert_eq!(long.ratchet(100.0, 99.0, 5, None), Some(98.0));
+        assert_eq!(long.ratchet(110.0, 104.0, 5, None), Some(107.8));
+        assert_eq!(long.ratchet(108.0, 100.0, 5, None), None);
+        assert_eq!(long.ratchet(110.02, 109.0, 5, None), None);
+
+        let atr = Trail::Atr {
+            atr: IndicatorKind::Atr(14),
+            tf: TimeFrame::Min15,
+            mult: 2.0,
+        };
+        let mut short = TrailingStop::new(atr, &pos(Side::Short));
+        assert_eq!(short.ratchet(101.0, 99.0, 5, None), None);
+        assert_eq!(short.ratchet(101.0, 95.0, 5, Some(1.5)), Some(98.0));
+        assert_eq!(short.ratchet(97.0, 96.0, 5, Some(1.5)), None);
+        assert_eq!(short.ratchet(96.0, 94.0, 5, Some(0.5)), Some(95.0));
+    }
+
     #[test]
     fn exec_params_zero_leverage_does_not_divide_by_zero() {
         let mut params = ExecParams::new(100.0, 0);
@@ -350,4 +612,113 @@ mod tests {
         assert_eq!(params.get_max_open_size(0.0), 0.0);
         assert_eq!(params.get_max_open_size(f64::NAN), 0.0);
     }
+
+    #[test]
+    fn tracker_history_keeps_bounded_closes_before_the_current_value() {
+        let kind = IndicatorKind::Sma(2);
+        let mut tracker = Tracker::new(Arc::from("BTC"), TimeFrame::Min1, 2);
+        tracker.add_indicator(kind);
+        let candle = |close_time: u64, close: f64| Price {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            open_time: close_time - 60_000,
+            close_time,
+            vlm: 1.0,
+        };
+        let closes = |tracker: &Tracker| -> Vec<u64> {
+            tracker
+                .get_active_history()
+                .values()
+                .flat_map(|past| past.iter().map(|tv| tv.ts))
+                .collect()
+        };
+
+        for i in 1..=10_u64 {
+            tracker.digest(candle(i * 60_000, i as f64));
+        }
+        assert_eq!(closes(&tracker), vec![540_000, 480_000]);
+
+        tracker.digest(candle(630_000, 11.0));
+        assert_eq!(closes(&tracker), vec![600_000, 540_000]);
+
+        tracker.set_history_len(0);
+        assert!(tracker.get_active_history().is_empty());
+    }
+
+    #[test]
+    fn tracker_candles_merge_live_updates_into_closed_timeframe_bars() {
+        const MIN: u64 = 60_000;
+        let tf = TimeFrame::Min5;
+        let update = |open_time: u64, high: f64, low: f64, close: f64, vlm: f64| Price {
+            open: close,
+            high,
+            low,
+            close,
+            open_time,
+            close_time: open_time + MIN - 1,
+            vlm,
+        };
+        let mut candles = TrackerCandles::default();
+
+        candles.push(update(0, 10.0, 9.0, 9.5, 1.0), tf);
+        candles.push(update(0, 11.0, 9.0, 10.5, 2.0), tf);
+        candles.push(update(MIN, 12.0, 10.0, 11.0, 3.0), tf);
+        candles.push(update(5 * MIN, 11.5, 10.5, 11.2, 1.0), tf);
+        // Stale updates are ignored.
+        candles.push(update(4 * MIN, 99.0, 1.0, 50.0, 9.0), tf);
+
+        // The forming candle is left out.
+        let recent = candles.recent(tf, 5);
+        assert_eq!(recent.len(), 1);
+        let first = recent[0];
+        assert_eq!(first.open_time, 0);
+        assert_eq!(first.close_time, 2 * MIN - 1);
+        assert_eq!((first.open, first.close), (10.5, 11.0));
+        assert_eq!((first.high, first.low), (12.0, 9.0));
+        assert_eq!(first.vlm, 5.0);
+
+        // Until its last minute has been seen.
+        candles.push(update(9 * MIN, 11.4, 10.9, 11.3, 1.0), tf);
+        let recent = candles.recent(tf, 5);
+        assert_eq!(recent.len(), 2);
+        assert_eq!((recent[0].open_time, recent[0].close), (5 * MIN, 11.3));
+        assert_eq!(candles.recent(tf, 1).len(), 1);
+    }
+
+    #[test]
+    fn tracker_candles_follow_calendar_weeks_and_months() {
+        const DAY: u64 = 24 * 60 * 60 * 1000;
+        // Monday 2024-01-01 00:00 UTC.
+        const JAN_1: u64 = 1_704_067_200_000;
+        let feb_1 = JAN_1 + 31 * DAY;
+        let mar_1 = feb_1 + 29 * DAY;
+        assert_eq!(TimeFrame::Week.candle_open(JAN_1 + 6 * DAY), JAN_1);
+        assert_eq!(TimeFrame::Week.candle_open(JAN_1 - 1), JAN_1 - 7 * DAY);
+        assert_eq!(TimeFrame::Month.candle_open(feb_1 + 14 * DAY), feb_1);
+        assert_eq!(TimeFrame::Month.next_candle_open(feb_1 + 14 * DAY), mar_1);
+
+        let daily = |open_time: u64, close: f64| Price {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            open_time,
+            close_time: open_time + DAY,
+            vlm: 1.0,
+        };
+        let mut candles = TrackerCandles::default();
+        // Saturday and Sunday close the week; Monday opens the next one.
+        candles.push(daily(JAN_1 + 5 * DAY, 10.0), TimeFrame::Week);
+        candles.push(daily(JAN_1 + 6 * DAY, 11.0), TimeFrame::Week);
+        let week = candles.recent(TimeFrame::Week, 5);
+        assert_eq!(week.len(), 1);
+        assert_eq!((week[0].open_time, week[0].close), (JAN_1, 11.0));
+
+        candles.push(daily(JAN_1 + 7 * DAY, 12.0), TimeFrame::Week);
+        let week = candles.recent(TimeFrame::Week, 5);
+        assert_eq!(week.len(), 1);
+        assert_eq!((week[0].open, week[0].close), (10.0, 11.0));
+    }
 }
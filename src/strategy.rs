@@ -1,13 +1,13 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use rhai::{Dynamic, Engine, Map, Scope};
+use rhai::{Array, Dynamic, Engine, Map, Scope};
 use rustc_hash::FxHasher;
 use std::hash::BuildHasherDefault;
 
-use crate::backend::scripting::CompiledStrategy;
+use crate::backend::scripting::{CompiledStrategy, ScriptLogKind, capture_script_log};
 use crate::metrics;
-use crate::signal::ValuesMap;
+use crate::signal::{HistoryMap, ValuesMap};
 use crate::{IndexId, IndicatorKind, OpenPosInfo, Price, Side, TimeDelta, TimeFrame, timedelta};
 
 use tokio::sync::mpsc::Sender;
@@ -19,6 +19,7 @@ pub struct StratContext<'a> {
     pub lev: usize,
     pub last_price: Price,
     pub indicators: &'a ValuesMap,
+    pub history: &'a HistoryMap,
 }
 
 pub trait Strat: Send {
@@ -114,6 +115,10 @@ impl Strategy {
         self.scope.push("state", Map::new());
     }
 
+    pub fn state(&self) -> Map {
+        self.scope.get_value::<Map>("state").unwrap_or_default()
+    }
+
     fn sync_state_back(&mut self) {
         if self.compiled.state_var_names.is_empty() {
             return;
@@ -144,6 +149,8 @@ impl Strategy {
         self.scope.push("last_price", ctx.last_price);
         self.scope
             .push("indicators", self.indicators_to_map(ctx.indicators));
+        self.scope
+            .push("indicator_history", self.history_to_map(ctx.history));
     }
 
     /// Build the Rhai indicator map using pre-computed keys (no `format!` per tick).
@@ -166,6 +173,28 @@ impl Strategy {
         }
         map
     }
+
+    /// Same keys as `indicators_to_map`, each holding its past closes newest first.
+    fn history_to_map(&self, history: &HistoryMap) -> Map {
+        let mut map = Map::new();
+        for ((asset, kind, tf), past) in history.iter() {
+            let resolved_asset = resolved_indicator_asset(asset, self.asset.as_ref());
+            let values: Array = past.iter().copied().map(Dynamic::from).collect();
+
+            if resolved_asset.as_ref() == self.asset.as_ref() {
+                let self_key = indicator_map_key("self", *kind, *tf);
+                map.insert(self_key.into(), values.clone().into());
+            }
+
+            if let Some(key) = self
+                .indicator_keys
+                .get(&(Arc::clone(&resolved_asset), *kind, *tf))
+            {
+                map.insert(key.as_str().into(), values.into());
+            }
+        }
+        map
+    }
 }
 
 fn push_scope_constants(scope: &mut Scope) {
@@ -203,8 +232,10 @@ fn eval_ast(
             }
         }
         Err(e) => {
-            if let Some(logger) = log_tx
-                && logger.try_send(e.to_string()).is_err()
+            let msg = e.to_string();
+            if !capture_script_log(ScriptLogKind::Error, &msg)
+                && let Some(logger) = log_tx
+                && logger.try_send(msg).is_err()
             {
                 metrics::inc_strategy_log_dropped();
             }
@@ -399,6 +430,7 @@ pub struct Order {
     pub size: SizeSpec,
     pub tp: Option<f64>,
     pub sl: Option<f64>,
+    pub trail: Option<Trail>,
     pub liq_side: LiqSide,
 }
 
@@ -408,20 +440,48 @@ pub struct ReduceOrder {
     pub liq_side: LiqSide,
 }
 
+/// Order adding to the open position on its own side.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AddOrder {
+    pub size: SizeSpec,
+    pub liq_side: LiqSide,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Intent {
     Open(Order),
+    Add(AddOrder),
     Reduce(ReduceOrder),
     Flatten(LiqSide),
     Arm(TimeDelta),
     Disarm,
     Abort,
+    /// Replace the position's stop-loss with one at an absolute price.
+    SetSl(f64),
+    /// Replace the position's take-profit with one at an absolute price.
+    SetTp(f64),
+    ClearTriggers,
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct Triggers {
     pub tp: Option<f64>,
     pub sl: Option<f64>,
+    pub trail: Option<Trail>,
+}
+
+/// Stop-loss the engine keeps at a fixed distance behind the best price
+/// since entry, only ever moving it in the position's favour.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Trail {
+    /// Distance as a leverage-adjusted ROE percentage, like `triggers()`.
+    Pct(f64),
+    /// Distance as `mult` times the market's ATR indicator on `tf`.
+    Atr {
+        atr: IndicatorKind,
+        tf: TimeFrame,
+        mult: f64,
+    },
 }
 
 impl Intent {
@@ -433,15 +493,18 @@ impl Intent {
     ) -> Self {
         let mut tp = None;
         let mut sl = None;
+        let mut trail = None;
         if let Some(triggers) = tp_sl {
             tp = triggers.tp;
             sl = triggers.sl;
+            trail = triggers.trail;
         }
         Intent::Open(Order {
             side,
             size,
             tp,
             sl,
+            trail,
             liq_side,
         })
     }
@@ -464,6 +527,26 @@ impl Intent {
         Self::new_open(side, size, LiqSide::Maker(limit_options), tp_sl)
     }
 
+    pub fn add(size: SizeSpec, liq_side: LiqSide) -> Self {
+        Intent::Add(AddOrder { size, liq_side })
+    }
+
+    pub fn add_market_order(size: SizeSpec) -> Self {
+        Self::add(size, LiqSide::Taker)
+    }
+
+    /// A limit add's unfilled part is canceled after `expire_after`.
+    pub fn add_limit_order(size: SizeSpec, limit_px: f64, expire_after: TimeDelta) -> Self {
+        let limit_options = LimitOptions {
+            limit_px,
+            timeout: Some(TimeoutInfo {
+                action: OnTimeout::Cancel,
+                duration: expire_after,
+            }),
+        };
+        Self::add(size, LiqSide::Maker(limit_options))
+    }
+
     pub fn reduce(size: SizeSpec, liq_side: LiqSide) -> Self {
         Intent::Reduce(ReduceOrder { size, liq_side })
     }
@@ -504,6 +587,10 @@ impl Intent {
                 LiqSide::Maker(opts) => opts.timeout,
                 LiqSide::Taker => None,
             },
+            Intent::Add(order) => match &order.liq_side {
+                LiqSide::Maker(opts) => opts.timeout,
+                LiqSide::Taker => None,
+            },
             Intent::Reduce(order) => match &order.liq_side {
                 LiqSide::Maker(opts) => opts.timeout,
                 LiqSide::Taker => None,
@@ -516,16 +603,24 @@ impl Intent {
         }
     }
 
+    pub fn is_trigger_update(&self) -> bool {
+        matches!(
+            self,
+            Intent::SetSl(_) | Intent::SetTp(_) | Intent::ClearTriggers
+        )
+    }
+
     pub fn is_order(&self) -> bool {
         matches!(
             self,
-            Intent::Open(_) | Intent::Reduce(_) | Intent::Flatten(_)
+            Intent::Open(_) | Intent::Add(_) | Intent::Reduce(_) | Intent::Flatten(_)
         )
     }
 
     pub fn is_market_order(&self) -> bool {
         match self {
             Intent::Open(order) => matches!(order.liq_side, LiqSide::Taker),
+            Intent::Add(order) => matches!(order.liq_side, LiqSide::Taker),
             Intent::Reduce(order) => matches!(order.liq_side, LiqSide::Taker),
             Intent::Flatten(liq_side) => matches!(liq_side, LiqSide::Taker),
             Intent::Abort => true,
@@ -1,5 +1,9 @@
+use chrono::{DateTime, Datelike, Months};
 use serde::{Deserialize, Serialize};
 
+/// The epoch is a Thursday; shifting by three days lines weeks up on Monday.
+const WEEK_SHIFT_MS: u64 = 3 * 24 * 60 * 60 * 1000;
+
 //TIME FRAME
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Hash)]
 #[serde(rename_all = "camelCase")]
@@ -42,6 +46,27 @@ impl TimeFrame {
         self.to_secs() * 1000
     }
 
+    /// Open time of the candle containing `ts`, in ms. Weeks open on Monday
+    /// 00:00 UTC and months on the 1st, like the exchanges' candles.
+    pub fn candle_open(&self, ts: u64) -> u64 {
+        match self {
+            TimeFrame::Week => {
+                let week = TimeFrame::Week.to_millis();
+                ((ts + WEEK_SHIFT_MS) / week * week).saturating_sub(WEEK_SHIFT_MS)
+            }
+            TimeFrame::Month => month_start(ts, 0),
+            tf => ts / tf.to_millis() * tf.to_millis(),
+        }
+    }
+
+    /// Open time of the candle after the one containing `ts`, in ms.
+    pub fn next_candle_open(&self, ts: u64) -> u64 {
+        match self {
+            TimeFrame::Month => month_start(ts, 1),
+            tf => tf.candle_open(ts) + tf.to_millis(),
+        }
+    }
+
     pub const fn available_tfs() -> [TimeFrame; 13] {
         use TimeFrame::*;
         [
@@ -70,6 +95,15 @@ impl TimeFrame {
     }
 }
 
+/// Midnight UTC on the 1st of the month `months_ahead` after the one holding `ts`.
+fn month_start(ts: u64, months_ahead: u32) -> u64 {
+    DateTime::from_timestamp_millis(ts as i64)
+        .and_then(|dt| dt.date_naive().with_day(1))
+        .and_then(|day| day.checked_add_months(Months::new(months_ahead)))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map_or(ts, |start| start.and_utc().timestamp_millis() as u64)
+}
+
 impl std::fmt::Display for TimeFrame {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(self.as_str())
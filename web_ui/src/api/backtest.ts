@@ -1,5 +1,10 @@
 import { API_URL } from "../consts";
-import type { BacktestRunEntry, BacktestResultDetail } from "../types";
+import type {
+    BacktestCheckpoint,
+    BacktestComparison,
+    BacktestResult,
+    BacktestRunEntry,
+} from "../types";
 
 function authHeaders(token: string | null): Record<string, string> {
     const h: Record<string, string> = {};
@@ -35,7 +40,7 @@ export async function fetchBacktestHistory(
 export async function fetchBacktestResult(
     token: string | null,
     runId: string
-): Promise<BacktestResultDetail> {
+): Promise<BacktestResult> {
     const res = await fetch(`${API_URL}/backtest/history/${runId}`, {
         headers: authHeaders(token),
     });
@@ -55,3 +60,53 @@ export async function deleteBacktestRun(
     if (!res.ok)
         throw new Error(`Failed to delete backtest run (${res.status})`);
 }
+
+export async function compareBacktestRuns(
+    token: string | null,
+    runIds: string[]
+): Promise<BacktestComparison> {
+    const res = await fetch(`${API_URL}/backtest/compare`, {
+        method: "POST",
+        headers: { ...authHeaders(token), "Content-Type": "application/json" },
+        body: JSON.stringify({ runIds }),
+    });
+    if (!res.ok)
+        throw new Error(`Failed to compare backtest runs (${res.status})`);
+    return res.json();
+}
+
+export async function cancelBacktestRun(
+    token: string | null,
+    runId: string
+): Promise<void> {
+    const res = await fetch(
+        `${API_URL}/backtest/${encodeURIComponent(runId)}/cancel`,
+        { method: "POST", headers: authHeaders(token) }
+    );
+    if (!res.ok)
+        throw new Error(`Failed to cancel backtest run (${res.status})`);
+}
+
+export async function retryBacktestRun(
+    token: string | null,
+    runId: string
+): Promise<{ runId: string; result: BacktestResult }> {
+    const res = await fetch(
+        `${API_URL}/backtest/${encodeURIComponent(runId)}/retry`,
+        { method: "POST", headers: authHeaders(token) }
+    );
+    if (!res.ok)
+        throw new Error(`Failed to retry backtest run (${res.status})`);
+    return res.json();
+}
+
+export async function fetchBacktestCheckpoints(
+    token: string | null
+): Promise<BacktestCheckpoint[]> {
+    const res = await fetch(`${API_URL}/backtest/checkpoints`, {
+        headers: authHeaders(token),
+    });
+    if (!res.ok)
+        throw new Error(`Failed to fetch backtest checkpoints (${res.status})`);
+    return res.json();
+}
@@ -6,6 +6,7 @@ import ChartContainer from "../chart/ChartContainer";
 import { isTimeframeSupported } from "../chart/dataSources";
 import { loadCandles } from "../chart/loader";
 import { API_URL } from "../consts";
+import { cancelBacktestRun } from "../api/backtest";
 import {
     DEFAULT_DATA_SOURCE,
     type DataSource,
@@ -399,7 +400,8 @@ function BacktestContent({ routeAsset }: BacktestContentProps) {
             activeRunId &&
             latestProgress &&
             latestProgress.kind !== "done" &&
-            latestProgress.kind !== "failed"
+            latestProgress.kind !== "failed" &&
+            latestProgress.kind !== "cancelled"
         ) {
             return "loading";
         }
@@ -415,6 +417,10 @@ function BacktestContent({ routeAsset }: BacktestContentProps) {
                 return `Warming engine: ${latestProgress.loaded}/${latestProgress.total}`;
             case "simulating":
                 return `Simulating: ${latestProgress.processed}/${latestProgress.total}`;
+            case "optimizing":
+                return `Optimizing: ${latestProgress.completed}/${latestProgress.total} runs`;
+            case "walkForward":
+                return `Walk-forward window ${latestProgress.window}/${latestProgress.total}`;
             case "initializing":
                 return "Initializing backtest...";
             case "finalizing":
@@ -423,6 +429,8 @@ function BacktestContent({ routeAsset }: BacktestContentProps) {
                 return "Backtest done.";
             case "failed":
                 return `Backtest failed: ${latestProgress.message}`;
+            case "cancelled":
+                return "Backtest cancelled.";
             default:
                 return null;
         }
@@ -1135,6 +1143,25 @@ function BacktestContent({ routeAsset }: BacktestContentProps) {
                                 <p className="text-app-text/90 text-center text-2xl font-bold">
                                     {progressLabel ?? "Loading backtest..."}
                                 </p>
+                                {activeRunId && (
+                                    <button
+                                        onClick={() =>
+                                            void cancelBacktestRun(
+                                                token,
+                                                activeRunId
+                                            ).catch((err) =>
+                                                setBacktestError(
+                                                    err instanceof Error
+                                                        ? err.message
+                                                        : "Failed to cancel backtest."
+                                                )
+                                            )
+                                        }
+                                        className="border-line-weak text-app-text/70 hover:bg-ink-60 mt-4 rounded border px-3 py-1 text-xs"
+                                    >
+                                        Cancel
+                                    </button>
+                                )}
                             </div>
                         )}
 
@@ -148,10 +148,73 @@ export default function BacktestResult({
                     <p className="text-app-text/50 text-xs">Max DD %</p>
                     <p>{num(result.summary.maxDrawdownPct, 2)}%</p>
                 </div>
+                <div className="border-line-subtle rounded border p-2">
+                    <p className="text-app-text/50 text-xs">Sortino</p>
+                    <p>
+                        {result.summary.sortinoRatio == null
+                            ? "—"
+                            : num(result.summary.sortinoRatio, 3)}
+                    </p>
+                </div>
+                <div className="border-line-subtle rounded border p-2">
+                    <p className="text-app-text/50 text-xs">Calmar</p>
+                    <p>
+                        {result.summary.calmarRatio == null
+                            ? "—"
+                            : num(result.summary.calmarRatio, 3)}
+                    </p>
+                </div>
+                <div className="border-line-subtle rounded border p-2">
+                    <p className="text-app-text/50 text-xs">Time in Market</p>
+                    <p>{num(result.summary.timeInMarketPct ?? 0, 2)}%</p>
+                </div>
                 <div className="border-line-subtle rounded border p-2">
                     <p className="text-app-text/50 text-xs">Candles</p>
                     <p>{result.candlesProcessed}</p>
                 </div>
+                {result.benchmark && (
+                    <>
+                        <div className="border-line-subtle rounded border p-2">
+                            <p className="text-app-text/50 text-xs">
+                                Buy &amp; Hold %
+                            </p>
+                            <p>{num(result.benchmark.returnPct, 2)}%</p>
+                        </div>
+                        <div className="border-line-subtle rounded border p-2">
+                            <p className="text-app-text/50 text-xs">
+                                Excess Return
+                            </p>
+                            <p
+                                className={
+                                    result.benchmark.excessReturnPct >= 0
+                                        ? "text-accent-success"
+                                        : "text-accent-danger-soft"
+                                }
+                            >
+                                {result.benchmark.excessReturnPct >= 0
+                                    ? "+"
+                                    : ""}
+                                {num(result.benchmark.excessReturnPct, 2)}%
+                            </p>
+                        </div>
+                        <div className="border-line-subtle rounded border p-2">
+                            <p className="text-app-text/50 text-xs">Alpha %</p>
+                            <p>
+                                {result.benchmark.alphaPct == null
+                                    ? "—"
+                                    : num(result.benchmark.alphaPct, 2)}
+                            </p>
+                        </div>
+                        <div className="border-line-subtle rounded border p-2">
+                            <p className="text-app-text/50 text-xs">Beta</p>
+                            <p>
+                                {result.benchmark.beta == null
+                                    ? "—"
+                                    : num(result.benchmark.beta, 3)}
+                            </p>
+                        </div>
+                    </>
+                )}
             </div>
 
             <div className="mt-4 min-h-0 flex-1 overflow-auto">
@@ -229,6 +292,31 @@ export default function BacktestResult({
                     </tbody>
                 </table>
             </div>
+
+            {result.scriptLogs?.length > 0 && (
+                <div className="border-line-subtle mt-4 max-h-48 overflow-auto rounded border p-2 font-mono text-xs">
+                    {result.scriptLogs.map((log, idx) => (
+                        <p
+                            key={`${result.runId}-log-${idx}`}
+                            className={
+                                log.kind === "error"
+                                    ? "text-accent-danger-soft"
+                                    : "text-app-text/70"
+                            }
+                        >
+                            <span className="text-app-text/45">
+                                {formatUTC(log.ts)}
+                            </span>{" "}
+                            {log.msg}
+                        </p>
+                    ))}
+                    {result.scriptLogsDropped > 0 && (
+                        <p className="text-app-text/45">
+                            … {result.scriptLogsDropped} more not kept
+                        </p>
+                    )}
+                </div>
+            )}
         </div>
     );
 }
@@ -68,7 +68,7 @@ export default function BacktestRunDetail() {
 
         fetchBacktestResult(token, runId)
             .then((data) => {
-                if (!cancelled) setDetail(data);
+                if (!cancelled) setDetail(resultToDetail(data));
             })
             .catch((e) => {
                 if (!cancelled)
@@ -391,7 +391,7 @@ export default function Docs() {
                                 {
                                     title: "on_open",
                                     body: "Runs when the market already has an open position.",
-                                    code: "flatten or reduce",
+                                    code: "flatten, reduce, add, set_sl, set_tp",
                                 },
                                 {
                                     title: "on_busy",
@@ -441,6 +441,11 @@ open_market(LONG, margin_pct(50.0));`}</CodeBlock>
                                     "Map",
                                     "Raw indicator map. Prefer extract() instead of accessing this directly.",
                                 ],
+                                [
+                                    "indicator_history",
+                                    "Map",
+                                    "Past closed indicator values. Prefer prev() and history() instead of accessing this directly.",
+                                ],
                                 [
                                     "state variables",
                                     "declared names",
@@ -656,6 +661,34 @@ if !market_rsi_on_close || market_rsi_ts == last_market_ts {
 }
 
 last_market_ts = market_rsi_ts;`}</CodeBlock>
+                        <p className="text-app-text/65 text-sm leading-6">
+                            `prev(key, n)` returns the value an indicator had `n`
+                            closes before its current one, or `()` until enough
+                            closes were seen. `history(key, n)` returns up to `n`
+                            of them, newest first. The lookback must be an
+                            integer literal; it sets how many closes are kept.
+                        </p>
+                        <CodeBlock>{`let rsi = extract("self_rsi_14_15m");
+let before = prev("self_rsi_14_15m", 1);
+if before == () { return; }
+
+// RSI crossed above 30
+if as_f64(before.value) < 30.0 && rsi_value >= 30.0 {
+    return open_market(LONG, margin_pct(20.0));
+}`}</CodeBlock>
+                        <p className="text-app-text/65 text-sm leading-6">
+                            `candles(asset, tf, n)` returns up to `n` recent
+                            closed candles, newest first, for any asset
+                            and timeframe with a configured indicator. Use
+                            `"self"` for the traded market.
+                        </p>
+                        <CodeBlock>{`let bars = candles("self", MIN15, 2);
+if bars.len() < 2 { return; }
+
+// Breakout above the previous candle's high
+if bars[0].close > bars[1].high {
+    return open_market(LONG, margin_pct(20.0));
+}`}</CodeBlock>
                     </DocsSection>
 
                     <DocsSection section={sectionById["state"]}>
@@ -801,6 +834,31 @@ reduce_limit(
                                     "reduce_limit(size, price, timeout)",
                                     "Partial limit close with timeout policy.",
                                 ],
+                                [
+                                    "add_market",
+                                    "add_market(size)",
+                                    "Add to the open position at market.",
+                                ],
+                                [
+                                    "add_limit",
+                                    "add_limit(size, price, timedelta)",
+                                    "Limit add that is canceled if unfilled after the timedelta.",
+                                ],
+                                [
+                                    "set_sl",
+                                    "set_sl(price)",
+                                    "Replace the stop-loss with one at an absolute price.",
+                                ],
+                                [
+                                    "set_tp",
+                                    "set_tp(price)",
+                                    "Replace the take-profit with one at an absolute price.",
+                                ],
+                                [
+                                    "clear_triggers",
+                                    "clear_triggers()",
+                                    "Cancel both the take-profit and the stop-loss.",
+                                ],
                             ]}
                         />
                     </DocsSection>
@@ -813,6 +871,11 @@ reduce_limit(
                                     body: "Attach take-profit and stop-loss to open orders with percentage values adjusted by leverage.",
                                     code: "triggers(5.0, 3.0)",
                                 },
+                                {
+                                    title: "Trailing stop",
+                                    body: "The engine keeps the stop a fixed distance behind the best price since entry and replaces the resting SL as it moves. Use a percentage or a multiple of the market's ATR indicator.",
+                                    code: "trailing(trail_pct(3.0))",
+                                },
                                 {
                                     title: "FORCE",
                                     body: "On timeout, cancel the target limit order and submit the market equivalent. Flatten force-closes at market.",
@@ -828,6 +891,8 @@ reduce_limit(
                         <CodeBlock>{`triggers(5.0, 3.0)   // TP and SL
 tp_only(5.0)          // TP only
 sl_only(3.0)          // SL only
+trailing(trail_pct(3.0))             // trailing stop
+trailing(5.0, trail_atr(MIN15, 14, 2.0))  // TP + ATR trailing stop
 
 timeout(FORCE, timedelta(MIN15, 1))
 timeout(CANCEL, timedelta(MIN15, 1))`}</CodeBlock>
@@ -4,6 +4,8 @@ import type {
     BackendMarketInfo,
     MarketInfo,
     Message,
+    StepCommand,
+    StepEvent,
     assetMeta,
 } from "../types";
 import type { Strategy } from "../strats";
@@ -50,6 +52,7 @@ export const WebSocketProvider: React.FC<{ children: React.ReactNode }> = ({
     const [backtestRuns, setBacktestRuns] = useState<
         Record<string, BacktestRunState>
     >({});
+    const [backtestStep, setBacktestStep] = useState<StepEvent | null>(null);
     const [totalMargin, setTotalMargin] = useState(0);
     const [errorMsg, setErrorMsg] = useState<string | null>(null);
     const [isOffline, setIsOffline] = useState(false);
@@ -324,6 +327,11 @@ export const WebSocketProvider: React.FC<{ children: React.ReactNode }> = ({
                 return;
             }
 
+            if ("backtestStep" in payload) {
+                setBacktestStep(payload.backtestStep);
+                return;
+            }
+
             if ("backtestResult" in payload) {
                 const { runId, result } = payload.backtestResult;
                 setBacktestRuns((prev) => {
@@ -562,6 +570,13 @@ export const WebSocketProvider: React.FC<{ children: React.ReactNode }> = ({
         []
     );
 
+    const sendBacktestStep = useCallback((command: StepCommand) => {
+        const ws = wsRef.current;
+        if (!ws || ws.readyState !== WebSocket.OPEN) return false;
+        ws.send(JSON.stringify({ backtestStep: command }));
+        return true;
+    }, []);
+
     const dismissError = useCallback(
         () => setErrorWithTimeout(null),
         [setErrorWithTimeout]
@@ -572,6 +587,7 @@ export const WebSocketProvider: React.FC<{ children: React.ReactNode }> = ({
         universe,
         cachedMarkets,
         backtestRuns,
+        backtestStep,
         strategies,
         totalMargin,
         isOffline,
@@ -581,6 +597,7 @@ export const WebSocketProvider: React.FC<{ children: React.ReactNode }> = ({
         setNeedsBuilderApproval,
         errorMsg,
         sendCommand,
+        sendBacktestStep,
         dismissError,
         cacheMarket,
         deleteCachedMarket,
@@ -1,5 +1,11 @@
 import { createContext, useContext } from "react";
-import type { BacktestRunState, MarketInfo, assetMeta } from "../types";
+import type {
+    BacktestRunState,
+    MarketInfo,
+    StepCommand,
+    StepEvent,
+    assetMeta,
+} from "../types";
 import type { Strategy } from "../strats";
 
 export interface WebSocketContextValue {
@@ -7,6 +13,7 @@ export interface WebSocketContextValue {
     universe: assetMeta[];
     cachedMarkets: string[];
     backtestRuns: Record<string, BacktestRunState>;
+    backtestStep: StepEvent | null;
     strategies: Strategy[];
     totalMargin: number;
     errorMsg: string | null;
@@ -16,6 +23,7 @@ export interface WebSocketContextValue {
     needsBuilderApproval: boolean;
     setNeedsBuilderApproval: (v: boolean) => void;
     sendCommand: (body: unknown) => Promise<Response>;
+    sendBacktestStep: (command: StepCommand) => boolean;
     dismissError: () => void;
     cacheMarket: (asset: string) => void;
     deleteCachedMarket: (asset: string) => void;
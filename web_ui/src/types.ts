@@ -433,12 +433,15 @@ export type BacktestProgress =
     | { kind: "loadingCandles"; loaded: number; total: number }
     | { kind: "warmingEngine"; loaded: number; total: number }
     | { kind: "simulating"; processed: number; total: number }
+    | { kind: "optimizing"; completed: number; total: number }
+    | { kind: "walkForward"; window: number; total: number }
     | { kind: "finalizing" }
     | { kind: "done" }
-    | { kind: "failed"; message: string };
+    | { kind: "failed"; message: string }
+    | { kind: "cancelled"; processed: number };
 
 export interface BacktestSource {
-    exchange: "binance" | "bybit" | "htx";
+    exchange: "binance" | "bybit" | "htx" | "hyperliquid" | "local" | "synthetic";
     market: "spot" | "futures";
     quoteAsset: "USDT" | "USDC" | string;
 }
@@ -458,6 +461,7 @@ export interface BacktestConfig {
     snapshotIntervalCandles: number;
     maxEquityPoints?: number;
     maxSnapshots?: number;
+    maxScriptLogs?: number;
 }
 
 export interface CandlePoint {
@@ -479,11 +483,13 @@ export interface EquityPoint {
 
 export type SnapshotReason =
     | "open"
+    | "add"
     | "reduce"
     | "flatten"
     | "close"
     | "forceClose"
     | "cancelResting"
+    | "triggerUpdate"
     | "fill"
     | "interval";
 
@@ -518,6 +524,53 @@ export interface BacktestSummary {
     profitFactor: number | null;
     expectancy: number;
     sharpeRatio?: number | null;
+    monteCarlo?: MonteCarloSummary;
+    sortinoRatio?: number | null;
+    calmarRatio?: number | null;
+    timeInMarketPct?: number;
+    avgTradeDurationMs?: number;
+    medianTradeDurationMs?: number;
+    longestWinStreak?: number;
+    longestLossStreak?: number;
+    avgMaePct?: number;
+    avgMfePct?: number;
+    tradeExcursions?: TradeExcursion[];
+    monthlyReturns?: MonthlyReturn[];
+}
+
+export interface TradeExcursion {
+    maePct: number;
+    mfePct: number;
+}
+
+export interface MonthlyReturn {
+    year: number;
+    month: number;
+    startEquity: number;
+    endEquity: number;
+    pnl: number;
+    returnPct: number;
+}
+
+export interface Percentiles {
+    p5: number;
+    p25: number;
+    p50: number;
+    p75: number;
+    p95: number;
+    mean: number;
+}
+
+export interface MonteCarloSummary {
+    iterations: number;
+    mode: "shuffle" | "bootstrap";
+    seed: number | null;
+    margin: number;
+    ruinEquity: number;
+    finalEquity: Percentiles;
+    maxDrawdownPct: Percentiles;
+    worstLosingStreak: Percentiles;
+    riskOfRuinPct: number;
 }
 
 export interface BacktestResult {
@@ -531,8 +584,134 @@ export interface BacktestResult {
     trades: TradeInfo[];
     equityCurve: EquityPoint[];
     snapshots: PositionSnapshot[];
+    benchmark?: BenchmarkSummary;
+    scriptLogs: ScriptLogEntry[];
+    scriptLogsDropped: number;
+}
+
+/** Strategy `print` or runtime error, stamped with the execution candle open time */
+export interface ScriptLogEntry {
+    ts: number;
+    kind: "print" | "error";
+    msg: string;
+}
+
+export interface BenchmarkSummary {
+    initialEquity: number;
+    finalEquity: number;
+    netPnl: number;
+    returnPct: number;
+    maxDrawdownPct: number;
+    liquidated: boolean;
+    excessReturnPct: number;
+    alphaPct: number | null;
+    beta: number | null;
+    equityCurve: EquityPoint[];
+}
+
+export interface MetricDelta {
+    metric: string;
+    baseline: number | null;
+    value: number | null;
+    delta: number | null;
 }
 
+/** Diff of stored runs against the first (baseline) run */
+export interface BacktestComparison {
+    baselineRunId: string;
+    runs: { runId: string; config: BacktestConfig; summary: BacktestSummary }[];
+    summaryDiffs: { runId: string; metrics: MetricDelta[] }[];
+    /** `series[i][j]` is run i's equity at `ts[j]`, null before its first point */
+    equity: { ts: number[]; series: (number | null)[][] };
+    uniqueTrades: { runId: string; trades: TradeInfo[] }[];
+}
+
+/** Progress of a cancelled or interrupted run; retryable (from the start) until it completes */
+export interface BacktestCheckpoint {
+    runId: string;
+    request: { runId?: string; config: BacktestConfig; warmupCandles: number };
+    status: "running" | "cancelled" | "interrupted";
+    processed: number;
+    total: number;
+    startedAt: number;
+    updatedAt: number;
+}
+
+export type IntentKind =
+    | "open"
+    | "add"
+    | "reduce"
+    | "flatten"
+    | "arm"
+    | "disarm"
+    | "abort"
+    | "setSl"
+    | "setTp"
+    | "clearTriggers";
+
+export type StepBreakpoint =
+    | { kind: "intent"; intent: IntentKind }
+    | { kind: "anyIntent" }
+    | { kind: "tradeClosed" }
+    | { kind: "engineState"; state: EngineView };
+
+/** Sent over the websocket as `{ backtestStep: StepCommand }` */
+export type StepCommand =
+    | {
+          kind: "start";
+          request: {
+              runId?: string;
+              config: BacktestConfig;
+              warmupCandles: number;
+          };
+          breakpoints?: StepBreakpoint[];
+      }
+    | { kind: "step"; bars: number }
+    | { kind: "continue" }
+    | { kind: "pause" }
+    | { kind: "setBreakpoints"; breakpoints: StepBreakpoint[] }
+    | { kind: "close" };
+
+export interface StepIntent {
+    ts: number;
+    asset: string;
+    tf: TimeFrame;
+    kind: IntentKind;
+    detail: string;
+}
+
+export type StopReason =
+    | { kind: "bars" }
+    | { kind: "breakpoint"; index: number }
+    | { kind: "paused" }
+    | { kind: "end" }
+    | { kind: "liquidated" };
+
+export interface StepFrame {
+    bar: number;
+    candle: CandlePoint;
+    engineState: EngineView;
+    indicators: indicatorData[];
+    state: Record<string, unknown>;
+    intents: StepIntent[];
+    logs: ScriptLogEntry[];
+    position: OpenPositionLocal | null;
+    balance: number;
+    equity: number;
+    trades: number;
+}
+
+export type StepEvent =
+    | { kind: "started"; sessionId: string; total: number }
+    | {
+          kind: "stopped";
+          sessionId: string;
+          reason: StopReason;
+          frame: StepFrame | null;
+      }
+    | { kind: "finished"; sessionId: string; result: BacktestResult }
+    | { kind: "error"; sessionId: string | null; message: string };
+
 /** Lightweight row from `backtest_runs` table — used for history list */
 export interface BacktestRunEntry {
     id: string;
@@ -559,7 +738,7 @@ export interface BacktestRunEntry {
     createdAt: string;
 }
 
-/** Full result from `backtest_results` table — fetched on click */
+/** Flattened view of a `BacktestResult` for the run detail page */
 export interface BacktestResultDetail {
     id: string;
     runId: string;
@@ -610,6 +789,7 @@ export type Message =
     | { userError: string }
     | { backtestProgress: BacktestProgressUpdate }
     | { backtestResult: BacktestResultUpdate }
+    | { backtestStep: StepEvent }
     | { loadSession: BackendLoadSessionPayload }
     | { needsApiKey: boolean }
     | { needsBuilderApproval: boolean }